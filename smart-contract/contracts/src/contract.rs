@@ -1,10 +1,60 @@
-use soroban_sdk::{contract, contractimpl, Address, Env, String, Vec, vec};
-use crate::storage::DataKey;
-use crate::types::{Product, ProductStats};
+use soroban_sdk::{contract, contractimpl, symbol_short, token, Address, Bytes, BytesN, Env, IntoVal, Map, String, Symbol, Val, Vec};
+use crate::storage::{self, AdminKey, AuthKey, DataKey, FeeKey, IndexKey, LifecycleKey, StatsKey, TrackingKey, TransferKey};
+use crate::validation;
+use crate::events;
+use crate::types::{ActivityEntry, ActivityItem, AuditEntry, BulkTransferResult, Certification, Config, ContractInfo, Checkpoint, CheckpointCorrection, CheckpointKind, CheckpointRef, ChannelStats, CursorPage, CustodyEntry, CustodyRole, DailyStats, EtaRevision, IncomingTransfer, MediaRef, OriginStats, OwnerStats, PendingTransfer, Product, ProductNote, ProductPage, ProductStats, ProductStatus, ProofOfDelivery, ProductTemplate, Quantity, RegistrationFee, RepairReport, Role, ScheduledTransfer, SensorReading, Sponsorship, TempReading, TempStats, TransferClaim, TransferRecord, TransferWindow};
 use crate::error::Error;
- use soroban_sdk::{contract, contractimpl, Address, BytesN, Env, Map, String, Symbol, Vec};
 
-use crate::{storage, validation, Error, Origin, Product, TrackingEvent};
+const MAX_SERIAL_LEN: u32 = 64;
+const MAX_TAGS_PER_PRODUCT: u32 = 10;
+const MAX_CERTIFICATIONS_PER_PRODUCT: u32 = 50;
+const MAX_MEDIA_PER_PRODUCT: u32 = 16;
+const MAX_NOTE_LEN: u32 = 256;
+const MAX_NOTES_PER_PRODUCT: u32 = 200;
+const MAX_MEMO_LEN: u32 = 128;
+const MIN_FORCED_REASON_LEN: u32 = 8;
+const MAX_ORIGIN_CHAIN_LEN: u32 = 8;
+const MAX_SENSOR_BATCH: u32 = 100;
+const MAX_GET_BATCH: u32 = 100;
+const MAX_CHANNELS_PER_PRODUCT: u32 = 8;
+const MAX_ROUTE_WAYPOINTS: u32 = 20;
+const MAX_ETA_HISTORY: u32 = 10;
+const DEFAULT_MAX_TRANSFER_BATCH: u32 = 50;
+const DEFAULT_MAX_ACTORS: u32 = 32;
+const DEFAULT_MAX_PAGE_LIMIT: u32 = 100;
+const MAX_METADATA_LEN_CEILING: u32 = 8192;
+const MAX_CO_OWNERS: u32 = 4;
+const MAX_ACTIVITY_LOG: u32 = 500;
+const TOP_ORIGINS_SIZE: u32 = 16;
+const DEFAULT_RECENT_ACTIVITY: u32 = 100;
+const MAX_RECENT_ACTIVITY: u32 = 1000;
+/// Slots a filtered listing reads before returning a short page.
+const MAX_SCAN_SLOTS: u64 = 1000;
+const SECONDS_PER_DAY: u64 = 86_400;
+/// Widest span of registration days one time-range query may walk.
+const MAX_TIME_RANGE_DAYS: u64 = 366;
+/// Most days one `get_daily_activity` call may return.
+const MAX_DAILY_RANGE_DAYS: u64 = 92;
+/// Data layout version of this build. Bump it alongside a new step in
+/// `migrate` whenever stored data needs rewriting after an upgrade.
+const CONTRACT_VERSION: u32 = 1;
+/// Release version reported by `get_contract_info`.
+const CONTRACT_SEMVER: &str = "1.0.0";
+const MAX_CONTRACT_NAME_LEN: u32 = 64;
+const ACTION_TRANSFER: &str = "TRANSFER";
+const ACTION_CHECKPOINT: &str = "CHECKPOINT";
+const ACTION_INSPECT: &str = "INSPECT";
+const ACTION_UPDATE_META: &str = "UPDATE_META";
+
+/// Alert bits reported by `get_alerts`. A product is listed by
+/// `get_products_with_alerts` while any of them is raised.
+pub const ALERT_EXCURSION: u32 = 1;
+pub const ALERT_ROUTE_DEVIATION: u32 = 1 << 1;
+pub const ALERT_EXCEPTION: u32 = 1 << 2;
+pub const ALERT_DISPUTE: u32 = 1 << 3;
+/// Raised when a checkpoint or ETA update lands after the delivery deadline;
+/// cleared on delivery or when the owner sets a new deadline.
+pub const ALERT_OVERDUE: u32 = 1 << 4;
 
 #[contract]
 pub struct ChainLogisticsContract;
@@ -19,61 +69,90 @@ impl ChainLogisticsContract {
         origin: String, 
         metadata: String
     ) -> Result<u64, Error> {
-        owner.require_auth();
-
-        // increments product count
-        let mut total_products: u64 = env.storage().instance().get(&DataKey::TotalProducts).unwrap_or(0);
-        total_products += 1;
-        
-        let product = Product {
-            id: total_products,
-            owner: owner.clone(),
-            origin: origin.clone(),
-            active: true,
-            metadata,
-            created_at: env.ledger().timestamp(),
-        };
-
-        // 1. Store Product
-        env.storage().persistent().set(&DataKey::Product(total_products), &product);
-        
-        // 2. Global Index (Index -> ID)
-        // Since ID is sequential and starts at 1, we can just use ID as the index for "All Products" 
-        // if we assume we iterate by ID. 
-        // But if we want to support deleting or non-sequential IDs later, an explicit index is better.
-        // For now, let's map Index (1-based) to ProductID.
-        env.storage().persistent().set(&DataKey::AllProductsIndex(total_products), &total_products);
-
-        // 3. Owner Index
-        let mut owner_count: u64 = env.storage().persistent().get(&DataKey::OwnerProductCount(owner.clone())).unwrap_or(0);
-        owner_count += 1;
-        env.storage().persistent().set(&DataKey::OwnerProductIndex(owner.clone(), owner_count), &total_products);
-        env.storage().persistent().set(&DataKey::OwnerProductCount(owner.clone()), &owner_count);
-
-        // 4. Origin Index
-        let mut origin_count: u64 = env.storage().persistent().get(&DataKey::OriginProductCount(origin.clone())).unwrap_or(0);
-        origin_count += 1;
-        env.storage().persistent().set(&DataKey::OriginProductIndex(origin.clone(), origin_count), &total_products);
-        env.storage().persistent().set(&DataKey::OriginProductCount(origin.clone()), &origin_count);
-        
-        // Update global counters
-        env.storage().instance().set(&DataKey::TotalProducts, &total_products);
-        
-        // Update active count
-        let mut active_products: u64 = env.storage().instance().get(&DataKey::ActiveProducts).unwrap_or(0);
-        active_products += 1;
-        env.storage().instance().set(&DataKey::ActiveProducts, &active_products);
+        count_mutation(&env);
+        register(&env, owner, origin, metadata, None)
+    }
 
-        Ok(total_products)
+    /// Register a new product under the manufacturer's own serial number.
+    /// Serials are unique across the registry until the product is burned.
+    pub fn register_product_with_serial(
+        env: Env,
+        owner: Address,
+        origin: String,
+        metadata: String,
+        serial: String,
+    ) -> Result<u64, Error> {
+        count_mutation(&env);
+        register(&env, owner, origin, metadata, Some(serial))
     }
 
     /// Get a product by ID
     pub fn get_product(env: Env, id: u64) -> Option<Product> {
-        env.storage().persistent().get(&DataKey::Product(id))
+        let product = storage::get_product(&env, id)?;
+        touch_product(&env, &product);
+        Some(product)
+    }
+
+    /// Fetch up to `MAX_GET_BATCH` products in one call. The result lines up
+    /// with `ids`, holding None wherever no product exists.
+    pub fn get_products(env: Env, ids: Vec<u64>) -> Result<Vec<Option<Product>>, Error> {
+        if ids.len() > MAX_GET_BATCH {
+            return Err(Error::BatchTooLarge);
+        }
+        let mut products = Vec::new(&env);
+        for id in ids.iter() {
+            let product = storage::get_product(&env, id);
+            if let Some(product) = &product {
+                touch_product(&env, product);
+            }
+            products.push_back(product);
+        }
+        Ok(products)
+    }
+
+    /// Whether a product with this id is stored, without reading it
+    pub fn product_exists(env: Env, id: u64) -> bool {
+        env.storage().persistent().has(&DataKey::Product(id))
+    }
+
+    /// Slots in the owner's index, the `total` of `get_products_by_owner_paged`.
+    /// Slots vacated by transfers still count.
+    pub fn get_owner_product_count(env: Env, owner: Address) -> u64 {
+        env.storage().persistent().get(&DataKey::OwnerProductCount(owner)).unwrap_or(0)
+    }
+
+    /// Slots in the origin's index, the `total` of `get_products_by_origin_paged`.
+    /// Slots vacated by origin changes and burns still count.
+    pub fn get_origin_product_count(env: Env, origin: String) -> u64 {
+        env.storage().persistent().get(&DataKey::OriginProductCount(origin)).unwrap_or(0)
+    }
+
+    /// `get_product` that says why a product cannot be read: never
+    /// registered, burned, or archived. (The client already generates
+    /// `try_get_product` for `get_product`, hence the name.)
+    pub fn get_product_checked(env: Env, id: u64) -> Result<Product, Error> {
+        let product = read_product(&env, id)?;
+        touch_product(&env, &product);
+        Ok(product)
+    }
+
+    /// Look up a product by its manufacturer serial number
+    pub fn get_product_by_serial(env: Env, serial: String) -> Option<Product> {
+        let product_id: u64 = env.storage().persistent().get(&DataKey::SerialIndex(serial))?;
+        let product = storage::get_product(&env, product_id)?;
+        touch_product(&env, &product);
+        Some(product)
     }
 
-    /// Get all products with pagination
+    /// Get the manufacturer serial number a product was registered with
+    pub fn get_product_serial(env: Env, product_id: u64) -> Option<String> {
+        env.storage().persistent().get(&DataKey::ProductSerial(product_id))
+    }
+
+    /// Get all products with pagination. `limit` is capped by the config's
+    /// `max_page_limit`, and a `start` past the end returns an empty page.
     pub fn get_all_products(env: Env, start: u64, limit: u64) -> Vec<Product> {
+        let limit = page_limit(&env, limit);
         let total = env.storage().instance().get(&DataKey::TotalProducts).unwrap_or(0);
         let mut products = Vec::new(&env);
         
@@ -84,8 +163,8 @@ impl ChainLogisticsContract {
         
         // If start=0, limit=10. We want indices 1, 2, ..., 10.
         
-        let start_index = start + 1;
-        let end_index = start + limit + 1; // exclusive in loop, so effectively start+1 to start+limit
+        let start_index = start.saturating_add(1);
+        let end_index = start.saturating_add(limit).saturating_add(1); // exclusive in loop, so effectively start+1 to start+limit
 
         for i in start_index..end_index {
             if i > total {
@@ -94,7 +173,8 @@ impl ChainLogisticsContract {
             // In our simple case, Index i maps to Product ID i. 
             // access key: AllProductsIndex(i)
             if let Some(product_id) = env.storage().persistent().get::<DataKey, u64>(&DataKey::AllProductsIndex(i)) {
-                 if let Some(product) = env.storage().persistent().get::<DataKey, Product>(&DataKey::Product(product_id)) {
+                 if let Some(product) = storage::get_product(&env, product_id) {
+                    touch_product(&env, &product);
                     products.push_back(product);
                  }
             }
@@ -103,41 +183,226 @@ impl ChainLogisticsContract {
         products
     }
 
-    /// Get products by owner with pagination
+    /// Get products by owner with pagination.
+    /// Slots vacated by transfers are skipped, so a page may hold fewer than
+    /// `limit` items. `start` counts slots, not items returned; clients that
+    /// advance it by page length skip products once slots are vacated, and
+    /// should use `get_products_by_owner_cursor` instead.
     pub fn get_products_by_owner(env: Env, owner: Address, start: u64, limit: u64) -> Vec<Product> {
+        let limit = page_limit(&env, limit);
         let count: u64 = env.storage().persistent().get(&DataKey::OwnerProductCount(owner.clone())).unwrap_or(0);
         let mut products = Vec::new(&env);
         
-        let start_index = start + 1;
-        let end_index = start + limit + 1;
+        let start_index = start.saturating_add(1);
+        let end_index = start.saturating_add(limit).saturating_add(1);
 
         for i in start_index..end_index {
             if i > count {
                 break;
             }
             if let Some(product_id) = env.storage().persistent().get::<DataKey, u64>(&DataKey::OwnerProductIndex(owner.clone(), i)) {
-                 if let Some(product) = env.storage().persistent().get::<DataKey, Product>(&DataKey::Product(product_id)) {
-                    products.push_back(product);
+                 if let Some(product) = storage::get_product(&env, product_id) {
+                    // Slots written before transfers tombstoned them may be stale
+                    if product.owner == owner {
+                        touch_product(&env, &product);
+                        products.push_back(product);
+                    }
                  }
             }
         }
         products
     }
 
-    /// Get products by origin with pagination
+    /// Keep a product's record, index slots and grants from being archived
+    /// for another `extend_to` ledgers. Anyone may pay for this.
+    pub fn extend_product_ttl(env: Env, product_id: u64, extend_to: u32) -> Result<(), Error> {
+        if extend_to == 0 || extend_to > env.storage().max_ttl() {
+            return Err(Error::InvalidInput);
+        }
+        let product = read_product(&env, product_id)?;
+        storage::extend_product_ttl(&env, &product, extend_to);
+        env.storage().instance().extend_ttl(extend_to, extend_to);
+        Ok(())
+    }
+
+    /// Extend a page of an owner's products to the network's maximum TTL.
+    /// Returns how many products were extended.
+    pub fn extend_owner_products_ttl(env: Env, owner: Address, start: u64, limit: u64) -> u32 {
+        let extend_to = env.storage().max_ttl();
+        let limit = page_limit(&env, limit);
+        let count: u64 = env.storage().persistent().get(&DataKey::OwnerProductCount(owner.clone())).unwrap_or(0);
+        storage::extend_if_present(&env, &DataKey::OwnerProductCount(owner.clone()), extend_to);
+        let mut extended = 0u32;
+        let end_index = start.saturating_add(limit).min(count);
+        for i in start.saturating_add(1)..=end_index {
+            if let Some(product_id) = env.storage().persistent().get::<DataKey, u64>(&DataKey::OwnerProductIndex(owner.clone(), i)) {
+                if let Some(product) = storage::get_product(&env, product_id) {
+                    if product.owner == owner {
+                        storage::extend_product_ttl(&env, &product, extend_to);
+                        extended += 1;
+                    }
+                }
+            }
+        }
+        env.storage().instance().extend_ttl(extend_to, extend_to);
+        extended
+    }
+
+    /// `get_all_products` that can leave out inactive products. With
+    /// `only_active`, skipped products do not count toward `limit`, so a page
+    /// may read further than `start + limit` slots; `start` is still a slot
+    /// offset, so continue from the id of the last product returned.
+    pub fn get_all_products_v2(env: Env, start: u64, limit: u64, only_active: bool) -> Vec<Product> {
+        let limit = page_limit(&env, limit);
+        let total: u64 = env.storage().instance().get(&DataKey::TotalProducts).unwrap_or(0);
+        matching_products_from_index(&env, total, start, limit, DataKey::AllProductsIndex, |p| !only_active || p.active).0
+    }
+
+    /// `get_products_by_owner` that can leave out inactive products. With
+    /// `only_active` a page may read past `start + limit` slots; continuing
+    /// at `start + limit` never skips a product but may repeat some.
+    pub fn get_products_by_owner_v2(env: Env, owner: Address, start: u64, limit: u64, only_active: bool) -> Vec<Product> {
+        let limit = page_limit(&env, limit);
+        let count: u64 = env.storage().persistent().get(&DataKey::OwnerProductCount(owner.clone())).unwrap_or(0);
+        matching_products_from_index(
+            &env,
+            count,
+            start,
+            limit,
+            |i| DataKey::OwnerProductIndex(owner.clone(), i),
+            |p| p.owner == owner && (!only_active || p.active),
+        )
+        .0
+    }
+
+    /// `get_products_by_origin` that can leave out inactive products, with
+    /// the same paging caveat as `get_products_by_owner_v2`.
+    pub fn get_products_by_origin_v2(env: Env, origin: String, start: u64, limit: u64, only_active: bool) -> Vec<Product> {
+        let limit = page_limit(&env, limit);
+        let count: u64 = env.storage().persistent().get(&DataKey::OriginProductCount(origin.clone())).unwrap_or(0);
+        matching_products_from_index(
+            &env,
+            count,
+            start,
+            limit,
+            |i| DataKey::OriginProductIndex(origin.clone(), i),
+            |p| !only_active || p.active,
+        )
+        .0
+    }
+
+    /// `get_all_products` with the product count and the next page's start,
+    /// for one round trip per page
+    pub fn get_all_products_paged(env: Env, start: u64, limit: u64) -> ProductPage {
+        let limit = page_limit(&env, limit);
+        let total: u64 = env.storage().instance().get(&DataKey::TotalProducts).unwrap_or(0);
+        product_page(&env, total, start, limit, DataKey::AllProductsIndex, |_| true)
+    }
+
+    /// Paged `get_products_by_owner`. Slots vacated by transfers are skipped
+    /// and still count toward `total`.
+    pub fn get_products_by_owner_paged(env: Env, owner: Address, start: u64, limit: u64) -> ProductPage {
+        let limit = page_limit(&env, limit);
+        let count: u64 = env.storage().persistent().get(&DataKey::OwnerProductCount(owner.clone())).unwrap_or(0);
+        product_page(&env, count, start, limit, |i| DataKey::OwnerProductIndex(owner.clone(), i), |p| p.owner == owner)
+    }
+
+    /// Paged `get_products_by_origin`, with the same caveat about vacated
+    /// slots as `get_products_by_owner_paged`.
+    pub fn get_products_by_origin_paged(env: Env, origin: String, start: u64, limit: u64) -> ProductPage {
+        let limit = page_limit(&env, limit);
+        let count: u64 = env.storage().persistent().get(&DataKey::OriginProductCount(origin.clone())).unwrap_or(0);
+        product_page(&env, count, start, limit, |i| DataKey::OriginProductIndex(origin.clone(), i), |_| true)
+    }
+
+    /// `get_all_products` resuming after the slot `cursor` (0 for the first
+    /// page). Slots of burned products are passed over without disturbing
+    /// later pages.
+    pub fn get_all_products_cursor(env: Env, cursor: u64, limit: u64) -> CursorPage {
+        let limit = page_limit(&env, limit);
+        let total: u64 = env.storage().instance().get(&DataKey::TotalProducts).unwrap_or(0);
+        cursor_page(&env, total, cursor, limit, DataKey::AllProductsIndex, |_| true)
+    }
+
+    /// Cursor form of `get_products_by_owner`, stable across transfers and
+    /// burns between page fetches.
+    pub fn get_products_by_owner_cursor(env: Env, owner: Address, cursor: u64, limit: u64) -> CursorPage {
+        let limit = page_limit(&env, limit);
+        let count: u64 = env.storage().persistent().get(&DataKey::OwnerProductCount(owner.clone())).unwrap_or(0);
+        cursor_page(&env, count, cursor, limit, |i| DataKey::OwnerProductIndex(owner.clone(), i), |p| p.owner == owner)
+    }
+
+    /// Cursor form of `get_products_by_origin`, stable across origin changes
+    /// and burns between page fetches.
+    pub fn get_products_by_origin_cursor(env: Env, origin: String, cursor: u64, limit: u64) -> CursorPage {
+        let limit = page_limit(&env, limit);
+        let count: u64 = env.storage().persistent().get(&DataKey::OriginProductCount(origin.clone())).unwrap_or(0);
+        cursor_page(&env, count, cursor, limit, |i| DataKey::OriginProductIndex(origin.clone(), i), |_| true)
+    }
+
+    /// Ids of `get_all_products`, read from the index alone so clients can
+    /// hydrate products lazily
+    pub fn get_product_ids(env: Env, start: u64, limit: u64) -> Vec<u64> {
+        let limit = page_limit(&env, limit);
+        let total: u64 = env.storage().instance().get(&DataKey::TotalProducts).unwrap_or(0);
+        ids_from_index(&env, total, start, limit, DataKey::AllProductsIndex)
+    }
+
+    pub fn get_product_ids_by_owner(env: Env, owner: Address, start: u64, limit: u64) -> Vec<u64> {
+        let limit = page_limit(&env, limit);
+        let count: u64 = env.storage().persistent().get(&DataKey::OwnerProductCount(owner.clone())).unwrap_or(0);
+        ids_from_index(&env, count, start, limit, |i| DataKey::OwnerProductIndex(owner.clone(), i))
+    }
+
+    pub fn get_product_ids_by_origin(env: Env, origin: String, start: u64, limit: u64) -> Vec<u64> {
+        let limit = page_limit(&env, limit);
+        let count: u64 = env.storage().persistent().get(&DataKey::OriginProductCount(origin.clone())).unwrap_or(0);
+        ids_from_index(&env, count, start, limit, |i| DataKey::OriginProductIndex(origin.clone(), i))
+    }
+
+    /// Newest products first. `start` counts down from the newest product at
+    /// the time of `as_of_total`; pass the `total_products` seen on the first
+    /// page to keep later pages stable, or 0 for the current total.
+    pub fn get_all_products_desc(env: Env, start: u64, limit: u64, as_of_total: u64) -> Vec<Product> {
+        let limit = page_limit(&env, limit);
+        let total: u64 = env.storage().instance().get(&DataKey::TotalProducts).unwrap_or(0);
+        products_from_index_desc(&env, total, as_of_total, start, limit, DataKey::AllProductsIndex, |_| true)
+    }
+
+    /// An owner's products, most recently acquired first. `as_of_total` pins
+    /// the owner's slot count the same way as in `get_all_products_desc`.
+    pub fn get_products_by_owner_desc(env: Env, owner: Address, start: u64, limit: u64, as_of_total: u64) -> Vec<Product> {
+        let limit = page_limit(&env, limit);
+        let count: u64 = env.storage().persistent().get(&DataKey::OwnerProductCount(owner.clone())).unwrap_or(0);
+        products_from_index_desc(&env, count, as_of_total, start, limit, |i| DataKey::OwnerProductIndex(owner.clone(), i), |p| p.owner == owner)
+    }
+
+    /// An origin's products, newest first, pinned like `get_all_products_desc`
+    pub fn get_products_by_origin_desc(env: Env, origin: String, start: u64, limit: u64, as_of_total: u64) -> Vec<Product> {
+        let limit = page_limit(&env, limit);
+        let count: u64 = env.storage().persistent().get(&DataKey::OriginProductCount(origin.clone())).unwrap_or(0);
+        products_from_index_desc(&env, count, as_of_total, start, limit, |i| DataKey::OriginProductIndex(origin.clone(), i), |_| true)
+    }
+
+    /// Get products by origin with pagination.
+    /// `start` is an offset into the origin's index slots; slots vacated by
+    /// `update_origin` are skipped, so a page may hold fewer than `limit` items.
+    /// The same caveat as `get_products_by_owner` applies to advancing `start`.
     pub fn get_products_by_origin(env: Env, origin: String, start: u64, limit: u64) -> Vec<Product> {
+        let limit = page_limit(&env, limit);
         let count: u64 = env.storage().persistent().get(&DataKey::OriginProductCount(origin.clone())).unwrap_or(0);
         let mut products = Vec::new(&env);
         
-        let start_index = start + 1;
-        let end_index = start + limit + 1;
+        let start_index = start.saturating_add(1);
+        let end_index = start.saturating_add(limit).saturating_add(1);
 
         for i in start_index..end_index {
             if i > count {
                 break;
             }
              if let Some(product_id) = env.storage().persistent().get::<DataKey, u64>(&DataKey::OriginProductIndex(origin.clone(), i)) {
-                 if let Some(product) = env.storage().persistent().get::<DataKey, Product>(&DataKey::Product(product_id)) {
+                 if let Some(product) = storage::get_product(&env, product_id) {
+                    touch_product(&env, &product);
                     products.push_back(product);
                  }
             }
@@ -145,233 +410,3911 @@ impl ChainLogisticsContract {
         products
     }
     
-    /// Get product stats
-    pub fn get_stats(env: Env) -> ProductStats {
-        ProductStats {
-            total_products: env.storage().instance().get(&DataKey::TotalProducts).unwrap_or(0),
-            active_products: env.storage().instance().get(&DataKey::ActiveProducts).unwrap_or(0),
+    /// Get products `owner` currently holds that originated in `origin`.
+    /// Slots vacated by transfers and origin changes are skipped, so a page
+    /// may hold fewer than `limit` items.
+    pub fn get_products_by_owner_and_origin(env: Env, owner: Address, origin: String, start: u64, limit: u64) -> Vec<Product> {
+        let limit = page_limit(&env, limit);
+        let count: u64 = env
+            .storage()
+            .persistent()
+            .get(&IndexKey::OwnerOriginCount(owner.clone(), origin.clone()))
+            .unwrap_or(0);
+        products_from_index(&env, count, start, limit, |i| IndexKey::OwnerOriginIndex(owner.clone(), origin.clone(), i))
+    }
+
+    /// Get products whose primary origin or any appended origin matches.
+    /// Pages first through the primary origin index, then through the
+    /// secondary index of appended origins, as one run of slots.
+    pub fn get_products_by_any_origin(env: Env, origin: String, start: u64, limit: u64) -> Vec<Product> {
+        let limit = page_limit(&env, limit);
+        let primary_count: u64 = env.storage().persistent().get(&DataKey::OriginProductCount(origin.clone())).unwrap_or(0);
+        let chain_count: u64 = env.storage().persistent().get(&DataKey::AnyOriginCount(origin.clone())).unwrap_or(0);
+        products_from_index(&env, primary_count + chain_count, start, limit, |i| {
+            if i <= primary_count {
+                DataKey::OriginProductIndex(origin.clone(), i)
+            } else {
+                DataKey::AnyOriginIndex(origin.clone(), i - primary_count)
+            }
+        })
+    }
+
+    /// Get products registered between `from_ts` and `to_ts` inclusive,
+    /// oldest first. Walks the registration day index, so the range may span
+    /// at most `MAX_TIME_RANGE_DAYS` days. `start` counts live products in
+    /// the range; burned ones are neither returned nor counted.
+    pub fn get_products_by_time_range(env: Env, from_ts: u64, to_ts: u64, start: u64, limit: u64) -> Result<Vec<Product>, Error> {
+        if from_ts > to_ts {
+            return Err(Error::InvalidInput);
         }
-fn read_product(env: &Env, product_id: &String) -> Result<Product, Error> {
-    storage::get_product(env, product_id).ok_or(Error::ProductNotFound)
-}
+        let first_day = from_ts / SECONDS_PER_DAY;
+        let last_day = to_ts / SECONDS_PER_DAY;
+        if last_day - first_day >= MAX_TIME_RANGE_DAYS {
+            return Err(Error::InvalidInput);
+        }
+        let limit = page_limit(&env, limit);
+        let mut skip = start;
+        let mut products = Vec::new(&env);
+        for day in first_day..=last_day {
+            let count: u64 = env.storage().persistent().get(&IndexKey::DayCount(day)).unwrap_or(0);
+            for i in 1..=count {
+                if products.len() as u64 >= limit {
+                    return Ok(products);
+                }
+                let Some(product) = env
+                    .storage()
+                    .persistent()
+                    .get::<IndexKey, u64>(&IndexKey::DayIndex(day, i))
+                    .and_then(|product_id| storage::get_product(&env, product_id))
+                else {
+                    continue;
+                };
+                // The first and last day also hold registrations outside the range
+                if product.created_at < from_ts || product.created_at > to_ts {
+                    continue;
+                }
+                if skip > 0 {
+                    skip -= 1;
+                    continue;
+                }
+                touch_product(&env, &product);
+                products.push_back(product);
+            }
+        }
+        Ok(products)
+    }
 
-fn write_product(env: &Env, product: &Product) {
-    storage::put_product(env, product);
-}
+    /// Get a product's full origin chain, primary origin first
+    pub fn get_origin_chain(env: Env, product_id: u64) -> Result<Vec<String>, Error> {
+        let product = read_product(&env, product_id)?;
+        let mut chain = Vec::new(&env);
+        chain.push_back(product.origin);
+        chain.append(&read_origin_chain(&env, product_id));
+        Ok(chain)
+    }
 
-fn require_owner(product: &Product, caller: &Address) -> Result<(), Error> {
-    caller.require_auth();
-    if &product.owner != caller {
-        return Err(Error::Unauthorized);
+    /// Get products carrying a tag, with the same slot-based pagination as
+    /// `get_products_by_origin`
+    pub fn get_products_by_tag(env: Env, tag: Symbol, start: u64, limit: u64) -> Vec<Product> {
+        let limit = page_limit(&env, limit);
+        let count: u64 = env.storage().persistent().get(&DataKey::TagProductCount(tag.clone())).unwrap_or(0);
+        products_from_index(&env, count, start, limit, |i| DataKey::TagProductIndex(tag.clone(), i))
     }
-    Ok(())
-}
 
-fn require_can_add_event(env: &Env, product_id: &String, product: &Product, caller: &Address) -> Result<(), Error> {
-    caller.require_auth();
-    if !product.active {
-        return Err(Error::InvalidInput);
+    /// Get the units registered under a GTIN, with slot-based pagination
+    pub fn get_products_by_gtin(env: Env, gtin: String, start: u64, limit: u64) -> Vec<Product> {
+        let limit = page_limit(&env, limit);
+        let count: u64 = env.storage().persistent().get(&DataKey::GtinCount(gtin.clone())).unwrap_or(0);
+        products_from_index(&env, count, start, limit, |i| DataKey::GtinIndex(gtin.clone(), i))
     }
-    if &product.owner == caller {
-        return Ok(());
+
+    pub fn get_product_gtin(env: Env, product_id: u64) -> Option<String> {
+        env.storage()
+            .persistent()
+            .get::<DataKey, (String, u64)>(&DataKey::ProductGtin(product_id))
+            .map(|(gtin, _)| gtin)
     }
-    if !storage::is_authorized(env, product_id, caller) {
-        return Err(Error::Unauthorized);
+
+    /// Get the products in a lot, with slot-based pagination
+    pub fn get_products_by_lot(env: Env, lot: String, start: u64, limit: u64) -> Vec<Product> {
+        let limit = page_limit(&env, limit);
+        let count: u64 = env.storage().persistent().get(&DataKey::LotCount(lot.clone())).unwrap_or(0);
+        products_from_index(&env, count, start, limit, |i| DataKey::LotIndex(lot.clone(), i))
     }
-    Ok(())
-}
 
-#[contractimpl]
-impl ChainLogisticsContract {
-    pub fn register_product(
-        env: Env,
-        owner: Address,
-        id: String,
-        name: String,
-        description: String,
-        origin_location: String,
-        category: String,
-        tags: Vec<String>,
-        certifications: Vec<BytesN<32>>,
-        media_hashes: Vec<BytesN<32>>,
-        custom: Map<Symbol, String>,
-    ) -> Result<Product, Error> {
-        const MAX_ID_LEN: u32 = 64;
-        const MAX_NAME_LEN: u32 = 128;
-        const MAX_ORIGIN_LEN: u32 = 256;
-        const MAX_CATEGORY_LEN: u32 = 64;
-        const MAX_DESCRIPTION_LEN: u32 = 2048;
-        const MAX_TAG_LEN: u32 = 64;
-        const MAX_CUSTOM_VALUE_LEN: u32 = 512;
-
-        const MAX_TAGS: u32 = 20;
-        const MAX_CERTIFICATIONS: u32 = 50;
-        const MAX_MEDIA_HASHES: u32 = 50;
-        const MAX_CUSTOM_FIELDS: u32 = 20;
-
-        if !validation::non_empty(&id) {
-            return Err(Error::InvalidProductId);
-        }
-        if !validation::max_len(&id, MAX_ID_LEN) {
-            return Err(Error::ProductIdTooLong);
+    pub fn get_product_lot(env: Env, product_id: u64) -> Option<String> {
+        env.storage()
+            .persistent()
+            .get::<DataKey, (String, u64)>(&DataKey::ProductLot(product_id))
+            .map(|(lot, _)| lot)
+    }
+
+    /// Get the tags attached to a product
+    pub fn get_product_tags(env: Env, product_id: u64) -> Vec<Symbol> {
+        storage::get_product_tags(&env, product_id).keys()
+    }
+
+    /// Every origin a product has been registered or moved under, in the
+    /// order first seen. Origins stay listed after their products are gone.
+    pub fn get_origins(env: Env, start: u64, limit: u64) -> Vec<String> {
+        let limit = page_limit(&env, limit);
+        let count: u64 = env.storage().instance().get(&IndexKey::OriginListCount).unwrap_or(0);
+        let mut origins = Vec::new(&env);
+        let end_index = start.saturating_add(limit).min(count);
+        for i in start.saturating_add(1)..=end_index {
+            if let Some(origin) = env.storage().persistent().get::<IndexKey, String>(&IndexKey::OriginList(i)) {
+                origins.push_back(origin);
+            }
         }
-        if !validation::non_empty(&name) {
-            return Err(Error::InvalidProductName);
+        origins
+    }
+
+    /// The origins with the most registrations, up to `TOP_ORIGINS_SIZE`
+    /// of them, busiest first. Ties go to the origin seen first.
+    pub fn get_top_origins(env: Env) -> Vec<(String, u64)> {
+        let board: Vec<(String, u64, u64)> = env.storage().instance().get(&IndexKey::TopOrigins).unwrap_or(Vec::new(&env));
+        let mut top = Vec::new(&env);
+        for (origin, count, _) in board.iter() {
+            top.push_back((origin, count));
         }
-        if !validation::max_len(&name, MAX_NAME_LEN) {
-            return Err(Error::ProductNameTooLong);
+        top
+    }
+
+    pub fn get_origin_count(env: Env) -> u64 {
+        env.storage().instance().get(&IndexKey::OriginListCount).unwrap_or(0)
+    }
+
+    /// Holdings and transfer totals for `owner`. Unlike the owner index,
+    /// which keeps the slots of products sent away, `owned` only counts
+    /// products the address holds now.
+    pub fn get_owner_stats(env: Env, owner: Address) -> OwnerStats {
+        let persistent = env.storage().persistent();
+        OwnerStats {
+            owned: persistent.get(&StatsKey::Owned(owner.clone())).unwrap_or(0),
+            registered: persistent.get(&StatsKey::RegisteredBy(owner.clone())).unwrap_or(0),
+            received: persistent.get(&StatsKey::Received(owner.clone())).unwrap_or(0),
+            sent: persistent.get(&TransferKey::SentCount(owner)).unwrap_or(0),
         }
-        if !validation::non_empty(&origin_location) {
-            return Err(Error::InvalidOrigin);
+    }
+
+    pub fn get_origin_stats(env: Env, origin: String) -> OriginStats {
+        env.storage().persistent().get(&StatsKey::Origin(origin)).unwrap_or_default()
+    }
+
+    /// Registrations and transfers for each ledger day from `from_day` to
+    /// `to_day` inclusive, at most `MAX_DAILY_RANGE_DAYS` of them. Quiet days
+    /// are included as zeros.
+    pub fn get_daily_activity(env: Env, from_day: u64, to_day: u64) -> Result<Vec<DailyStats>, Error> {
+        if from_day > to_day || to_day - from_day >= MAX_DAILY_RANGE_DAYS {
+            return Err(Error::InvalidInput);
         }
-        if !validation::max_len(&origin_location, MAX_ORIGIN_LEN) {
-            return Err(Error::OriginTooLong);
+        let persistent = env.storage().persistent();
+        let mut days = Vec::new(&env);
+        for day in from_day..=to_day {
+            days.push_back(DailyStats {
+                day,
+                registrations: persistent.get(&IndexKey::DayCount(day)).unwrap_or(0),
+                transfers: persistent.get(&StatsKey::DailyTransfers(day)).unwrap_or(0),
+            });
         }
-        if !validation::non_empty(&category) {
-            return Err(Error::InvalidCategory);
+        Ok(days)
+    }
+
+    /// Successful state-changing calls made so far across the contract.
+    /// Each such call takes the next number, however many products or
+    /// events it touches, and every event carries the number of the call
+    /// that published it, so an indexer can tell from a gap exactly how
+    /// many calls it missed. Reads and TTL extensions are not counted.
+    pub fn get_event_seq(env: Env) -> u64 {
+        storage::get_event_seq(&env)
+    }
+
+    /// The latest registrations, transfers and renunciations across the
+    /// registry, newest first. Only the last `recent_activity_size` events
+    /// are kept, and resizing the feed drops the ones written before.
+    pub fn get_recent_activity(env: Env, limit: u32) -> Vec<ActivityItem> {
+        let size = read_config(&env).recent_activity_size as u64;
+        let head: u64 = env.storage().instance().get(&IndexKey::RecentHead).unwrap_or(0);
+        let mut items = Vec::new(&env);
+        if size == 0 {
+            return items;
         }
-        if !validation::max_len(&category, MAX_CATEGORY_LEN) {
-            return Err(Error::CategoryTooLong);
+        let oldest = head.saturating_sub((limit as u64).min(size));
+        for seq in (oldest + 1..=head).rev() {
+            let slot = (seq % size) as u32;
+            match env.storage().persistent().get::<IndexKey, ActivityItem>(&IndexKey::RecentEvent(slot)) {
+                Some(item) if item.seq == seq => items.push_back(item),
+                _ => break,
+            }
         }
-        if !validation::max_len(&description, MAX_DESCRIPTION_LEN) {
-            return Err(Error::DescriptionTooLong);
+        items
+    }
+
+    /// Get product stats
+    pub fn get_stats(env: Env) -> ProductStats {
+        ProductStats {
+            total_products: env.storage().instance().get(&DataKey::TotalProducts).unwrap_or(0),
+            active_products: env.storage().instance().get(&DataKey::ActiveProducts).unwrap_or(0),
+            inactive_products: env.storage().instance().get(&StatsKey::InactiveProducts).unwrap_or(0),
+            total_transfers: env.storage().instance().get(&TransferKey::TotalTransfers).unwrap_or(0),
+            distinct_origins: env.storage().instance().get(&IndexKey::OriginListCount).unwrap_or(0),
+            total_owners: env.storage().instance().get(&StatsKey::TotalOwners).unwrap_or(0),
+            last_registration_at: env.storage().instance().get(&StatsKey::LastRegistrationAt),
         }
+    }
+}
 
-        if tags.len() > MAX_TAGS {
-            return Err(Error::TooManyTags);
+fn register(
+    env: &Env,
+    owner: Address,
+    origin: String,
+    metadata: String,
+    serial: Option<String>,
+) -> Result<u64, Error> {
+    require_not_paused(env)?;
+    owner.require_auth();
+    require_not_blacklisted(env, &owner)?;
+    require_registrar(env, &owner)?;
+
+    validation::validate_origin(&origin)?;
+    validation::validate_metadata(&metadata, read_config(env).max_metadata_len)?;
+    if let Some(serial) = &serial {
+        if !validation::non_empty(serial) || !validation::max_len(serial, MAX_SERIAL_LEN) {
+            return Err(Error::InvalidInput);
         }
-        for i in 0..tags.len() {
-            let t = tags.get_unchecked(i);
-            if !validation::max_len(&t, MAX_TAG_LEN) {
-                return Err(Error::TagTooLong);
-            }
+        if env.storage().persistent().has(&DataKey::SerialIndex(serial.clone())) {
+            return Err(Error::DuplicateSerial);
         }
+    }
 
-        if certifications.len() > MAX_CERTIFICATIONS {
-            return Err(Error::TooManyCertifications);
+    require_origin_allowed(env, &origin)?;
+    record_registration(env, &owner)?;
+
+    // Collect the fee before any state is written; a failed payment aborts
+    if let Some(fee) = env.storage().instance().get::<AdminKey, RegistrationFee>(&AdminKey::Fee) {
+        pay_registration_fee(env, &owner, &fee)?;
+    }
+
+    // increments product count
+    let mut total_products: u64 = env.storage().instance().get(&DataKey::TotalProducts).unwrap_or(0);
+    total_products += 1;
+    
+    let product = Product {
+        id: total_products,
+        owner: owner.clone(),
+        origin: origin.clone(),
+        active: true,
+        metadata,
+        created_at: env.ledger().timestamp(),
+        recalled: false,
+        previous_owner: Vec::new(env),
+        transferred_at: None,
+    };
+
+    // 1. Store Product
+    env.storage().persistent().set(&DataKey::Product(total_products), &product);
+    if let Some(serial) = serial {
+        env.storage().persistent().set(&DataKey::SerialIndex(serial.clone()), &total_products);
+        env.storage().persistent().set(&DataKey::ProductSerial(total_products), &serial);
+    }
+    
+    // 2. Global Index (Index -> ID)
+    // Since ID is sequential and starts at 1, we can just use ID as the index for "All Products" 
+    // if we assume we iterate by ID. 
+    // But if we want to support deleting or non-sequential IDs later, an explicit index is better.
+    // For now, let's map Index (1-based) to ProductID.
+    env.storage().persistent().set(&DataKey::AllProductsIndex(total_products), &total_products);
+
+    // 3. Owner Index
+    storage::append_owner_index(env, &owner, total_products);
+
+    // 4. Origin Index
+    storage::append_origin_index(env, &origin, total_products);
+    storage::append_owner_origin_index(env, &owner, &origin, total_products);
+    let origin_slot = storage::note_origin(env, &origin);
+
+    // 5. Registration day index
+    storage::append_day_index(env, product.created_at / SECONDS_PER_DAY, total_products);
+    storage::set_status(env, total_products, Some(ProductStatus::Registered));
+
+    // 6. Custody log and transfer history start with the registering owner
+    log_custody(env, total_products, &owner, &owner, CustodyRole::Owner);
+    record_transfer(env, total_products, 0, &owner, &owner, String::from_str(env, ""));
+    
+    // Update global counters
+    env.storage().instance().set(&DataKey::TotalProducts, &total_products);
+    
+    // Update active count
+    let mut active_products: u64 = env.storage().instance().get(&DataKey::ActiveProducts).unwrap_or(0);
+    active_products += 1;
+    env.storage().instance().set(&DataKey::ActiveProducts, &active_products);
+
+    let registered: u64 = env.storage().persistent().get(&StatsKey::RegisteredBy(owner.clone())).unwrap_or(0);
+    if registered == 0 {
+        let owners: u64 = env.storage().instance().get(&StatsKey::TotalOwners).unwrap_or(0);
+        env.storage().instance().set(&StatsKey::TotalOwners, &(owners + 1));
+    }
+    env.storage().persistent().set(&StatsKey::RegisteredBy(owner.clone()), &(registered + 1));
+    adjust_owned(env, &owner, true);
+    env.storage().instance().set(&StatsKey::LastRegistrationAt, &product.created_at);
+    let origin_stats = update_origin_stats(env, &origin, |stats| {
+        stats.total_registered += 1;
+        stats.active += 1;
+        stats.last_registration_at = product.created_at;
+    });
+    rank_origin(env, &origin, origin_stats.total_registered, origin_slot);
+    push_recent_activity(env, symbol_short!("register"), total_products, &owner);
+    audit(env, total_products, symbol_short!("register"), &owner, 0);
+    events::product_registered(env, &owner, total_products, &origin, product.created_at);
+
+    Ok(total_products)
+}
+
+/// Reads `limit` slots of a positional index starting after `start`, skipping
+/// tombstoned slots and products that no longer exist.
+fn products_from_index<K, F>(env: &Env, count: u64, start: u64, limit: u64, slot_key: F) -> Vec<Product>
+where
+    K: IntoVal<Env, Val>,
+    F: Fn(u64) -> K,
+{
+    let mut products = Vec::new(env);
+
+    let start_index = start.saturating_add(1);
+    let end_index = start.saturating_add(limit).saturating_add(1);
+
+    for i in start_index..end_index {
+        if i > count {
+            break;
         }
-        if media_hashes.len() > MAX_MEDIA_HASHES {
-            return Err(Error::TooManyMediaHashes);
+        if let Some(product_id) = env.storage().persistent().get::<K, u64>(&slot_key(i)) {
+            if let Some(product) = storage::get_product(env, product_id) {
+                touch_product(env, &product);
+                products.push_back(product);
+            }
         }
+    }
+    products
+}
 
-        if custom.len() > MAX_CUSTOM_FIELDS {
-            return Err(Error::TooManyCustomFields);
+/// The ids in `limit` slots of a positional index after `start`, read
+/// without loading any product.
+fn ids_from_index<F>(env: &Env, count: u64, start: u64, limit: u64, slot_key: F) -> Vec<u64>
+where
+    F: Fn(u64) -> DataKey,
+{
+    let mut ids = Vec::new(env);
+    let end_index = start.saturating_add(limit).min(count);
+    for i in start.saturating_add(1)..=end_index {
+        if let Some(product_id) = env.storage().persistent().get::<DataKey, u64>(&slot_key(i)) {
+            ids.push_back(product_id);
         }
-        let custom_keys = custom.keys();
-        for i in 0..custom_keys.len() {
-            let k = custom_keys.get_unchecked(i);
-            let v = custom.get_unchecked(k);
-            if !validation::max_len(&v, MAX_CUSTOM_VALUE_LEN) {
-                return Err(Error::CustomFieldValueTooLong);
+    }
+    ids
+}
+
+/// Reads `limit` slots of a positional index downwards, starting `start`
+/// slots below `top`. Pinning `top` keeps pages stable while later slots are
+/// appended; `top` of 0 means the current `count`.
+fn products_from_index_desc<F, P>(env: &Env, count: u64, top: u64, start: u64, limit: u64, slot_key: F, keep: P) -> Vec<Product>
+where
+    F: Fn(u64) -> DataKey,
+    P: Fn(&Product) -> bool,
+{
+    let mut products = Vec::new(env);
+    let top = if top == 0 { count } else { top.min(count) };
+    let first = top.saturating_sub(start);
+    let last = first.saturating_sub(limit).saturating_add(1);
+    for i in (last..=first).rev() {
+        if let Some(product_id) = env.storage().persistent().get::<DataKey, u64>(&slot_key(i)) {
+            if let Some(product) = storage::get_product(env, product_id) {
+                if keep(&product) {
+                    touch_product(env, &product);
+                    products.push_back(product);
+                }
             }
         }
+    }
+    products
+}
 
-        if storage::has_product(&env, &id) {
-            return Err(Error::ProductAlreadyExists);
+/// Like `products_from_index`, but fills the page with products that pass
+/// `keep`, reading on past `start + limit` slots as needed, up to
+/// `MAX_SCAN_SLOTS`. Returns the page and the last slot read, which is where
+/// the next page starts.
+fn matching_products_from_index<F, P>(env: &Env, count: u64, start: u64, limit: u64, slot_key: F, keep: P) -> (Vec<Product>, u64)
+where
+    F: Fn(u64) -> DataKey,
+    P: Fn(&Product) -> bool,
+{
+    let mut products = Vec::new(env);
+    let end_index = start.saturating_add(MAX_SCAN_SLOTS).min(count);
+    let mut last = start;
+    for i in start.saturating_add(1)..=end_index {
+        if products.len() as u64 >= limit {
+            break;
         }
+        last = i;
+        if let Some(product_id) = env.storage().persistent().get::<DataKey, u64>(&slot_key(i)) {
+            if let Some(product) = storage::get_product(env, product_id) {
+                if keep(&product) {
+                    touch_product(env, &product);
+                    products.push_back(product);
+                }
+            }
+        }
+    }
+    (products, last)
+}
 
-        owner.require_auth();
+/// Wraps a filled page with the slot count and where the next page starts.
+fn product_page<F, P>(env: &Env, count: u64, start: u64, limit: u64, slot_key: F, keep: P) -> ProductPage
+where
+    F: Fn(u64) -> DataKey,
+    P: Fn(&Product) -> bool,
+{
+    let (items, last) = matching_products_from_index(env, count, start, limit, slot_key, keep);
+    ProductPage {
+        items,
+        total: count,
+        next_start: if last < count { Some(last) } else { None },
+    }
+}
 
-        let product = Product {
-            id: id.clone(),
-            name,
-            description,
-            origin: Origin {
-                location: origin_location,
-            },
-            owner: owner.clone(),
-            created_at: env.ledger().timestamp(),
-            active: true,
-            category,
-            tags,
-            certifications,
-            media_hashes,
-            custom,
-        };
+/// Wraps a filled page with the last slot examined as the next cursor.
+fn cursor_page<F, P>(env: &Env, count: u64, cursor: u64, limit: u64, slot_key: F, keep: P) -> CursorPage
+where
+    F: Fn(u64) -> DataKey,
+    P: Fn(&Product) -> bool,
+{
+    let (items, last) = matching_products_from_index(env, count, cursor, limit, slot_key, keep);
+    CursorPage {
+        items,
+        cursor: last,
+        has_more: last < count,
+    }
+}
 
-        write_product(&env, &product);
-        storage::put_product_event_ids(&env, &id, &Vec::new(&env));
-        storage::set_auth(&env, &id, &owner, true);
+fn burn(env: &Env, product: &Product) {
+    audit(env, product.id, symbol_short!("burn"), &product.owner, 0);
+    if let Some(serial) = env.storage().persistent().get::<DataKey, String>(&DataKey::ProductSerial(product.id)) {
+        env.storage().persistent().remove(&DataKey::SerialIndex(serial));
+        env.storage().persistent().remove(&DataKey::ProductSerial(product.id));
+    }
+    env.storage().persistent().remove(&DataKey::Quantity(product.id));
+    storage::remove_product(env, product.id);
+    env.storage().persistent().set(&LifecycleKey::Burned(product.id), &true);
+    storage::clear_pending_transfer(env, product.id);
+    storage::set_status(env, product.id, None);
+    storage::set_alerts(env, product.id, 0);
+    adjust_owned(env, &product.owner, false);
 
-        env.events().publish((Symbol::new(&env, "product_registered"), id.clone()), product.clone());
-        Ok(product)
+    if product.active {
+        let active_products: u64 = env.storage().instance().get(&DataKey::ActiveProducts).unwrap_or(0);
+        env.storage().instance().set(&DataKey::ActiveProducts, &active_products.saturating_sub(1));
+        update_origin_stats(env, &product.origin, |stats| stats.active = stats.active.saturating_sub(1));
+    } else {
+        let inactive: u64 = env.storage().instance().get(&StatsKey::InactiveProducts).unwrap_or(0);
+        env.storage().instance().set(&StatsKey::InactiveProducts, &inactive.saturating_sub(1));
     }
+}
+
+fn adjust_owned(env: &Env, owner: &Address, gained: bool) {
+    let key = StatsKey::Owned(owner.clone());
+    let owned: u64 = env.storage().persistent().get(&key).unwrap_or(0);
+    let owned = if gained { owned + 1 } else { owned.saturating_sub(1) };
+    env.storage().persistent().set(&key, &owned);
+}
+
+fn update_origin_stats<F>(env: &Env, origin: &String, update: F) -> OriginStats
+where
+    F: FnOnce(&mut OriginStats),
+{
+    let key = StatsKey::Origin(origin.clone());
+    let mut stats: OriginStats = env.storage().persistent().get(&key).unwrap_or_default();
+    update(&mut stats);
+    env.storage().persistent().set(&key, &stats);
+    stats
+}
 
-    pub fn get_product(env: Env, id: String) -> Result<Product, Error> {
-        read_product(&env, &id)
+/// Moves `origin` to its place on the top-origins board after its count
+/// changed to `count`. Higher counts rank first, then earlier first-seen
+/// slots; an origin off a full board enters only by beating the last entry,
+/// and one whose count fell to zero leaves the board.
+fn rank_origin(env: &Env, origin: &String, count: u64, first_seen: u64) {
+    let mut board: Vec<(String, u64, u64)> = env.storage().instance().get(&IndexKey::TopOrigins).unwrap_or(Vec::new(env));
+    let outranks = |entry: &(String, u64, u64)| count > entry.1 || (count == entry.1 && first_seen < entry.2);
+    if let Some(pos) = board.iter().position(|entry| &entry.0 == origin) {
+        board.remove(pos as u32);
+        if count == 0 {
+            env.storage().instance().set(&IndexKey::TopOrigins, &board);
+            return;
+        }
+    } else if board.len() >= TOP_ORIGINS_SIZE {
+        match board.last() {
+            Some(last) if outranks(&last) => {
+                board.pop_back();
+            }
+            _ => return,
+        }
     }
+    let at = board.iter().position(|entry| outranks(&entry)).unwrap_or(board.len() as usize);
+    board.insert(at as u32, (origin.clone(), count, first_seen));
+    env.storage().instance().set(&IndexKey::TopOrigins, &board);
+}
 
-    pub fn get_product_event_ids(env: Env, id: String) -> Result<Vec<u64>, Error> {
-        let _ = read_product(&env, &id)?;
-        Ok(storage::get_product_event_ids(&env, &id))
+fn tag_product(env: &Env, product_id: u64, tag: Symbol) -> Result<(), Error> {
+    let mut tags = storage::get_product_tags(env, product_id);
+    if tags.contains_key(tag.clone()) {
+        return Ok(());
+    }
+    if tags.len() >= MAX_TAGS_PER_PRODUCT {
+        return Err(Error::TooManyTags);
     }
 
-    pub fn add_authorized_actor(env: Env, owner: Address, product_id: String, actor: Address) -> Result<(), Error> {
-        let product = read_product(&env, &product_id)?;
-        require_owner(&product, &owner)?;
-        storage::set_auth(&env, &product_id, &actor, true);
-        Ok(())
+    let slot = storage::append_tag_index(env, &tag, product_id);
+    tags.set(tag, slot);
+    storage::put_product_tags(env, product_id, &tags);
+    Ok(())
+}
+
+fn read_origin_chain(env: &Env, product_id: u64) -> Vec<String> {
+    env.storage()
+        .persistent()
+        .get(&DataKey::OriginChain(product_id))
+        .unwrap_or(Vec::new(env))
+}
+
+/// Counts a breach and raises the excursion flag when the reading is outside
+/// the product's limits. Products without limits never breach.
+fn check_temperature_limits(env: &Env, product_id: u64, temp_centi_c: i32) {
+    let limits: Option<(i32, i32)> = env.storage().persistent().get(&TrackingKey::TempLimits(product_id));
+    if let Some((min, max)) = limits {
+        if temp_centi_c < min || temp_centi_c > max {
+            let breaches: u32 = env.storage().persistent().get(&TrackingKey::BreachCount(product_id)).unwrap_or(0);
+            env.storage().persistent().set(&TrackingKey::BreachCount(product_id), &(breaches + 1));
+            env.storage().persistent().set(&TrackingKey::Excursion(product_id), &true);
+            raise_alert(env, product_id, ALERT_EXCURSION);
+        }
     }
+}
 
-    pub fn remove_authorized_actor(env: Env, owner: Address, product_id: String, actor: Address) -> Result<(), Error> {
-        let product = read_product(&env, &product_id)?;
-        require_owner(&product, &owner)?;
-        storage::set_auth(&env, &product_id, &actor, false);
-        Ok(())
+/// Validates every reading before writing any, then writes each channel's
+/// stats once. Timestamps must not decrease across the product's channels.
+fn append_sensor_readings(env: &Env, product_id: u64, readings: &Vec<SensorReading>) -> Result<(), Error> {
+    if readings.is_empty() {
+        return Err(Error::InvalidInput);
+    }
+    let mut last_ts: u64 = env.storage().persistent().get(&TrackingKey::LastReadingAt(product_id)).unwrap_or(0);
+    let now = env.ledger().timestamp();
+    let mut channels: Vec<Symbol> = env.storage().persistent().get(&TrackingKey::ProductChannels(product_id)).unwrap_or(Vec::new(env));
+    let channel_count = channels.len();
+    let mut stats: Map<Symbol, ChannelStats> = Map::new(env);
+    for r in readings.iter() {
+        if r.timestamp < last_ts || r.timestamp > now {
+            return Err(Error::InvalidTimestamp);
+        }
+        last_ts = r.timestamp;
+        if !channels.contains(&r.kind) {
+            if channels.len() >= MAX_CHANNELS_PER_PRODUCT {
+                return Err(Error::TooManyChannels);
+            }
+            channels.push_back(r.kind.clone());
+        }
+        if !stats.contains_key(r.kind.clone()) {
+            stats.set(r.kind.clone(), read_channel_stats(env, product_id, &r.kind));
+        }
     }
 
-    pub fn transfer_product(env: Env, owner: Address, product_id: String, new_owner: Address) -> Result<(), Error> {
-        let mut product = read_product(&env, &product_id)?;
-        require_owner(&product, &owner)?;
+    for r in readings.iter() {
+        let mut s = stats.get(r.kind.clone()).unwrap();
+        if s.count == 0 {
+            s.min = r.value;
+            s.max = r.value;
+        } else {
+            s.min = s.min.min(r.value);
+            s.max = s.max.max(r.value);
+        }
+        s.count += 1;
+        env.storage().persistent().set(&TrackingKey::ChannelReading(product_id, r.kind.clone(), s.count), &r);
+        stats.set(r.kind.clone(), s);
+    }
+    for (channel, s) in stats.iter() {
+        env.storage().persistent().set(&TrackingKey::ChannelStats(product_id, channel), &s);
+    }
+    if channels.len() != channel_count {
+        env.storage().persistent().set(&TrackingKey::ProductChannels(product_id), &channels);
+    }
+    env.storage().persistent().set(&TrackingKey::LastReadingAt(product_id), &last_ts);
+    Ok(())
+}
 
-        new_owner.require_auth();
+fn read_channel_stats(env: &Env, product_id: u64, channel: &Symbol) -> ChannelStats {
+    env.storage()
+        .persistent()
+        .get(&TrackingKey::ChannelStats(product_id, channel.clone()))
+        .unwrap_or(ChannelStats { count: 0, min: 0, max: 0 })
+}
 
-        storage::set_auth(&env, &product_id, &product.owner, false);
-        product.owner = new_owner.clone();
-        write_product(&env, &product);
-        storage::set_auth(&env, &product_id, &new_owner, true);
-        Ok(())
+/// Advances the route when a checkpoint reaches the next waypoint and flags
+/// a deviation otherwise. Checkpoints after the last waypoint are not compared.
+fn follow_route(env: &Env, product_id: u64, location: &String) {
+    let route: Option<Vec<String>> = env.storage().persistent().get(&TrackingKey::Route(product_id));
+    let Some(route) = route else {
+        return;
+    };
+    let progress: u32 = env.storage().persistent().get(&TrackingKey::RouteProgress(product_id)).unwrap_or(0);
+    match route.get(progress) {
+        Some(expected) if &expected == location => {
+            env.storage().persistent().set(&TrackingKey::RouteProgress(product_id), &(progress + 1));
+        }
+        Some(_) => {
+            env.storage().persistent().set(&TrackingKey::RouteDeviation(product_id), &true);
+            raise_alert(env, product_id, ALERT_ROUTE_DEVIATION);
+        }
+        None => {}
     }
+}
 
-    pub fn set_product_active(env: Env, owner: Address, product_id: String, active: bool) -> Result<(), Error> {
-        let mut product = read_product(&env, &product_id)?;
-        require_owner(&product, &owner)?;
-        product.active = active;
-        write_product(&env, &product);
-        Ok(())
+/// Reads a checkpoint with any correction's location applied.
+fn read_checkpoint(env: &Env, product_id: u64, seq: u32) -> Option<Checkpoint> {
+    let mut checkpoint: Checkpoint = env.storage().persistent().get(&DataKey::Checkpoint(product_id, seq))?;
+    if let Some(correction) = env.storage().persistent().get::<TrackingKey, CheckpointCorrection>(&TrackingKey::CheckpointCorrection(product_id, seq)) {
+        checkpoint.location = correction.location;
     }
+    Some(checkpoint)
+}
 
-    pub fn add_tracking_event(env: Env, actor: Address, product_id: String, event_type: Symbol, data_hash: BytesN<32>, note: String) -> Result<u64, Error> {
-        let product = read_product(&env, &product_id)?;
-        require_can_add_event(&env, &product_id, &product, &actor)?;
-
-        let event_id = storage::next_event_id(&env);
-        let event = TrackingEvent {
-            event_id,
-            product_id: product_id.clone(),
-            actor,
-            timestamp: env.ledger().timestamp(),
-            event_type,
-            data_hash,
-            note,
-        };
+/// Guards shared by every path that changes a product's owner.
+fn require_transferable(env: &Env, product: &Product, new_owner: &Address) -> Result<(), Error> {
+    require_single_signer(env, product)?;
+    require_transfer_guards(env, product, new_owner)
+}
 
-        storage::put_event(&env, &event);
-        let mut ids = storage::get_product_event_ids(&env, &product_id);
-        ids.push_back(event_id);
-        storage::put_product_event_ids(&env, &product_id, &ids);
-        Ok(event_id)
+/// Products whose co-owners require more than one signature can only move
+/// through `transfer_jointly`.
+fn require_single_signer(env: &Env, product: &Product) -> Result<(), Error> {
+    if storage::co_owner_threshold(env, product.id) > 1 {
+        return Err(Error::Unauthorized);
     }
+    Ok(())
+}
 
-    pub fn get_event(env: Env, event_id: u64) -> Result<TrackingEvent, Error> {
-        storage::get_event(&env, event_id).ok_or(Error::EventNotFound)
+fn require_transfer_guards(env: &Env, product: &Product, new_owner: &Address) -> Result<(), Error> {
+    if &product.owner == new_owner {
+        return Err(Error::SelfTransfer);
     }
+    require_not_blacklisted(env, &product.owner)?;
+    require_not_blacklisted(env, new_owner)?;
+    require_transfer_state(env, product)
+}
 
-    pub fn is_authorized(env: Env, product_id: String, actor: Address) -> Result<bool, Error> {
-        let product = read_product(&env, &product_id)?;
-        if product.owner == actor {
+fn require_transfer_state(env: &Env, product: &Product) -> Result<(), Error> {
+    require_unlocked(env, product)?;
+    require_checkpoint_requirements(env, product.id)
+}
+
+/// Every checkpoint kind the owner listed must have been logged at least once.
+fn require_checkpoint_requirements(env: &Env, product_id: u64) -> Result<(), Error> {
+    let required: Vec<CheckpointKind> = env.storage().persistent().get(&TransferKey::Requirements(product_id)).unwrap_or(Vec::new(env));
+    if required.is_empty() {
+        return Ok(());
+    }
+    let seen: Vec<CheckpointKind> = env.storage().persistent().get(&TrackingKey::SeenKinds(product_id)).unwrap_or(Vec::new(env));
+    for kind in required.iter() {
+        if !seen.contains(kind) {
+            return Err(Error::RequirementsNotMet);
+        }
+    }
+    Ok(())
+}
+
+/// Fails for products whose state rules out a change of owner.
+fn require_unlocked(env: &Env, product: &Product) -> Result<(), Error> {
+    require_movable(env, product)?;
+    let in_custody = storage::get_custodian(env, product.id).is_some();
+    if in_custody && !env.storage().persistent().get(&TrackingKey::TransferInCustody(product.id)).unwrap_or(false) {
+        return Err(Error::ProductInCustody);
+    }
+    Ok(())
+}
+
+/// Fails for products that may not change hands at all, whether to a new
+/// owner or a new custodian.
+fn require_movable(env: &Env, product: &Product) -> Result<(), Error> {
+    require_not_renounced(env, product.id)?;
+    if !product.active {
+        return Err(Error::ProductInactive);
+    }
+    if product.recalled {
+        return Err(Error::ProductRecalled);
+    }
+    if env.storage().persistent().has(&TransferKey::Claim(product.id)) {
+        return Err(Error::ProductLocked);
+    }
+    if !is_transfer_final(env, product.id) {
+        return Err(Error::ProductLocked);
+    }
+    Ok(())
+}
+
+fn is_transfer_final(env: &Env, product_id: u64) -> bool {
+    match env.storage().persistent().get::<TransferKey, TransferWindow>(&TransferKey::Window(product_id)) {
+        Some(window) => env.ledger().timestamp() >= window.final_at,
+        None => true,
+    }
+}
+
+/// Moves the product to `new_owner`'s index (tombstoning the old slot),
+/// records the change in the custody log and saves the product. Any
+/// pending proposal, scheduled transfer, open claim, dispute window, approved
+/// agent and co-owners are dropped, as are actor grants if the owner asked
+/// for that.
+/// Every change of owner goes through here, writes one audit entry under
+/// the previous owner and publishes one transfer event. Returns the
+/// transfer history seq of the new record.
+fn reassign_owner(env: &Env, product: &mut Product, new_owner: Address, memo: String) -> u32 {
+    let seq = env.storage().persistent().get::<TransferKey, u32>(&TransferKey::HistoryCount(product.id)).unwrap_or(0) + 1;
+    audit(env, product.id, symbol_short!("transfer"), &product.owner, seq.into());
+    env.storage().persistent().remove(&AuthKey::CoOwners(product.id));
+    env.storage().persistent().remove(&AuthKey::CoOwnerThreshold(product.id));
+    if env.storage().persistent().get(&AuthKey::ClearOnTransfer(product.id)).unwrap_or(false) {
+        storage::clear_auth(env, product.id);
+    }
+    storage::clear_pending_transfer(env, product.id);
+    env.storage().persistent().remove(&TransferKey::Scheduled(product.id));
+    env.storage().persistent().remove(&TransferKey::Claim(product.id));
+    env.storage().persistent().remove(&TransferKey::Window(product.id));
+    env.storage().persistent().remove(&TransferKey::Agent(product.id));
+    storage::remove_owner_index(env, &product.owner, product.id);
+    storage::append_owner_index(env, &new_owner, product.id);
+    storage::remove_owner_origin_index(env, &product.owner, &product.origin, product.id);
+    storage::append_owner_origin_index(env, &new_owner, &product.origin, product.id);
+    log_custody(env, product.id, &product.owner, &new_owner, CustodyRole::Owner);
+    record_transfer(env, product.id, seq, &product.owner, &new_owner, memo);
+    if seq == 1 {
+        update_origin_stats(env, &product.origin, |stats| stats.transferred += 1);
+    }
+    env.storage().persistent().set(&TransferKey::HistoryCount(product.id), &seq);
+
+    let total: u64 = env.storage().instance().get(&TransferKey::TotalTransfers).unwrap_or(0);
+    env.storage().instance().set(&TransferKey::TotalTransfers, &(total + 1));
+    let day_key = StatsKey::DailyTransfers(env.ledger().timestamp() / SECONDS_PER_DAY);
+    let today: u64 = env.storage().persistent().get(&day_key).unwrap_or(0);
+    env.storage().persistent().set(&day_key, &(today + 1));
+    let sent: u64 = env.storage().persistent().get(&TransferKey::SentCount(product.owner.clone())).unwrap_or(0);
+    env.storage().persistent().set(&TransferKey::SentCount(product.owner.clone()), &(sent + 1));
+    let received: u64 = env.storage().persistent().get(&StatsKey::Received(new_owner.clone())).unwrap_or(0);
+    env.storage().persistent().set(&StatsKey::Received(new_owner.clone()), &(received + 1));
+    adjust_owned(env, &product.owner, false);
+    adjust_owned(env, &new_owner, true);
+    push_recent_activity(env, symbol_short!("transfer"), product.id, &new_owner);
+    events::product_transferred(env, product.id, &product.owner, &new_owner);
+    product.previous_owner = Vec::from_array(env, [product.owner.clone()]);
+    product.transferred_at = Some(env.ledger().timestamp());
+    product.owner = new_owner;
+    write_product(env, product);
+    seq
+}
+
+fn record_transfer(env: &Env, product_id: u64, seq: u32, from: &Address, to: &Address, memo: String) {
+    let record = TransferRecord {
+        seq,
+        from: from.clone(),
+        to: to.clone(),
+        timestamp: env.ledger().timestamp(),
+        memo,
+        forced: false,
+    };
+    env.storage().persistent().set(&TransferKey::History(product_id, seq), &record);
+}
+
+fn log_custody(env: &Env, product_id: u64, from: &Address, to: &Address, role: CustodyRole) {
+    let seq: u32 = env.storage().persistent().get::<TrackingKey, u32>(&TrackingKey::CustodyCount(product_id)).unwrap_or(0) + 1;
+    let entry = CustodyEntry {
+        seq,
+        from: from.clone(),
+        to: to.clone(),
+        role,
+        timestamp: env.ledger().timestamp(),
+    };
+    env.storage().persistent().set(&TrackingKey::CustodyLog(product_id, seq), &entry);
+    env.storage().persistent().set(&TrackingKey::CustodyCount(product_id), &seq);
+}
+
+/// Records a mutating call by anyone but the owner. Once the log holds
+/// `MAX_ACTIVITY_LOG` entries, further calls are only counted.
+fn log_activity(env: &Env, product: &Product, actor: &Address, action: Symbol) {
+    if &product.owner == actor {
+        return;
+    }
+    let count: u32 = env.storage().persistent().get(&TrackingKey::ActivityCount(product.id)).unwrap_or(0);
+    if count >= MAX_ACTIVITY_LOG {
+        let overflow: u32 = env.storage().persistent().get(&TrackingKey::ActivityOverflow(product.id)).unwrap_or(0);
+        env.storage().persistent().set(&TrackingKey::ActivityOverflow(product.id), &(overflow + 1));
+        return;
+    }
+    let entry = ActivityEntry {
+        seq: count + 1,
+        actor: actor.clone(),
+        action,
+        timestamp: env.ledger().timestamp(),
+    };
+    env.storage().persistent().set(&TrackingKey::ActivityLog(product.id, entry.seq), &entry);
+    env.storage().persistent().set(&TrackingKey::ActivityCount(product.id), &entry.seq);
+}
+
+/// Counts the running entry point as one mutation. Every state-changing
+/// entry point calls this first and exactly once, so the audit entries and
+/// events it writes all carry the same event seq.
+fn count_mutation(env: &Env) {
+    storage::next_event_seq(env);
+}
+
+/// Appends to the product's audit log, the single chronological record of
+/// every change made to the product, whoever made it. The entry carries the
+/// event seq of the call that wrote it.
+fn audit(env: &Env, product_id: u64, action: Symbol, actor: &Address, ref_id: u64) {
+    let seq: u32 = env.storage().persistent().get(&TrackingKey::AuditCount(product_id)).unwrap_or(0) + 1;
+    let entry = AuditEntry {
+        event_seq: storage::get_event_seq(env),
+        action,
+        actor: actor.clone(),
+        timestamp: env.ledger().timestamp(),
+        ref_id,
+    };
+    env.storage().persistent().set(&TrackingKey::AuditLog(product_id, seq), &entry);
+    env.storage().persistent().set(&TrackingKey::AuditCount(product_id), &seq);
+}
+
+/// Writes the next feed item over the oldest slot of the ring buffer.
+fn push_recent_activity(env: &Env, kind: Symbol, product_id: u64, actor: &Address) {
+    let size = read_config(env).recent_activity_size;
+    if size == 0 {
+        return;
+    }
+    let seq: u64 = env.storage().instance().get::<IndexKey, u64>(&IndexKey::RecentHead).unwrap_or(0) + 1;
+    let item = ActivityItem {
+        seq,
+        kind,
+        product_id,
+        actor: actor.clone(),
+        timestamp: env.ledger().timestamp(),
+    };
+    env.storage().persistent().set(&IndexKey::RecentEvent((seq % size as u64) as u32), &item);
+    env.storage().instance().set(&IndexKey::RecentHead, &seq);
+}
+
+fn raise_alert(env: &Env, product_id: u64, alert: u32) {
+    storage::set_alerts(env, product_id, storage::get_alerts(env, product_id) | alert);
+}
+
+fn clear_alert(env: &Env, product_id: u64, alert: u32) {
+    storage::set_alerts(env, product_id, storage::get_alerts(env, product_id) & !alert);
+}
+
+fn mark_delivered(env: &Env, product_id: u64) {
+    env.storage().persistent().set(&DataKey::Delivered(product_id), &true);
+    storage::set_status(env, product_id, Some(ProductStatus::Delivered));
+    clear_alert(env, product_id, ALERT_OVERDUE);
+}
+
+/// Raises the overdue alert once the delivery deadline has passed on a
+/// product that is still undelivered.
+fn flag_if_overdue(env: &Env, product_id: u64) {
+    if env.storage().persistent().get(&DataKey::Delivered(product_id)).unwrap_or(false) {
+        return;
+    }
+    let deadline: Option<u64> = env.storage().persistent().get(&TrackingKey::DeliveryDeadline(product_id));
+    if deadline.is_some_and(|deadline| env.ledger().timestamp() > deadline) {
+        raise_alert(env, product_id, ALERT_OVERDUE);
+    }
+}
+
+fn require_no_excursion(env: &Env, product_id: u64) -> Result<(), Error> {
+    if env.storage().persistent().get(&TrackingKey::Excursion(product_id)).unwrap_or(false) {
+        return Err(Error::TemperatureExcursion);
+    }
+    Ok(())
+}
+
+/// Loads a product, telling apart ids that were never issued, products
+/// that were burned, and records that are gone otherwise (archived).
+fn read_product(env: &Env, product_id: u64) -> Result<Product, Error> {
+    if let Some(product) = storage::get_product(env, product_id) {
+        return Ok(product);
+    }
+    if env.storage().persistent().has(&LifecycleKey::Burned(product_id)) {
+        return Err(Error::ProductBurned);
+    }
+    let total: u64 = env.storage().instance().get(&DataKey::TotalProducts).unwrap_or(0);
+    if product_id >= 1 && product_id <= total {
+        return Err(Error::ProductArchived);
+    }
+    Err(Error::ProductNotFound)
+}
+
+fn write_product(env: &Env, product: &Product) {
+    storage::put_product(env, product);
+}
+
+fn require_authorized(env: &Env, product: &Product, caller: &Address) -> Result<(), Error> {
+    require_not_paused(env)?;
+    caller.require_auth();
+    require_not_renounced(env, product.id)?;
+    require_not_blacklisted(env, &product.owner)?;
+    require_not_blacklisted(env, caller)?;
+    if &product.owner == caller || storage::has_role(env, product.id, caller, Role::Updater) {
+        return Ok(());
+    }
+    Err(Error::Unauthorized)
+}
+
+/// The authorization matrix behind `can` and the entry points that check
+/// an action, so the two cannot disagree:
+/// - TRANSFER: the owner, the approved agent or a Transferrer, while the
+///   product is unlocked and no co-owner signatures are needed
+/// - CHECKPOINT, INSPECT: the owner, Updaters (including co-owners, global
+///   actors and the custodian) and operators holding that scope
+/// - UPDATE_META: the owner
+fn check_permission(env: &Env, product: &Product, actor: &Address, action: &Symbol) -> Result<(), Error> {
+    require_not_paused(env)?;
+    require_not_renounced(env, product.id)?;
+    require_not_blacklisted(env, &product.owner)?;
+    require_not_blacklisted(env, actor)?;
+    let is_owner = &product.owner == actor;
+    if action == &Symbol::new(env, ACTION_TRANSFER) {
+        let approved = env.storage().persistent().get::<TransferKey, Address>(&TransferKey::Agent(product.id)).as_ref() == Some(actor);
+        if !is_owner && !approved && !storage::has_role(env, product.id, actor, Role::Transferrer) {
+            return Err(Error::Unauthorized);
+        }
+        require_single_signer(env, product)?;
+        return require_transfer_state(env, product);
+    }
+    if action == &Symbol::new(env, ACTION_CHECKPOINT) || action == &Symbol::new(env, ACTION_INSPECT) {
+        if is_owner || storage::has_role(env, product.id, actor, Role::Updater) || storage::is_operator(env, actor, action) {
+            return Ok(());
+        }
+        return Err(Error::Unauthorized);
+    }
+    if action == &Symbol::new(env, ACTION_UPDATE_META) {
+        if is_owner {
+            return Ok(());
+        }
+        return Err(Error::Unauthorized);
+    }
+    Err(Error::InvalidInput)
+}
+
+fn require_permission(env: &Env, product: &Product, actor: &Address, action: &str) -> Result<(), Error> {
+    actor.require_auth();
+    check_permission(env, product, actor, &Symbol::new(env, action))
+}
+
+fn read_config(env: &Env) -> Config {
+    env.storage().instance().get(&AdminKey::Config).unwrap_or(Config {
+        max_metadata_len: validation::DEFAULT_MAX_METADATA_LEN,
+        max_page_limit: DEFAULT_MAX_PAGE_LIMIT,
+        max_actors: DEFAULT_MAX_ACTORS,
+        max_batch_size: DEFAULT_MAX_TRANSFER_BATCH,
+        read_ttl_extend_to: 0,
+        max_registrations_per_window: 0,
+        registration_window_secs: 0,
+        recent_activity_size: DEFAULT_RECENT_ACTIVITY,
+    })
+}
+
+/// Draws the fee from the owner's sponsor while their sponsorship lasts,
+/// through the allowance the sponsor gave this contract, and from the
+/// owner otherwise.
+fn pay_registration_fee(env: &Env, owner: &Address, fee: &RegistrationFee) -> Result<(), Error> {
+    let client = token::Client::new(env, &fee.token);
+    let key = FeeKey::Sponsorship(owner.clone());
+    let paid = match env.storage().persistent().get::<FeeKey, Sponsorship>(&key) {
+        Some(mut sponsorship) => {
+            sponsorship.remaining -= 1;
+            if sponsorship.remaining == 0 {
+                env.storage().persistent().remove(&key);
+            } else {
+                env.storage().persistent().set(&key, &sponsorship);
+            }
+            client.try_transfer_from(&env.current_contract_address(), &sponsorship.sponsor, &fee.recipient, &fee.amount)
+        }
+        None => client.try_transfer(owner, &fee.recipient, &fee.amount),
+    };
+    if !matches!(paid, Ok(Ok(()))) {
+        return Err(Error::FeePaymentFailed);
+    }
+    Ok(())
+}
+
+/// Counts a registration against the owner's current window. A new window
+/// opens on the first registration after the previous one has run out.
+fn record_registration(env: &Env, owner: &Address) -> Result<(), Error> {
+    let config = read_config(env);
+    if config.max_registrations_per_window == 0
+        || env.storage().persistent().get(&AdminKey::RateLimitExempt(owner.clone())).unwrap_or(false)
+    {
+        return Ok(());
+    }
+    let now = env.ledger().timestamp();
+    let key = AdminKey::RegWindow(owner.clone());
+    let (mut window_start, mut count): (u64, u32) = env.storage().persistent().get(&key).unwrap_or((now, 0));
+    if now >= window_start.saturating_add(config.registration_window_secs) {
+        window_start = now;
+        count = 0;
+    }
+    if count >= config.max_registrations_per_window {
+        return Err(Error::RateLimited);
+    }
+    env.storage().persistent().set(&key, &(window_start, count + 1));
+    Ok(())
+}
+
+/// Opportunistic TTL bump for product reads, when the config enables it.
+fn touch_product(env: &Env, product: &Product) {
+    let extend_to = read_config(env).read_ttl_extend_to;
+    if extend_to > 0 {
+        storage::extend_product_ttl(env, product, extend_to.min(env.storage().max_ttl()));
+    }
+}
+
+/// Clamps a requested page size to the configured maximum. Listings treat
+/// a zero limit as an empty page rather than an error.
+fn page_limit(env: &Env, limit: u64) -> u64 {
+    limit.min(read_config(env).max_page_limit as u64)
+}
+
+/// While paused, every state-changing entry point except the admin's own
+/// fails; views keep working.
+fn require_not_paused(env: &Env) -> Result<(), Error> {
+    require_not_sunset(env)?;
+    if env.storage().instance().get(&AdminKey::Paused).unwrap_or(false) {
+        return Err(Error::ContractPaused);
+    }
+    Ok(())
+}
+
+/// A sunset deployment refuses every state change, the admin's included.
+fn require_not_sunset(env: &Env) -> Result<(), Error> {
+    if env.storage().instance().get(&AdminKey::Sunset).unwrap_or(false) {
+        return Err(Error::ContractSunset);
+    }
+    Ok(())
+}
+
+/// Renounced products stay readable but accept no further changes.
+fn require_not_renounced(env: &Env, product_id: u64) -> Result<(), Error> {
+    if env.storage().persistent().get(&TransferKey::Renounced(product_id)).unwrap_or(false) {
+        return Err(Error::ProductRenounced);
+    }
+    Ok(())
+}
+
+fn require_origin_allowed(env: &Env, origin: &String) -> Result<(), Error> {
+    let enforced: bool = env.storage().instance().get(&DataKey::OriginWhitelistEnforced).unwrap_or(false);
+    if enforced && !env.storage().persistent().has(&DataKey::AllowedOrigin(origin.clone())) {
+        return Err(Error::OriginNotAllowed);
+    }
+    Ok(())
+}
+
+/// Blacklisted addresses cannot register, send, receive or be granted
+/// access, and their products are frozen until they are cleared.
+fn require_not_blacklisted(env: &Env, address: &Address) -> Result<(), Error> {
+    if env.storage().persistent().get(&AdminKey::Blacklisted(address.clone())).unwrap_or(false) {
+        return Err(Error::AddressBlacklisted);
+    }
+    Ok(())
+}
+
+fn read_registrars(env: &Env) -> Vec<Address> {
+    env.storage().persistent().get(&AdminKey::Registrars).unwrap_or(Vec::new(env))
+}
+
+fn require_registrar(env: &Env, owner: &Address) -> Result<(), Error> {
+    if !env.storage().instance().get(&AdminKey::RegistrationRestricted).unwrap_or(false) {
+        return Ok(());
+    }
+    if !read_registrars(env).contains(owner) {
+        return Err(Error::NotRegistrar);
+    }
+    Ok(())
+}
+
+fn require_admin(env: &Env, admin: &Address) -> Result<(), Error> {
+    require_not_sunset(env)?;
+    admin.require_auth();
+    let stored: Address = env.storage().instance().get(&DataKey::Admin).ok_or(Error::NotInitialized)?;
+    if &stored != admin {
+        return Err(Error::Unauthorized);
+    }
+    Ok(())
+}
+
+/// A new grant needs a free slot; expired grants awaiting a purge do not
+/// hold one. Changing an existing actor's role always fits. Blacklisted
+/// actors cannot be granted anything.
+fn require_actor_slot(env: &Env, product_id: u64, actor: &Address) -> Result<(), Error> {
+    require_not_blacklisted(env, actor)?;
+    let actors = storage::get_actors(env, product_id);
+    if actors.contains(actor) {
+        return Ok(());
+    }
+    let max = read_config(env).max_actors;
+    let active = actors.iter().filter(|a| !storage::is_expired(env, product_id, a)).count() as u32;
+    if active >= max {
+        return Err(Error::TooManyActors);
+    }
+    Ok(())
+}
+
+/// Owners who opted into invitations cannot attach actors unilaterally.
+fn require_direct_grants(env: &Env, owner: &Address) -> Result<(), Error> {
+    if env.storage().persistent().get(&AuthKey::InviteOnly(owner.clone())).unwrap_or(false) {
+        return Err(Error::Unauthorized);
+    }
+    Ok(())
+}
+
+/// Changes to co-ownership need every current owner's signature.
+fn require_all_owners(env: &Env, product: &Product, owner: &Address) -> Result<Vec<Address>, Error> {
+    require_owner(env, product, owner)?;
+    let co_owners = storage::get_co_owners(env, product.id);
+    for co_owner in co_owners.iter() {
+        co_owner.require_auth();
+    }
+    Ok(co_owners)
+}
+
+fn require_owner(env: &Env, product: &Product, caller: &Address) -> Result<(), Error> {
+    require_not_paused(env)?;
+    caller.require_auth();
+    require_not_renounced(env, product.id)?;
+    require_not_blacklisted(env, &product.owner)?;
+    if &product.owner != caller {
+        return Err(Error::Unauthorized);
+    }
+    Ok(())
+}
+
+#[contractimpl]
+impl ChainLogisticsContract {
+    pub fn add_authorized_actor(env: Env, owner: Address, product_id: u64, actor: Address) -> Result<(), Error> {
+        count_mutation(&env);
+        let product = read_product(&env, product_id)?;
+        require_owner(&env, &product, &owner)?;
+        require_direct_grants(&env, &owner)?;
+        require_actor_slot(&env, product_id, &actor)?;
+        audit(&env, product_id, symbol_short!("grant"), &owner, 0);
+        storage::set_role(&env, product_id, &actor, Role::Updater);
+        Ok(())
+    }
+
+    /// Offer `actor` a role on the product. The grant only takes effect
+    /// once the actor accepts; a new invitation replaces a pending one.
+    pub fn invite_actor(env: Env, owner: Address, product_id: u64, actor: Address, role: Role) -> Result<(), Error> {
+        count_mutation(&env);
+        let product = read_product(&env, product_id)?;
+        require_owner(&env, &product, &owner)?;
+        if actor == owner {
+            return Err(Error::InvalidInput);
+        }
+        require_not_blacklisted(&env, &actor)?;
+        audit(&env, product_id, symbol_short!("invite"), &owner, 0);
+        env.storage().persistent().set(&AuthKey::Invite(product_id, actor), &role);
+        Ok(())
+    }
+
+    /// Accept a pending invitation, activating the offered role
+    pub fn accept_invitation(env: Env, actor: Address, product_id: u64) -> Result<(), Error> {
+        count_mutation(&env);
+        require_not_paused(&env)?;
+        actor.require_auth();
+        let product = read_product(&env, product_id)?;
+        require_not_renounced(&env, product.id)?;
+        let key = AuthKey::Invite(product_id, actor.clone());
+        let role: Role = env.storage().persistent().get(&key).ok_or(Error::InvalidInput)?;
+        env.storage().persistent().remove(&key);
+        require_actor_slot(&env, product_id, &actor)?;
+        audit(&env, product_id, symbol_short!("grant"), &actor, 0);
+        storage::set_role(&env, product_id, &actor, role);
+        Ok(())
+    }
+
+    pub fn decline_invitation(env: Env, actor: Address, product_id: u64) -> Result<(), Error> {
+        count_mutation(&env);
+        require_not_paused(&env)?;
+        actor.require_auth();
+        let key = AuthKey::Invite(product_id, actor.clone());
+        if !env.storage().persistent().has(&key) {
+            return Err(Error::InvalidInput);
+        }
+        env.storage().persistent().remove(&key);
+        audit(&env, product_id, symbol_short!("decline"), &actor, 0);
+        Ok(())
+    }
+
+    pub fn cancel_invitation(env: Env, owner: Address, product_id: u64, actor: Address) -> Result<(), Error> {
+        count_mutation(&env);
+        let product = read_product(&env, product_id)?;
+        require_owner(&env, &product, &owner)?;
+        env.storage().persistent().remove(&AuthKey::Invite(product_id, actor));
+        audit(&env, product_id, symbol_short!("uninvite"), &owner, 0);
+        Ok(())
+    }
+
+    /// The role offered to `actor`, if an invitation is pending
+    pub fn get_invitation(env: Env, product_id: u64, actor: Address) -> Option<Role> {
+        env.storage().persistent().get(&AuthKey::Invite(product_id, actor))
+    }
+
+    /// When enabled, actors can only join the owner's products through
+    /// `invite_actor`; the direct grant entry points are refused.
+    pub fn set_invitations_required(env: Env, owner: Address, required: bool) -> Result<(), Error> {
+        count_mutation(&env);
+        require_not_paused(&env)?;
+        owner.require_auth();
+        env.storage().persistent().set(&AuthKey::InviteOnly(owner), &required);
+        Ok(())
+    }
+
+    /// Get the products `actor` holds a per-product grant on, with
+    /// pagination over the actor's index slots. Revoked and expired grants
+    /// are skipped, so a page may hold fewer than `limit` items; global
+    /// grants are listed by `get_global_actors` instead.
+    pub fn get_products_for_actor(env: Env, actor: Address, start: u64, limit: u64) -> Vec<Product> {
+        let limit = page_limit(&env, limit);
+        let count: u64 = env.storage().persistent().get(&AuthKey::ActorProductCount(actor.clone())).unwrap_or(0);
+        let end_index = start.saturating_add(limit).min(count);
+        let mut products = Vec::new(&env);
+        for i in start.saturating_add(1)..=end_index {
+            let Some(product_id) = env.storage().persistent().get::<AuthKey, u64>(&AuthKey::ActorProductIndex(actor.clone(), i)) else {
+                continue;
+            };
+            if !storage::is_authorized(&env, product_id, &actor) {
+                continue;
+            }
+            if let Some(product) = storage::get_product(&env, product_id) {
+                touch_product(&env, &product);
+                products.push_back(product);
+            }
+        }
+        products
+    }
+
+    /// Authorize `actor` as an Updater on every product `owner` holds, now
+    /// or later. Access follows ownership: it ends for products the owner
+    /// transfers away.
+    pub fn add_global_actor(env: Env, owner: Address, actor: Address) -> Result<(), Error> {
+        count_mutation(&env);
+        require_not_paused(&env)?;
+        owner.require_auth();
+        require_direct_grants(&env, &owner)?;
+        if actor == owner {
+            return Err(Error::InvalidInput);
+        }
+        require_not_blacklisted(&env, &owner)?;
+        require_not_blacklisted(&env, &actor)?;
+        storage::set_global_auth(&env, &owner, &actor, true);
+        Ok(())
+    }
+
+    pub fn remove_global_actor(env: Env, owner: Address, actor: Address) -> Result<(), Error> {
+        count_mutation(&env);
+        require_not_paused(&env)?;
+        owner.require_auth();
+        storage::set_global_auth(&env, &owner, &actor, false);
+        Ok(())
+    }
+
+    /// Get the owner's globally authorized actors with pagination
+    pub fn get_global_actors(env: Env, owner: Address, start: u32, limit: u32) -> Vec<Address> {
+        let actors = storage::get_global_actors(&env, &owner);
+        let end_index = start.saturating_add(limit).min(actors.len());
+        if start >= end_index {
+            return Vec::new(&env);
+        }
+        actors.slice(start..end_index)
+    }
+
+    /// Revoke every actor on the product at once and return how many were
+    /// removed. The custodian is not a grant and is left in place.
+    pub fn revoke_all_actors(env: Env, owner: Address, product_id: u64) -> Result<u32, Error> {
+        count_mutation(&env);
+        let product = read_product(&env, product_id)?;
+        require_owner(&env, &product, &owner)?;
+        audit(&env, product_id, symbol_short!("revoke"), &owner, 0);
+        Ok(storage::clear_auth(&env, product_id))
+    }
+
+    /// Grant `actor` Updater access that lapses once the ledger passes
+    /// `expires_at`. The record stays until removed or purged.
+    pub fn add_authorized_actor_until(env: Env, owner: Address, product_id: u64, actor: Address, expires_at: u64) -> Result<(), Error> {
+        count_mutation(&env);
+        let product = read_product(&env, product_id)?;
+        require_owner(&env, &product, &owner)?;
+        require_direct_grants(&env, &owner)?;
+        if expires_at < env.ledger().timestamp() {
+            return Err(Error::InvalidTimestamp);
+        }
+        require_actor_slot(&env, product_id, &actor)?;
+        audit(&env, product_id, symbol_short!("grant"), &owner, 0);
+        storage::set_role_until(&env, product_id, &actor, Role::Updater, expires_at);
+        Ok(())
+    }
+
+    /// Drop every expired grant on the product and return how many went.
+    /// Anyone may call this; it only removes grants that no longer apply.
+    pub fn purge_expired_auth(env: Env, product_id: u64) -> Result<u32, Error> {
+        count_mutation(&env);
+        require_not_paused(&env)?;
+        read_product(&env, product_id)?;
+        let mut purged = 0;
+        for actor in storage::get_actors(&env, product_id).iter() {
+            if storage::is_expired(&env, product_id, &actor) {
+                if purged == 0 {
+                    audit(&env, product_id, symbol_short!("purge"), &env.current_contract_address(), 0);
+                }
+                storage::set_auth(&env, product_id, &actor, false);
+                purged += 1;
+            }
+        }
+        Ok(purged)
+    }
+
+    /// Grant `actor` a specific role, replacing any role it held before.
+    pub fn add_authorized_actor_with_role(env: Env, owner: Address, product_id: u64, actor: Address, role: Role) -> Result<(), Error> {
+        count_mutation(&env);
+        let product = read_product(&env, product_id)?;
+        require_owner(&env, &product, &owner)?;
+        require_direct_grants(&env, &owner)?;
+        require_actor_slot(&env, product_id, &actor)?;
+        audit(&env, product_id, symbol_short!("grant"), &owner, 0);
+        storage::set_role(&env, product_id, &actor, role);
+        Ok(())
+    }
+
+    /// The role granted to `actor`; `None` for the owner and custodian,
+    /// who act by virtue of their position rather than a grant.
+    pub fn get_role(env: Env, product_id: u64, actor: Address) -> Option<Role> {
+        storage::get_role(&env, product_id, &actor)
+    }
+
+    /// True when the actor's grant covers `role`
+    pub fn has_role(env: Env, product_id: u64, actor: Address, role: Role) -> bool {
+        storage::has_role(&env, product_id, &actor, role)
+    }
+
+    pub fn remove_authorized_actor(env: Env, owner: Address, product_id: u64, actor: Address) -> Result<(), Error> {
+        count_mutation(&env);
+        let product = read_product(&env, product_id)?;
+        require_owner(&env, &product, &owner)?;
+        audit(&env, product_id, symbol_short!("revoke"), &owner, 0);
+        storage::set_auth(&env, product_id, &actor, false);
+        Ok(())
+    }
+
+    pub fn transfer_product(env: Env, owner: Address, product_id: u64, new_owner: Address) -> Result<(), Error> {
+        let memo = String::from_str(&env, "");
+        Self::transfer_product_with_memo(env, owner, product_id, new_owner, memo)
+    }
+
+    /// Transfer with a memo (PO number, shipment id) kept in the transfer history
+    pub fn transfer_product_with_memo(env: Env, owner: Address, product_id: u64, new_owner: Address, memo: String) -> Result<(), Error> {
+        count_mutation(&env);
+        let mut product = read_product(&env, product_id)?;
+        require_owner(&env, &product, &owner)?;
+        if !validation::max_len(&memo, MAX_MEMO_LEN) {
+            return Err(Error::MemoTooLong);
+        }
+        if product.owner == new_owner {
+            return Err(Error::SelfTransfer);
+        }
+        require_not_blacklisted(&env, &new_owner)?;
+        check_permission(&env, &product, &owner, &Symbol::new(&env, ACTION_TRANSFER))?;
+
+        new_owner.require_auth();
+
+        reassign_owner(&env, &mut product, new_owner, memo);
+        Ok(())
+    }
+
+    /// Reassign a product without its owner's consent, e.g. on a court
+    /// order. The reason is kept as the memo of a history entry marked as
+    /// forced, and the admin is recorded against it.
+    pub fn admin_transfer(env: Env, admin: Address, product_id: u64, new_owner: Address, reason: String) -> Result<(), Error> {
+        count_mutation(&env);
+        require_admin(&env, &admin)?;
+        let mut product = read_product(&env, product_id)?;
+        require_not_renounced(&env, product_id)?;
+        if reason.len() < MIN_FORCED_REASON_LEN {
+            return Err(Error::InvalidInput);
+        }
+        if !validation::max_len(&reason, MAX_MEMO_LEN) {
+            return Err(Error::MemoTooLong);
+        }
+        if product.owner == new_owner {
+            return Err(Error::SelfTransfer);
+        }
+        require_not_blacklisted(&env, &new_owner)?;
+
+        let seq = reassign_owner(&env, &mut product, new_owner, reason);
+        let key = TransferKey::History(product_id, seq);
+        let mut record: TransferRecord = env.storage().persistent().get(&key).ok_or(Error::ProductNotFound)?;
+        record.forced = true;
+        env.storage().persistent().set(&key, &record);
+        env.storage().persistent().set(&TransferKey::ForcedBy(product_id, seq), &admin);
+        Ok(())
+    }
+
+    /// The admin behind a forced transfer, or `None` for a regular one
+    pub fn get_forced_by(env: Env, product_id: u64, seq: u32) -> Option<Address> {
+        env.storage().persistent().get(&TransferKey::ForcedBy(product_id, seq))
+    }
+
+    /// Share ownership with `co_owner`, who must sign as well as every
+    /// current owner. Co-owners act as Updaters; `Product.owner` stays the
+    /// primary owner for indexing.
+    pub fn add_co_owner(env: Env, owner: Address, product_id: u64, co_owner: Address) -> Result<(), Error> {
+        count_mutation(&env);
+        let product = read_product(&env, product_id)?;
+        let mut co_owners = require_all_owners(&env, &product, &owner)?;
+        co_owner.require_auth();
+        if co_owner == owner || co_owners.contains(&co_owner) {
+            return Err(Error::InvalidInput);
+        }
+        if co_owners.len() >= MAX_CO_OWNERS {
+            return Err(Error::TooManyActors);
+        }
+        audit(&env, product_id, symbol_short!("coowner"), &owner, 0);
+        co_owners.push_back(co_owner);
+        env.storage().persistent().set(&AuthKey::CoOwners(product_id), &co_owners);
+        Ok(())
+    }
+
+    /// Remove a co-owner with every current owner's signature. A threshold
+    /// that no longer fits is lowered to the remaining number of owners,
+    /// and dropped with the last co-owner.
+    pub fn remove_co_owner(env: Env, owner: Address, product_id: u64, co_owner: Address) -> Result<(), Error> {
+        count_mutation(&env);
+        let product = read_product(&env, product_id)?;
+        let mut co_owners = require_all_owners(&env, &product, &owner)?;
+        let pos = co_owners.first_index_of(&co_owner).ok_or(Error::InvalidInput)?;
+        co_owners.remove(pos);
+        audit(&env, product_id, symbol_short!("rmcoowner"), &owner, 0);
+        if co_owners.is_empty() {
+            env.storage().persistent().remove(&AuthKey::CoOwners(product_id));
+            env.storage().persistent().remove(&AuthKey::CoOwnerThreshold(product_id));
+            return Ok(());
+        }
+        env.storage().persistent().set(&AuthKey::CoOwners(product_id), &co_owners);
+        let owners = co_owners.len() + 1;
+        if storage::co_owner_threshold(&env, product_id) > owners {
+            env.storage().persistent().set(&AuthKey::CoOwnerThreshold(product_id), &owners);
+        }
+        Ok(())
+    }
+
+    /// Set how many owners must sign a transfer, with every current
+    /// owner's signature
+    pub fn set_co_owner_threshold(env: Env, owner: Address, product_id: u64, threshold: u32) -> Result<(), Error> {
+        count_mutation(&env);
+        let product = read_product(&env, product_id)?;
+        let co_owners = require_all_owners(&env, &product, &owner)?;
+        if threshold == 0 || threshold > co_owners.len() + 1 {
+            return Err(Error::InvalidInput);
+        }
+        env.storage().persistent().set(&AuthKey::CoOwnerThreshold(product_id), &threshold);
+        audit(&env, product_id, symbol_short!("threshold"), &owner, 0);
+        Ok(())
+    }
+
+    pub fn get_co_owners(env: Env, product_id: u64) -> Vec<Address> {
+        storage::get_co_owners(&env, product_id)
+    }
+
+    pub fn get_co_owner_threshold(env: Env, product_id: u64) -> u32 {
+        storage::co_owner_threshold(&env, product_id)
+    }
+
+    /// Transfer a co-owned product once at least the threshold number of
+    /// distinct owners sign. Co-owners do not carry over to `new_owner`.
+    pub fn transfer_jointly(env: Env, signers: Vec<Address>, product_id: u64, new_owner: Address) -> Result<(), Error> {
+        count_mutation(&env);
+        require_not_paused(&env)?;
+        let mut product = read_product(&env, product_id)?;
+        let co_owners = storage::get_co_owners(&env, product_id);
+        for (i, signer) in signers.iter().enumerate() {
+            if signers.first_index_of(&signer) != Some(i as u32) {
+                return Err(Error::InvalidInput);
+            }
+            if signer != product.owner && !co_owners.contains(&signer) {
+                return Err(Error::Unauthorized);
+            }
+            require_not_blacklisted(&env, &signer)?;
+            signer.require_auth();
+        }
+        if signers.len() < storage::co_owner_threshold(&env, product_id) {
+            return Err(Error::Unauthorized);
+        }
+        require_transfer_guards(&env, &product, &new_owner)?;
+
+        new_owner.require_auth();
+
+        reassign_owner(&env, &mut product, new_owner, String::from_str(&env, ""));
+        Ok(())
+    }
+
+    /// Transfer and revoke every actor grant in the same call, so the new
+    /// owner starts without inherited access
+    pub fn transfer_and_clear_actors(env: Env, owner: Address, product_id: u64, new_owner: Address) -> Result<(), Error> {
+        count_mutation(&env);
+        let mut product = read_product(&env, product_id)?;
+        require_owner(&env, &product, &owner)?;
+        require_transferable(&env, &product, &new_owner)?;
+
+        new_owner.require_auth();
+
+        reassign_owner(&env, &mut product, new_owner, String::from_str(&env, ""));
+        storage::clear_auth(&env, product_id);
+        Ok(())
+    }
+
+    /// When set, every way of transferring the product, including
+    /// `accept_transfer`, also revokes its actor grants. Off by default.
+    pub fn set_clear_actors_on_transfer(env: Env, owner: Address, product_id: u64, clear: bool) -> Result<(), Error> {
+        count_mutation(&env);
+        let product = read_product(&env, product_id)?;
+        require_owner(&env, &product, &owner)?;
+        env.storage().persistent().set(&AuthKey::ClearOnTransfer(product_id), &clear);
+        audit(&env, product_id, symbol_short!("autoclear"), &owner, 0);
+        Ok(())
+    }
+
+    /// Require checkpoints of the given kinds (e.g. CustomsOut) before the
+    /// product may change hands. An empty list clears the requirement.
+    pub fn set_transfer_requirements(env: Env, owner: Address, product_id: u64, required_kinds: Vec<CheckpointKind>) -> Result<(), Error> {
+        count_mutation(&env);
+        let product = read_product(&env, product_id)?;
+        require_owner(&env, &product, &owner)?;
+        if required_kinds.is_empty() {
+            env.storage().persistent().remove(&TransferKey::Requirements(product_id));
+        } else {
+            env.storage().persistent().set(&TransferKey::Requirements(product_id), &required_kinds);
+        }
+        audit(&env, product_id, symbol_short!("requires"), &owner, 0);
+        Ok(())
+    }
+
+    /// Get the checkpoint kinds required before a transfer
+    pub fn get_transfer_requirements(env: Env, product_id: u64) -> Vec<CheckpointKind> {
+        env.storage().persistent().get(&TransferKey::Requirements(product_id)).unwrap_or(Vec::new(&env))
+    }
+
+    /// Transfer with a dispute window: for `dispute_window_secs` the
+    /// recipient may `reject_transfer` and the product cannot move again.
+    pub fn transfer_product_with_window(env: Env, owner: Address, product_id: u64, new_owner: Address, dispute_window_secs: u64) -> Result<(), Error> {
+        Self::transfer_product(env.clone(), owner.clone(), product_id, new_owner.clone())?;
+        let window = TransferWindow {
+            from: owner,
+            to: new_owner,
+            final_at: env.ledger().timestamp().saturating_add(dispute_window_secs),
+        };
+        env.storage().persistent().set(&TransferKey::Window(product_id), &window);
+        Ok(())
+    }
+
+    /// Send the product back to the sender within the dispute window. The
+    /// reason is kept as the memo of the reversing transfer.
+    pub fn reject_transfer(env: Env, recipient: Address, product_id: u64, reason: String) -> Result<(), Error> {
+        count_mutation(&env);
+        let mut product = read_product(&env, product_id)?;
+        require_owner(&env, &product, &recipient)?;
+        let window: TransferWindow = env
+            .storage()
+            .persistent()
+            .get(&TransferKey::Window(product_id))
+            .ok_or(Error::NoPendingTransfer)?;
+        if is_transfer_final(&env, product_id) {
+            return Err(Error::TransferExpired);
+        }
+        if !validation::max_len(&reason, MAX_MEMO_LEN) {
+            return Err(Error::MemoTooLong);
+        }
+        require_not_blacklisted(&env, &window.from)?;
+        reassign_owner(&env, &mut product, window.from, reason);
+        Ok(())
+    }
+
+    /// True unless the latest transfer is still inside its dispute window
+    pub fn get_transfer_finality(env: Env, product_id: u64) -> bool {
+        is_transfer_final(&env, product_id)
+    }
+
+    /// Correct a product's origin. The product leaves the old origin's index
+    /// (its slot is tombstoned) and is appended to the new origin's index.
+    pub fn update_origin(env: Env, owner: Address, product_id: u64, new_origin: String) -> Result<(), Error> {
+        count_mutation(&env);
+        let mut product = read_product(&env, product_id)?;
+        require_permission(&env, &product, &owner, ACTION_UPDATE_META)?;
+
+        validation::validate_origin(&new_origin)?;
+        if product.origin == new_origin {
+            return Ok(());
+        }
+        require_origin_allowed(&env, &new_origin)?;
+        if read_origin_chain(&env, product_id).contains(&new_origin) {
+            return Err(Error::InvalidInput);
+        }
+
+        storage::remove_origin_index(&env, &product.origin, product_id);
+        storage::append_origin_index(&env, &new_origin, product_id);
+        storage::remove_owner_origin_index(&env, &product.owner, &product.origin, product_id);
+        storage::append_owner_origin_index(&env, &product.owner, &new_origin, product_id);
+        let old_slot = storage::note_origin(&env, &product.origin);
+        let new_slot = storage::note_origin(&env, &new_origin);
+        let active = u64::from(product.active);
+        let transferred = u64::from(Self::get_transfer_count(env.clone(), product_id) > 0);
+        let old_stats = update_origin_stats(&env, &product.origin, |stats| {
+            stats.total_registered = stats.total_registered.saturating_sub(1);
+            stats.active = stats.active.saturating_sub(active);
+            stats.transferred = stats.transferred.saturating_sub(transferred);
+        });
+        let new_stats = update_origin_stats(&env, &new_origin, |stats| {
+            stats.total_registered += 1;
+            stats.active += active;
+            stats.transferred += transferred;
+        });
+        rank_origin(&env, &product.origin, old_stats.total_registered, old_slot);
+        rank_origin(&env, &new_origin, new_stats.total_registered, new_slot);
+
+        product.origin = new_origin;
+        write_product(&env, &product);
+        audit(&env, product_id, symbol_short!("origin"), &owner, 0);
+        Ok(())
+    }
+
+    /// Append a further origin (e.g. the processing country) to the product's
+    /// origin chain. The primary `origin` field is unchanged.
+    pub fn append_origin(env: Env, owner: Address, product_id: u64, origin: String) -> Result<(), Error> {
+        count_mutation(&env);
+        let product = read_product(&env, product_id)?;
+        require_permission(&env, &product, &owner, ACTION_UPDATE_META)?;
+        validation::validate_origin(&origin)?;
+        require_origin_allowed(&env, &origin)?;
+
+        let mut chain = read_origin_chain(&env, product_id);
+        if product.origin == origin || chain.contains(&origin) {
+            return Err(Error::InvalidInput);
+        }
+        if chain.len() + 1 >= MAX_ORIGIN_CHAIN_LEN {
+            return Err(Error::InvalidInput);
+        }
+
+        let mut count: u64 = env.storage().persistent().get(&DataKey::AnyOriginCount(origin.clone())).unwrap_or(0);
+        count += 1;
+        env.storage().persistent().set(&DataKey::AnyOriginIndex(origin.clone(), count), &product_id);
+        env.storage().persistent().set(&DataKey::AnyOriginCount(origin.clone()), &count);
+
+        audit(&env, product_id, symbol_short!("addorigin"), &owner, 0);
+        chain.push_back(origin);
+        env.storage().persistent().set(&DataKey::OriginChain(product_id), &chain);
+        Ok(())
+    }
+
+    /// Set or replace a product's GTIN. The check digit is validated and the
+    /// product moves from its previous GTIN's index, if any, to the new one.
+    pub fn set_gtin(env: Env, owner: Address, product_id: u64, gtin: String) -> Result<(), Error> {
+        count_mutation(&env);
+        let product = read_product(&env, product_id)?;
+        require_permission(&env, &product, &owner, ACTION_UPDATE_META)?;
+
+        if !validation::is_valid_gtin(&gtin) {
+            return Err(Error::InvalidGtin);
+        }
+
+        if let Some((old, slot)) = env.storage().persistent().get::<DataKey, (String, u64)>(&DataKey::ProductGtin(product_id)) {
+            if old == gtin {
+                return Ok(());
+            }
+            env.storage().persistent().remove(&DataKey::GtinIndex(old, slot));
+        }
+
+        let mut count: u64 = env.storage().persistent().get(&DataKey::GtinCount(gtin.clone())).unwrap_or(0);
+        count += 1;
+        env.storage().persistent().set(&DataKey::GtinIndex(gtin.clone(), count), &product_id);
+        env.storage().persistent().set(&DataKey::GtinCount(gtin.clone()), &count);
+        audit(&env, product_id, symbol_short!("gtin"), &owner, 0);
+        env.storage().persistent().set(&DataKey::ProductGtin(product_id), &(gtin, count));
+        Ok(())
+    }
+
+    /// Assign a product to a batch/lot, moving it out of its previous lot's index
+    pub fn set_lot(env: Env, owner: Address, product_id: u64, lot: String) -> Result<(), Error> {
+        count_mutation(&env);
+        let product = read_product(&env, product_id)?;
+        require_permission(&env, &product, &owner, ACTION_UPDATE_META)?;
+
+        if !validation::non_empty(&lot) {
+            return Err(Error::InvalidInput);
+        }
+
+        if let Some((old, slot)) = env.storage().persistent().get::<DataKey, (String, u64)>(&DataKey::ProductLot(product_id)) {
+            if old == lot {
+                return Ok(());
+            }
+            env.storage().persistent().remove(&DataKey::LotIndex(old, slot));
+        }
+
+        let mut count: u64 = env.storage().persistent().get(&DataKey::LotCount(lot.clone())).unwrap_or(0);
+        count += 1;
+        env.storage().persistent().set(&DataKey::LotIndex(lot.clone(), count), &product_id);
+        env.storage().persistent().set(&DataKey::LotCount(lot.clone()), &count);
+        audit(&env, product_id, symbol_short!("lot"), &owner, 0);
+        env.storage().persistent().set(&DataKey::ProductLot(product_id), &(lot, count));
+        Ok(())
+    }
+
+    /// Attach a tag to a product. Adding a tag the product already has is a no-op.
+    pub fn add_tag(env: Env, owner: Address, product_id: u64, tag: Symbol) -> Result<(), Error> {
+        count_mutation(&env);
+        let product = read_product(&env, product_id)?;
+        require_permission(&env, &product, &owner, ACTION_UPDATE_META)?;
+        tag_product(&env, product_id, tag)?;
+        audit(&env, product_id, symbol_short!("tag"), &owner, 0);
+        Ok(())
+    }
+
+    /// Detach a tag from a product, tombstoning its slot in the tag index
+    pub fn remove_tag(env: Env, owner: Address, product_id: u64, tag: Symbol) -> Result<(), Error> {
+        count_mutation(&env);
+        let product = read_product(&env, product_id)?;
+        require_permission(&env, &product, &owner, ACTION_UPDATE_META)?;
+
+        let mut tags = storage::get_product_tags(&env, product_id);
+        if let Some(slot) = tags.get(tag.clone()) {
+            env.storage().persistent().remove(&DataKey::TagProductIndex(tag.clone(), slot));
+            tags.remove(tag);
+            storage::put_product_tags(&env, product_id, &tags);
+            audit(&env, product_id, symbol_short!("untag"), &owner, 0);
+        }
+        Ok(())
+    }
+
+    /// Permanently remove a product. The id is never reused and stays in the
+    /// indexes as a gap that listings skip; the serial (if any) is freed.
+    pub fn burn_product(env: Env, owner: Address, product_id: u64) -> Result<(), Error> {
+        count_mutation(&env);
+        let product = read_product(&env, product_id)?;
+        require_owner(&env, &product, &owner)?;
+        burn(&env, &product);
+        Ok(())
+    }
+
+    /// Whether `actor` may perform `action` (TRANSFER, CHECKPOINT, INSPECT
+    /// or UPDATE_META) on the product right now. Uses the same checks as the
+    /// entry points themselves; signatures aside, a `true` here means the
+    /// call will pass authorization.
+    pub fn can(env: Env, actor: Address, product_id: u64, action: Symbol) -> bool {
+        match storage::get_product(&env, product_id) {
+            Some(product) => check_permission(&env, &product, &actor, &action).is_ok(),
+            None => false,
+        }
+    }
+
+    pub fn is_authorized(env: Env, product_id: u64, actor: Address) -> Result<bool, Error> {
+        let product = read_product(&env, product_id)?;
+        if product.owner == actor {
             return Ok(true);
         }
-        Ok(storage::is_authorized(&env, &product_id, &actor))
+        Ok(storage::is_authorized(&env, product_id, &actor))
+    }
+}
+
+#[contractimpl]
+impl ChainLogisticsContract {
+    /// Set the contract admin. Can only be called once.
+    pub fn initialize(env: Env, admin: Address, name: String) -> Result<(), Error> {
+        count_mutation(&env);
+        if env.storage().instance().has(&DataKey::Admin) {
+            return Err(Error::AlreadyInitialized);
+        }
+        admin.require_auth();
+        if !validation::max_len(&name, MAX_CONTRACT_NAME_LEN) {
+            return Err(Error::InvalidInput);
+        }
+        env.storage().instance().set(&DataKey::Admin, &admin);
+        env.storage().instance().set(&AdminKey::Name, &name);
+        env.storage().instance().set(&AdminKey::ContractVersion, &CONTRACT_VERSION);
+        Ok(())
+    }
+
+    pub fn set_contract_name(env: Env, admin: Address, name: String) -> Result<(), Error> {
+        count_mutation(&env);
+        require_admin(&env, &admin)?;
+        if !validation::max_len(&name, MAX_CONTRACT_NAME_LEN) {
+            return Err(Error::InvalidInput);
+        }
+        env.storage().instance().set(&AdminKey::Name, &name);
+        Ok(())
+    }
+
+    /// Name, versions, admin, switches and fee of this deployment in one call
+    pub fn get_contract_info(env: Env) -> ContractInfo {
+        let storage = env.storage().instance();
+        let mut admin = Vec::new(&env);
+        if let Some(current) = storage.get::<DataKey, Address>(&DataKey::Admin) {
+            admin.push_back(current);
+        }
+        let mut fee = Vec::new(&env);
+        if let Some(current) = storage.get::<AdminKey, RegistrationFee>(&AdminKey::Fee) {
+            fee.push_back(current);
+        }
+        ContractInfo {
+            name: storage.get(&AdminKey::Name).unwrap_or(String::from_str(&env, "")),
+            version: String::from_str(&env, CONTRACT_SEMVER),
+            data_version: storage.get(&AdminKey::ContractVersion).unwrap_or(0),
+            admin,
+            paused: storage.get(&AdminKey::Paused).unwrap_or(false),
+            sunset: storage.get(&AdminKey::Sunset).unwrap_or(false),
+            fee,
+        }
+    }
+
+    /// Swap in new contract code. Storage is kept; call `migrate` afterwards
+    /// so the data catches up with the new code.
+    pub fn upgrade(env: Env, admin: Address, new_wasm_hash: BytesN<32>) -> Result<(), Error> {
+        count_mutation(&env);
+        require_admin(&env, &admin)?;
+        env.deployer().update_current_contract_wasm(new_wasm_hash);
+        Ok(())
+    }
+
+    /// Run each data migration between the stored version and this build's,
+    /// then record the new version. Does nothing when already current.
+    pub fn migrate(env: Env, admin: Address) -> Result<u32, Error> {
+        count_mutation(&env);
+        require_admin(&env, &admin)?;
+        let stored: u32 = env.storage().instance().get(&AdminKey::ContractVersion).unwrap_or(0);
+        if stored < CONTRACT_VERSION {
+            // Per-version rewrites go here, oldest first, each guarded by
+            // `stored`. Version 1 only introduced the version marker itself.
+            env.storage().instance().set(&AdminKey::ContractVersion, &CONTRACT_VERSION);
+        }
+        Ok(stored.max(CONTRACT_VERSION))
+    }
+
+    /// Check a range of global index slots against the products they point
+    /// at. Slots for missing products are tombstoned, and owner and origin
+    /// slots that do not point back at their product are rewritten.
+    pub fn repair_indexes(env: Env, admin: Address, start: u64, limit: u64) -> Result<RepairReport, Error> {
+        count_mutation(&env);
+        require_admin(&env, &admin)?;
+        let limit = limit.min(read_config(&env).max_batch_size as u64);
+        let total: u64 = env.storage().instance().get(&DataKey::TotalProducts).unwrap_or(0);
+        let end_index = start.saturating_add(limit).min(total);
+        let mut report = RepairReport { checked: 0, fixed: 0, removed: 0, next_start: end_index.max(start) };
+        for i in start.saturating_add(1)..=end_index {
+            report.checked += 1;
+            let persistent = env.storage().persistent();
+            let product_id = match persistent.get::<DataKey, u64>(&DataKey::AllProductsIndex(i)) {
+                Some(product_id) => product_id,
+                // Slot i was written for product i at registration
+                None if persistent.has(&DataKey::Product(i)) => {
+                    persistent.set(&DataKey::AllProductsIndex(i), &i);
+                    report.fixed += 1;
+                    i
+                }
+                None => continue,
+            };
+            let Some(product) = storage::get_product(&env, product_id) else {
+                persistent.remove(&DataKey::AllProductsIndex(i));
+                persistent.remove(&DataKey::OwnerIndexPos(product_id));
+                persistent.remove(&DataKey::OriginIndexPos(product_id));
+                report.removed += 1;
+                continue;
+            };
+            let owner_slot = persistent
+                .get::<DataKey, u64>(&DataKey::OwnerIndexPos(product_id))
+                .and_then(|pos| persistent.get::<DataKey, u64>(&DataKey::OwnerProductIndex(product.owner.clone(), pos)));
+            if owner_slot != Some(product_id) {
+                storage::append_owner_index(&env, &product.owner, product_id);
+                report.fixed += 1;
+            }
+            let origin_slot = persistent
+                .get::<DataKey, u64>(&DataKey::OriginIndexPos(product_id))
+                .and_then(|pos| persistent.get::<DataKey, u64>(&DataKey::OriginProductIndex(product.origin.clone(), pos)));
+            if origin_slot != Some(product_id) {
+                storage::append_origin_index(&env, &product.origin, product_id);
+                report.fixed += 1;
+            }
+        }
+        Ok(report)
+    }
+
+    /// 0 for deployments that predate versioning and have not migrated yet
+    pub fn version(env: Env) -> u32 {
+        env.storage().instance().get(&AdminKey::ContractVersion).unwrap_or(0)
+    }
+
+    /// Stop all state changes except the admin's own, e.g. during an incident
+    pub fn pause(env: Env, admin: Address) -> Result<(), Error> {
+        count_mutation(&env);
+        require_admin(&env, &admin)?;
+        env.storage().instance().set(&AdminKey::Paused, &true);
+        Ok(())
+    }
+
+    pub fn unpause(env: Env, admin: Address) -> Result<(), Error> {
+        count_mutation(&env);
+        require_admin(&env, &admin)?;
+        env.storage().instance().remove(&AdminKey::Paused);
+        Ok(())
+    }
+
+    pub fn is_paused(env: Env) -> bool {
+        env.storage().instance().get(&AdminKey::Paused).unwrap_or(false)
+    }
+
+    /// Freeze this deployment for good: every later state change fails while
+    /// reads keep working. Cannot be undone, not even by the admin.
+    pub fn sunset(env: Env, admin: Address, successor: Option<Address>) -> Result<(), Error> {
+        count_mutation(&env);
+        require_admin(&env, &admin)?;
+        env.storage().instance().set(&AdminKey::Sunset, &true);
+        if let Some(successor) = successor {
+            env.storage().instance().set(&AdminKey::Successor, &successor);
+        }
+        Ok(())
+    }
+
+    pub fn is_sunset(env: Env) -> bool {
+        env.storage().instance().get(&AdminKey::Sunset).unwrap_or(false)
+    }
+
+    /// Where clients should go once this deployment has been sunset
+    pub fn get_successor(env: Env) -> Option<Address> {
+        env.storage().instance().get(&AdminKey::Successor)
+    }
+
+    /// Offer the admin role to `new_admin`. The current admin keeps full
+    /// rights until the new one accepts; a new proposal replaces the old.
+    pub fn propose_admin(env: Env, current_admin: Address, new_admin: Address) -> Result<(), Error> {
+        count_mutation(&env);
+        require_admin(&env, &current_admin)?;
+        env.storage().instance().set(&AdminKey::PendingAdmin, &new_admin);
+        Ok(())
+    }
+
+    pub fn accept_admin(env: Env, new_admin: Address) -> Result<(), Error> {
+        count_mutation(&env);
+        require_not_sunset(&env)?;
+        new_admin.require_auth();
+        let pending: Address = env.storage().instance().get(&AdminKey::PendingAdmin).ok_or(Error::InvalidInput)?;
+        if pending != new_admin {
+            return Err(Error::Unauthorized);
+        }
+        env.storage().instance().remove(&AdminKey::PendingAdmin);
+        env.storage().instance().set(&DataKey::Admin, &new_admin);
+        Ok(())
+    }
+
+    pub fn cancel_admin_proposal(env: Env, admin: Address) -> Result<(), Error> {
+        count_mutation(&env);
+        require_admin(&env, &admin)?;
+        env.storage().instance().remove(&AdminKey::PendingAdmin);
+        Ok(())
+    }
+
+    pub fn get_pending_admin(env: Env) -> Option<Address> {
+        env.storage().instance().get(&AdminKey::PendingAdmin)
+    }
+
+    /// The admin set by `initialize`, if the contract has been initialized
+    pub fn get_admin(env: Env) -> Option<Address> {
+        env.storage().instance().get(&DataKey::Admin)
+    }
+
+    /// Approve an address to issue certifications
+    pub fn add_certifier(env: Env, admin: Address, certifier: Address) -> Result<(), Error> {
+        count_mutation(&env);
+        require_admin(&env, &admin)?;
+        env.storage().persistent().set(&DataKey::Certifier(certifier), &true);
+        Ok(())
+    }
+
+    /// Withdraw a certifier's approval. Certifications it already issued are kept.
+    pub fn remove_certifier(env: Env, admin: Address, certifier: Address) -> Result<(), Error> {
+        count_mutation(&env);
+        require_admin(&env, &admin)?;
+        env.storage().persistent().remove(&DataKey::Certifier(certifier));
+        Ok(())
+    }
+
+    pub fn is_certifier(env: Env, certifier: Address) -> bool {
+        env.storage().persistent().get(&DataKey::Certifier(certifier)).unwrap_or(false)
+    }
+
+    /// Attach a certification issued by an approved certifier. Returns its sequence number.
+    pub fn add_certification(
+        env: Env,
+        certifier: Address,
+        product_id: u64,
+        cert_type: Symbol,
+        expires_at: u64,
+        evidence_hash: BytesN<32>,
+    ) -> Result<u32, Error> {
+        count_mutation(&env);
+        require_not_paused(&env)?;
+        certifier.require_auth();
+        read_product(&env, product_id)?;
+        require_not_renounced(&env, product_id)?;
+        if !Self::is_certifier(env.clone(), certifier.clone()) {
+            return Err(Error::Unauthorized);
+        }
+        let now = env.ledger().timestamp();
+        if expires_at <= now {
+            return Err(Error::InvalidTimestamp);
+        }
+
+        let count: u32 = env.storage().persistent().get(&DataKey::CertificationCount(product_id)).unwrap_or(0);
+        if count >= MAX_CERTIFICATIONS_PER_PRODUCT {
+            return Err(Error::TooManyCertifications);
+        }
+        let seq = count + 1;
+        audit(&env, product_id, symbol_short!("certify"), &certifier, seq.into());
+        let certification = Certification {
+            seq,
+            cert_type,
+            certifier,
+            issued_at: now,
+            expires_at,
+            evidence_hash,
+            revoked: false,
+        };
+        env.storage().persistent().set(&DataKey::Certification(product_id, seq), &certification);
+        env.storage().persistent().set(&DataKey::CertificationCount(product_id), &seq);
+        Ok(seq)
+    }
+
+    /// Revoke a certification. Only the certifier that issued it may revoke it.
+    pub fn revoke_certification(env: Env, certifier: Address, product_id: u64, cert_seq: u32) -> Result<(), Error> {
+        count_mutation(&env);
+        require_not_paused(&env)?;
+        certifier.require_auth();
+        let key = DataKey::Certification(product_id, cert_seq);
+        let mut certification: Certification = env.storage().persistent().get(&key).ok_or(Error::InvalidInput)?;
+        if certification.certifier != certifier {
+            return Err(Error::Unauthorized);
+        }
+        certification.revoked = true;
+        env.storage().persistent().set(&key, &certification);
+        audit(&env, product_id, symbol_short!("uncertify"), &certifier, cert_seq.into());
+        Ok(())
+    }
+
+    /// Get a product's certifications (including revoked and expired ones) in issue order
+    pub fn get_certifications(env: Env, product_id: u64, start: u32, limit: u32) -> Vec<Certification> {
+        let count: u32 = env.storage().persistent().get(&DataKey::CertificationCount(product_id)).unwrap_or(0);
+        let mut certifications = Vec::new(&env);
+
+        let start_index = start.saturating_add(1);
+        let end_index = start.saturating_add(limit).min(count);
+
+        for seq in start_index..=end_index {
+            if let Some(certification) = env.storage().persistent().get::<DataKey, Certification>(&DataKey::Certification(product_id, seq)) {
+                certifications.push_back(certification);
+            }
+        }
+        certifications
+    }
+
+    /// True if the product holds a certification of `cert_type` that is
+    /// neither revoked nor past its expiry at the current ledger time
+    pub fn has_valid_certification(env: Env, product_id: u64, cert_type: Symbol) -> bool {
+        let count: u32 = env.storage().persistent().get(&DataKey::CertificationCount(product_id)).unwrap_or(0);
+        let now = env.ledger().timestamp();
+        for seq in 1..=count {
+            if let Some(certification) = env.storage().persistent().get::<DataKey, Certification>(&DataKey::Certification(product_id, seq)) {
+                if certification.cert_type == cert_type && !certification.revoked && now < certification.expires_at {
+                    return true;
+                }
+            }
+        }
+        false
+    }
+}
+
+#[contractimpl]
+impl ChainLogisticsContract {
+    /// Mark every product in a lot as recalled. Lots can span owners, so this
+    /// is an admin action. Walks `limit` slots of the lot index starting after
+    /// `start` so large lots can be recalled over several calls, and returns
+    /// how many products were newly recalled.
+    pub fn recall_by_lot(env: Env, admin: Address, lot: String, start: u64, limit: u64) -> Result<u64, Error> {
+        count_mutation(&env);
+        require_admin(&env, &admin)?;
+
+        let count: u64 = env.storage().persistent().get(&DataKey::LotCount(lot.clone())).unwrap_or(0);
+        let end_index = start.saturating_add(limit).min(count);
+        let mut recalled = 0;
+
+        for i in start.saturating_add(1)..=end_index {
+            if let Some(product_id) = env.storage().persistent().get::<DataKey, u64>(&DataKey::LotIndex(lot.clone(), i)) {
+                if let Some(mut product) = storage::get_product(&env, product_id) {
+                    if !product.recalled {
+                        product.recalled = true;
+                        write_product(&env, &product);
+                        recalled += 1;
+                        audit(&env, product_id, symbol_short!("recall"), &admin, 0);
+                    }
+                }
+            }
+        }
+        Ok(recalled)
+    }
+}
+
+#[contractimpl]
+impl ChainLogisticsContract {
+    /// Set the quantity a product represents. A unit (KG, L, UNIT, PALLET, ...)
+    /// is always required alongside the amount.
+    pub fn set_quantity(env: Env, owner: Address, product_id: u64, amount: u64, unit: Symbol) -> Result<(), Error> {
+        count_mutation(&env);
+        let product = read_product(&env, product_id)?;
+        require_owner(&env, &product, &owner)?;
+        env.storage().persistent().set(&DataKey::Quantity(product_id), &Quantity { amount, unit });
+        audit(&env, product_id, symbol_short!("quantity"), &owner, 0);
+        Ok(())
+    }
+
+    pub fn get_quantity(env: Env, product_id: u64) -> Option<Quantity> {
+        env.storage().persistent().get(&DataKey::Quantity(product_id))
+    }
+
+    /// Draw down a product's quantity. The caller must state the unit it is
+    /// consuming in, which has to match the stored unit. Returns the remaining amount.
+    pub fn consume(env: Env, actor: Address, product_id: u64, amount: u64, unit: Symbol) -> Result<u64, Error> {
+        count_mutation(&env);
+        let product = read_product(&env, product_id)?;
+        require_authorized(&env, &product, &actor)?;
+        log_activity(&env, &product, &actor, symbol_short!("CONSUME"));
+
+        let mut quantity: Quantity = env.storage().persistent().get(&DataKey::Quantity(product_id)).ok_or(Error::InvalidInput)?;
+        if quantity.unit != unit {
+            return Err(Error::UnitMismatch);
+        }
+        quantity.amount = quantity.amount.checked_sub(amount).ok_or(Error::InvalidInput)?;
+        env.storage().persistent().set(&DataKey::Quantity(product_id), &quantity);
+        audit(&env, product_id, symbol_short!("consume"), &actor, 0);
+        Ok(quantity.amount)
+    }
+
+    /// Fold the quantity of `from_id` into `into_id` and burn `from_id`. Both
+    /// products must belong to the caller, be measured in the same unit and
+    /// be free of the locks that rule out a transfer.
+    pub fn merge_products(env: Env, owner: Address, into_id: u64, from_id: u64) -> Result<u64, Error> {
+        count_mutation(&env);
+        if into_id == from_id {
+            return Err(Error::InvalidInput);
+        }
+        let into = read_product(&env, into_id)?;
+        let from = read_product(&env, from_id)?;
+        require_owner(&env, &into, &owner)?;
+        require_unlocked(&env, &into)?;
+        require_unlocked(&env, &from)?;
+        if from.owner != owner {
+            return Err(Error::Unauthorized);
+        }
+
+        let mut into_qty: Quantity = env.storage().persistent().get(&DataKey::Quantity(into_id)).ok_or(Error::InvalidInput)?;
+        let from_qty: Quantity = env.storage().persistent().get(&DataKey::Quantity(from_id)).ok_or(Error::InvalidInput)?;
+        if into_qty.unit != from_qty.unit {
+            return Err(Error::UnitMismatch);
+        }
+        into_qty.amount = into_qty.amount.checked_add(from_qty.amount).ok_or(Error::InvalidInput)?;
+
+        env.storage().persistent().set(&DataKey::Quantity(into_id), &into_qty);
+        audit(&env, into_id, symbol_short!("merge"), &owner, from_id);
+        burn(&env, &from);
+        Ok(into_qty.amount)
+    }
+}
+
+fn read_media(env: &Env, product_id: u64) -> Vec<MediaRef> {
+    env.storage()
+        .persistent()
+        .get(&DataKey::Media(product_id))
+        .unwrap_or(Vec::new(env))
+}
+
+#[contractimpl]
+impl ChainLogisticsContract {
+    /// Record the hash of a photo, video or scan of the product. The owner and
+    /// authorized actors may append; adding a hash already on record is a no-op.
+    pub fn add_media(env: Env, actor: Address, product_id: u64, media_hash: BytesN<32>, kind: Symbol) -> Result<(), Error> {
+        count_mutation(&env);
+        let product = read_product(&env, product_id)?;
+        require_permission(&env, &product, &actor, ACTION_INSPECT)?;
+        log_activity(&env, &product, &actor, symbol_short!("MEDIA"));
+
+        let mut media = read_media(&env, product_id);
+        if media.iter().any(|m| m.hash == media_hash) {
+            return Ok(());
+        }
+        if media.len() >= MAX_MEDIA_PER_PRODUCT {
+            return Err(Error::TooManyMediaHashes);
+        }
+        audit(&env, product_id, symbol_short!("media"), &actor, 0);
+        media.push_back(MediaRef {
+            hash: media_hash,
+            kind,
+            added_by: actor,
+            added_at: env.ledger().timestamp(),
+        });
+        env.storage().persistent().set(&DataKey::Media(product_id), &media);
+        Ok(())
+    }
+
+    /// Remove a media hash. Only the owner may remove entries.
+    pub fn remove_media(env: Env, owner: Address, product_id: u64, media_hash: BytesN<32>) -> Result<(), Error> {
+        count_mutation(&env);
+        let product = read_product(&env, product_id)?;
+        require_owner(&env, &product, &owner)?;
+
+        let mut media = read_media(&env, product_id);
+        if let Some(i) = media.iter().position(|m| m.hash == media_hash) {
+            media.remove(i as u32);
+            env.storage().persistent().set(&DataKey::Media(product_id), &media);
+            audit(&env, product_id, symbol_short!("unmedia"), &owner, 0);
+        }
+        Ok(())
+    }
+
+    pub fn get_media(env: Env, product_id: u64) -> Vec<MediaRef> {
+        read_media(&env, product_id)
+    }
+
+    /// True if `hash` is one of the media hashes registered for the product
+    pub fn verify_media(env: Env, product_id: u64, hash: BytesN<32>) -> bool {
+        read_media(&env, product_id).iter().any(|m| m.hash == hash)
+    }
+}
+
+#[contractimpl]
+impl ChainLogisticsContract {
+    /// Add an origin to the controlled vocabulary
+    pub fn add_allowed_origin(env: Env, admin: Address, origin: String) -> Result<(), Error> {
+        count_mutation(&env);
+        require_admin(&env, &admin)?;
+        if env.storage().persistent().has(&DataKey::AllowedOrigin(origin.clone())) {
+            return Ok(());
+        }
+        let mut count: u64 = env.storage().instance().get(&DataKey::AllowedOriginCount).unwrap_or(0);
+        count += 1;
+        env.storage().persistent().set(&DataKey::AllowedOriginIndex(count), &origin);
+        env.storage().persistent().set(&DataKey::AllowedOrigin(origin), &count);
+        env.storage().instance().set(&DataKey::AllowedOriginCount, &count);
+        Ok(())
+    }
+
+    /// Remove an origin from the whitelist. Existing products keep their origin.
+    pub fn remove_allowed_origin(env: Env, admin: Address, origin: String) -> Result<(), Error> {
+        count_mutation(&env);
+        require_admin(&env, &admin)?;
+        if let Some(slot) = env.storage().persistent().get::<DataKey, u64>(&DataKey::AllowedOrigin(origin.clone())) {
+            env.storage().persistent().remove(&DataKey::AllowedOriginIndex(slot));
+            env.storage().persistent().remove(&DataKey::AllowedOrigin(origin));
+        }
+        Ok(())
+    }
+
+    /// Appoint `operator` to act on every product within `scope`:
+    /// CHECKPOINT to log checkpoints, INSPECT to add notes, media and
+    /// sensor readings. Ownership transfers are never delegated this way.
+    pub fn add_operator(env: Env, admin: Address, operator: Address, scope: Symbol) -> Result<(), Error> {
+        count_mutation(&env);
+        require_admin(&env, &admin)?;
+        if scope != Symbol::new(&env, ACTION_CHECKPOINT) && scope != Symbol::new(&env, ACTION_INSPECT) {
+            return Err(Error::InvalidInput);
+        }
+        storage::set_operator_scope(&env, &operator, &scope, true);
+        Ok(())
+    }
+
+    pub fn remove_operator(env: Env, admin: Address, operator: Address, scope: Symbol) -> Result<(), Error> {
+        count_mutation(&env);
+        require_admin(&env, &admin)?;
+        storage::set_operator_scope(&env, &operator, &scope, false);
+        Ok(())
+    }
+
+    pub fn is_operator(env: Env, address: Address, scope: Symbol) -> bool {
+        storage::is_operator(&env, &address, &scope)
+    }
+
+    pub fn get_operator_scopes(env: Env, operator: Address) -> Vec<Symbol> {
+        storage::get_operator_scopes(&env, &operator)
+    }
+
+    /// Get operators holding at least one scope, with pagination
+    pub fn get_operators(env: Env, start: u32, limit: u32) -> Vec<Address> {
+        let operators = storage::get_operators(&env);
+        let end_index = start.saturating_add(limit).min(operators.len());
+        if start >= end_index {
+            return Vec::new(&env);
+        }
+        operators.slice(start..end_index)
+    }
+
+    /// Sever a compromised actor: walks a chunk of the actor's product index
+    /// from `start` and revokes each grant, which publishes the usual auth
+    /// revocation event per product. Operator scopes are dropped on the
+    /// first chunk. Global grants are keyed by owner and are left for the
+    /// owners to remove. Returns how many grants this call revoked.
+    pub fn emergency_revoke(env: Env, admin: Address, actor: Address, start: u64, limit: u64) -> Result<u32, Error> {
+        count_mutation(&env);
+        require_admin(&env, &admin)?;
+        if start == 0 {
+            for scope in storage::get_operator_scopes(&env, &actor).iter() {
+                storage::set_operator_scope(&env, &actor, &scope, false);
+            }
+        }
+
+        let count: u64 = env.storage().persistent().get(&AuthKey::ActorProductCount(actor.clone())).unwrap_or(0);
+        let end_index = start.saturating_add(limit).min(count);
+        let mut revoked = 0;
+        for i in start.saturating_add(1)..=end_index {
+            if let Some(product_id) = env.storage().persistent().get::<AuthKey, u64>(&AuthKey::ActorProductIndex(actor.clone(), i)) {
+                audit(&env, product_id, symbol_short!("revoke"), &admin, 0);
+                storage::set_auth(&env, product_id, &actor, false);
+                revoked += 1;
+            }
+        }
+        Ok(revoked)
+    }
+
+    /// Set how many actors a product may have active grants for at once
+    pub fn set_max_actors(env: Env, admin: Address, max: u32) -> Result<(), Error> {
+        let mut config = read_config(&env);
+        config.max_actors = max;
+        Self::set_config(env, admin, config)
+    }
+
+    pub fn get_max_actors(env: Env) -> u32 {
+        read_config(&env).max_actors
+    }
+
+    /// Replace the contract's limits. Every limit must be non-zero, and
+    /// metadata may not exceed `MAX_METADATA_LEN_CEILING` bytes.
+    pub fn set_config(env: Env, admin: Address, config: Config) -> Result<(), Error> {
+        count_mutation(&env);
+        require_admin(&env, &admin)?;
+        if config.max_metadata_len == 0
+            || config.max_metadata_len > MAX_METADATA_LEN_CEILING
+            || config.max_page_limit == 0
+            || config.max_actors == 0
+            || config.max_batch_size == 0
+            || config.read_ttl_extend_to > env.storage().max_ttl()
+            || (config.max_registrations_per_window > 0 && config.registration_window_secs == 0)
+            || config.recent_activity_size > MAX_RECENT_ACTIVITY
+        {
+            return Err(Error::InvalidInput);
+        }
+        env.storage().instance().set(&AdminKey::Config, &config);
+        Ok(())
+    }
+
+    /// Pay the registration fee for `beneficiary`'s next `max_registrations`
+    /// registrations. Replaces any allowance this sponsor gave them before.
+    pub fn set_sponsor(env: Env, sponsor: Address, beneficiary: Address, max_registrations: u32) -> Result<(), Error> {
+        count_mutation(&env);
+        require_not_paused(&env)?;
+        sponsor.require_auth();
+        if max_registrations == 0 || sponsor == beneficiary {
+            return Err(Error::InvalidInput);
+        }
+        let key = FeeKey::Sponsorship(beneficiary);
+        if let Some(current) = env.storage().persistent().get::<FeeKey, Sponsorship>(&key) {
+            if current.sponsor != sponsor {
+                return Err(Error::Unauthorized);
+            }
+        }
+        env.storage().persistent().set(&key, &Sponsorship { sponsor, remaining: max_registrations });
+        Ok(())
+    }
+
+    pub fn revoke_sponsorship(env: Env, sponsor: Address, beneficiary: Address) -> Result<(), Error> {
+        count_mutation(&env);
+        require_not_paused(&env)?;
+        sponsor.require_auth();
+        let key = FeeKey::Sponsorship(beneficiary);
+        let current: Sponsorship = env.storage().persistent().get(&key).ok_or(Error::InvalidInput)?;
+        if current.sponsor != sponsor {
+            return Err(Error::Unauthorized);
+        }
+        env.storage().persistent().remove(&key);
+        Ok(())
+    }
+
+    pub fn get_sponsorship(env: Env, beneficiary: Address) -> Option<Sponsorship> {
+        env.storage().persistent().get(&FeeKey::Sponsorship(beneficiary))
+    }
+
+    /// Let an address, typically a trusted registrar, register without the
+    /// per-window limit
+    pub fn set_rate_limit_exempt(env: Env, admin: Address, address: Address, exempt: bool) -> Result<(), Error> {
+        count_mutation(&env);
+        require_admin(&env, &admin)?;
+        if exempt {
+            env.storage().persistent().set(&AdminKey::RateLimitExempt(address), &true);
+        } else {
+            env.storage().persistent().remove(&AdminKey::RateLimitExempt(address));
+        }
+        Ok(())
+    }
+
+    pub fn is_rate_limit_exempt(env: Env, address: Address) -> bool {
+        env.storage().persistent().get(&AdminKey::RateLimitExempt(address)).unwrap_or(false)
+    }
+
+    pub fn blacklist(env: Env, admin: Address, address: Address) -> Result<(), Error> {
+        count_mutation(&env);
+        require_admin(&env, &admin)?;
+        env.storage().persistent().set(&AdminKey::Blacklisted(address), &true);
+        Ok(())
+    }
+
+    pub fn unblacklist(env: Env, admin: Address, address: Address) -> Result<(), Error> {
+        count_mutation(&env);
+        require_admin(&env, &admin)?;
+        env.storage().persistent().remove(&AdminKey::Blacklisted(address));
+        Ok(())
+    }
+
+    pub fn is_blacklisted(env: Env, address: Address) -> bool {
+        env.storage().persistent().get(&AdminKey::Blacklisted(address)).unwrap_or(false)
+    }
+
+    /// Vet an address to register products while registration is restricted
+    pub fn add_registrar(env: Env, admin: Address, registrar: Address) -> Result<(), Error> {
+        count_mutation(&env);
+        require_admin(&env, &admin)?;
+        let mut registrars = read_registrars(&env);
+        if !registrars.contains(&registrar) {
+            registrars.push_back(registrar);
+            env.storage().persistent().set(&AdminKey::Registrars, &registrars);
+        }
+        Ok(())
+    }
+
+    pub fn remove_registrar(env: Env, admin: Address, registrar: Address) -> Result<(), Error> {
+        count_mutation(&env);
+        require_admin(&env, &admin)?;
+        let mut registrars = read_registrars(&env);
+        if let Some(pos) = registrars.first_index_of(&registrar) {
+            registrars.remove(pos);
+            env.storage().persistent().set(&AdminKey::Registrars, &registrars);
+        }
+        Ok(())
+    }
+
+    pub fn is_registrar(env: Env, address: Address) -> bool {
+        read_registrars(&env).contains(&address)
+    }
+
+    /// Get the vetted registrars with pagination
+    pub fn get_registrars(env: Env, start: u32, limit: u32) -> Vec<Address> {
+        let registrars = read_registrars(&env);
+        let end_index = start.saturating_add(limit).min(registrars.len());
+        if start >= end_index {
+            return Vec::new(&env);
+        }
+        registrars.slice(start..end_index)
+    }
+
+    /// When restricted, only registrars may register products
+    pub fn set_registration_restricted(env: Env, admin: Address, restricted: bool) -> Result<(), Error> {
+        count_mutation(&env);
+        require_admin(&env, &admin)?;
+        env.storage().instance().set(&AdminKey::RegistrationRestricted, &restricted);
+        Ok(())
+    }
+
+    pub fn is_registration_restricted(env: Env) -> bool {
+        env.storage().instance().get(&AdminKey::RegistrationRestricted).unwrap_or(false)
+    }
+
+    /// Charge `amount` of `token`, paid to `recipient`, for each
+    /// registration. A zero amount makes registration free again.
+    pub fn set_fee(env: Env, admin: Address, token: Address, amount: i128, recipient: Address) -> Result<(), Error> {
+        count_mutation(&env);
+        require_admin(&env, &admin)?;
+        if amount < 0 {
+            return Err(Error::InvalidInput);
+        }
+        if amount == 0 {
+            env.storage().instance().remove(&AdminKey::Fee);
+        } else {
+            env.storage().instance().set(&AdminKey::Fee, &RegistrationFee { token, amount, recipient });
+        }
+        Ok(())
+    }
+
+    /// The fee token and amount per registration; `(None, 0)` when free
+    pub fn get_fee(env: Env) -> (Option<Address>, i128) {
+        match env.storage().instance().get::<AdminKey, RegistrationFee>(&AdminKey::Fee) {
+            Some(fee) => (Some(fee.token), fee.amount),
+            None => (None, 0),
+        }
+    }
+
+    /// The limits in force, defaults included
+    pub fn get_config(env: Env) -> Config {
+        read_config(&env)
+    }
+
+    /// When enforced, registrations and origin updates must use a whitelisted origin
+    pub fn set_origin_whitelist_enforced(env: Env, admin: Address, enforced: bool) -> Result<(), Error> {
+        count_mutation(&env);
+        require_admin(&env, &admin)?;
+        env.storage().instance().set(&DataKey::OriginWhitelistEnforced, &enforced);
+        Ok(())
+    }
+
+    pub fn is_origin_whitelist_enforced(env: Env) -> bool {
+        env.storage().instance().get(&DataKey::OriginWhitelistEnforced).unwrap_or(false)
+    }
+
+    pub fn is_origin_allowed(env: Env, origin: String) -> bool {
+        env.storage().persistent().has(&DataKey::AllowedOrigin(origin))
+    }
+
+    /// List whitelisted origins. `start` is a slot offset; removed origins are skipped.
+    pub fn get_allowed_origins(env: Env, start: u64, limit: u64) -> Vec<String> {
+        let count: u64 = env.storage().instance().get(&DataKey::AllowedOriginCount).unwrap_or(0);
+        let mut origins = Vec::new(&env);
+        let end_index = start.saturating_add(limit).min(count);
+        for i in start.saturating_add(1)..=end_index {
+            if let Some(origin) = env.storage().persistent().get::<DataKey, String>(&DataKey::AllowedOriginIndex(i)) {
+                origins.push_back(origin);
+            }
+        }
+        origins
+    }
+}
+
+fn read_template(env: &Env, template_id: u64) -> Result<ProductTemplate, Error> {
+    env.storage()
+        .persistent()
+        .get(&DataKey::Template(template_id))
+        .ok_or(Error::InvalidInput)
+}
+
+#[contractimpl]
+impl ChainLogisticsContract {
+    /// Store a reusable origin/metadata/category template. Returns its id.
+    pub fn create_template(env: Env, owner: Address, origin: String, metadata: String, category: Symbol) -> Result<u64, Error> {
+        count_mutation(&env);
+        require_not_paused(&env)?;
+        owner.require_auth();
+        validation::validate_origin(&origin)?;
+        validation::validate_metadata(&metadata, read_config(&env).max_metadata_len)?;
+
+        let mut template_id: u64 = env.storage().instance().get(&DataKey::TemplateCount).unwrap_or(0);
+        template_id += 1;
+        let template = ProductTemplate {
+            id: template_id,
+            creator: owner.clone(),
+            origin,
+            metadata,
+            category,
+        };
+        env.storage().persistent().set(&DataKey::Template(template_id), &template);
+        env.storage().instance().set(&DataKey::TemplateCount, &template_id);
+
+        let mut owner_count: u64 = env.storage().persistent().get(&DataKey::OwnerTemplateCount(owner.clone())).unwrap_or(0);
+        owner_count += 1;
+        env.storage().persistent().set(&DataKey::OwnerTemplateIndex(owner.clone(), owner_count), &template_id);
+        env.storage().persistent().set(&DataKey::OwnerTemplateCount(owner), &owner_count);
+        Ok(template_id)
+    }
+
+    /// Edit a template. Only its creator may do so; products already
+    /// registered from it keep the values they were created with.
+    pub fn update_template(
+        env: Env,
+        owner: Address,
+        template_id: u64,
+        origin: String,
+        metadata: String,
+        category: Symbol,
+    ) -> Result<(), Error> {
+        count_mutation(&env);
+        require_not_paused(&env)?;
+        owner.require_auth();
+        let mut template = read_template(&env, template_id)?;
+        if template.creator != owner {
+            return Err(Error::Unauthorized);
+        }
+        validation::validate_origin(&origin)?;
+        validation::validate_metadata(&metadata, read_config(&env).max_metadata_len)?;
+
+        template.origin = origin;
+        template.metadata = metadata;
+        template.category = category;
+        env.storage().persistent().set(&DataKey::Template(template_id), &template);
+        Ok(())
+    }
+
+    pub fn get_template(env: Env, template_id: u64) -> Option<ProductTemplate> {
+        env.storage().persistent().get(&DataKey::Template(template_id))
+    }
+
+    pub fn get_templates_by_owner(env: Env, owner: Address, start: u64, limit: u64) -> Vec<ProductTemplate> {
+        let count: u64 = env.storage().persistent().get(&DataKey::OwnerTemplateCount(owner.clone())).unwrap_or(0);
+        let mut templates = Vec::new(&env);
+        let end_index = start.saturating_add(limit).min(count);
+        for i in start.saturating_add(1)..=end_index {
+            if let Some(template_id) = env.storage().persistent().get::<DataKey, u64>(&DataKey::OwnerTemplateIndex(owner.clone(), i)) {
+                if let Some(template) = env.storage().persistent().get::<DataKey, ProductTemplate>(&DataKey::Template(template_id)) {
+                    templates.push_back(template);
+                }
+            }
+        }
+        templates
+    }
+
+    /// Register a product copying a template's origin and metadata. The
+    /// template's category is attached to the new product as a tag.
+    pub fn register_from_template(env: Env, owner: Address, template_id: u64, serial: Option<String>) -> Result<u64, Error> {
+        count_mutation(&env);
+        let template = read_template(&env, template_id)?;
+        let product_id = register(&env, owner, template.origin, template.metadata, serial)?;
+        tag_product(&env, product_id, template.category)?;
+        Ok(product_id)
+    }
+}
+
+fn require_brand(env: &Env, brand: &Address) -> Result<(), Error> {
+    brand.require_auth();
+    if !env.storage().persistent().has(&DataKey::Brand(brand.clone())) {
+        return Err(Error::Unauthorized);
+    }
+    Ok(())
+}
+
+#[contractimpl]
+impl ChainLogisticsContract {
+    /// Add a verified brand/manufacturer to the registry
+    pub fn register_brand(env: Env, admin: Address, brand: Address, name: String) -> Result<(), Error> {
+        count_mutation(&env);
+        require_admin(&env, &admin)?;
+        if !validation::non_empty(&name) {
+            return Err(Error::InvalidInput);
+        }
+        env.storage().persistent().set(&DataKey::Brand(brand), &name);
+        Ok(())
+    }
+
+    /// Get a verified brand's name
+    pub fn get_brand(env: Env, brand: Address) -> Option<String> {
+        env.storage().persistent().get(&DataKey::Brand(brand))
+    }
+
+    /// Attest that a verified brand manufactured the product. The claim is
+    /// permanent, independent of ownership, and cannot be made by a brand
+    /// that has disavowed the product.
+    pub fn claim_product(env: Env, brand: Address, product_id: u64) -> Result<(), Error> {
+        count_mutation(&env);
+        require_not_paused(&env)?;
+        require_brand(&env, &brand)?;
+        read_product(&env, product_id)?;
+        require_not_renounced(&env, product_id)?;
+
+        if env.storage().persistent().has(&DataKey::BrandClaim(product_id))
+            || env.storage().persistent().has(&DataKey::Disavowed(product_id, brand.clone()))
+        {
+            return Err(Error::InvalidInput);
+        }
+        env.storage().persistent().set(&DataKey::BrandClaim(product_id), &brand);
+        audit(&env, product_id, symbol_short!("brand"), &brand, 0);
+        Ok(())
+    }
+
+    /// Publicly state that the brand did not make this product. Not allowed
+    /// for a product the brand itself has claimed.
+    pub fn disavow_product(env: Env, brand: Address, product_id: u64) -> Result<(), Error> {
+        count_mutation(&env);
+        require_not_paused(&env)?;
+        require_brand(&env, &brand)?;
+        read_product(&env, product_id)?;
+
+        if Self::get_brand_claim(env.clone(), product_id) == Some(brand.clone()) {
+            return Err(Error::InvalidInput);
+        }
+        audit(&env, product_id, symbol_short!("disavow"), &brand, 0);
+        env.storage().persistent().set(&DataKey::Disavowed(product_id, brand), &true);
+        Ok(())
+    }
+
+    /// Get the brand that attested manufacturing the product, if any
+    pub fn get_brand_claim(env: Env, product_id: u64) -> Option<Address> {
+        env.storage().persistent().get(&DataKey::BrandClaim(product_id))
+    }
+
+    pub fn is_disavowed(env: Env, product_id: u64, brand: Address) -> bool {
+        env.storage().persistent().get(&DataKey::Disavowed(product_id, brand)).unwrap_or(false)
+    }
+}
+
+#[contractimpl]
+impl ChainLogisticsContract {
+    /// Append a short operational note. Notes are append-only and stay on
+    /// record even if the author later loses authorization.
+    pub fn add_note(env: Env, actor: Address, product_id: u64, note: String) -> Result<u32, Error> {
+        count_mutation(&env);
+        let product = read_product(&env, product_id)?;
+        require_permission(&env, &product, &actor, ACTION_INSPECT)?;
+        log_activity(&env, &product, &actor, symbol_short!("NOTE"));
+
+        if !validation::non_empty(&note) {
+            return Err(Error::InvalidInput);
+        }
+        if !validation::max_len(&note, MAX_NOTE_LEN) {
+            return Err(Error::NoteTooLong);
+        }
+        let count: u32 = env.storage().persistent().get(&DataKey::NoteCount(product_id)).unwrap_or(0);
+        if count >= MAX_NOTES_PER_PRODUCT {
+            return Err(Error::TooManyNotes);
+        }
+
+        let seq = count + 1;
+        audit(&env, product_id, symbol_short!("note"), &actor, seq.into());
+        let entry = ProductNote {
+            seq,
+            author: actor,
+            text: note,
+            timestamp: env.ledger().timestamp(),
+        };
+        env.storage().persistent().set(&DataKey::Note(product_id, seq), &entry);
+        env.storage().persistent().set(&DataKey::NoteCount(product_id), &seq);
+        Ok(seq)
+    }
+
+    /// Get a product's notes oldest first, with a 0-based `start`
+    pub fn get_notes(env: Env, product_id: u64, start: u32, limit: u32) -> Vec<ProductNote> {
+        let count: u32 = env.storage().persistent().get(&DataKey::NoteCount(product_id)).unwrap_or(0);
+        let mut notes = Vec::new(&env);
+        let end_index = start.saturating_add(limit).min(count);
+        for seq in start.saturating_add(1)..=end_index {
+            if let Some(note) = env.storage().persistent().get::<DataKey, ProductNote>(&DataKey::Note(product_id, seq)) {
+                notes.push_back(note);
+            }
+        }
+        notes
+    }
+}
+
+#[contractimpl]
+impl ChainLogisticsContract {
+    /// Record a checkpoint as the product moves. Callable by the owner or an
+    /// authorized actor; returns the checkpoint's 1-based sequence number.
+    /// A Delivery checkpoint marks the product delivered and an Exception
+    /// checkpoint raises its open-exception flag.
+    pub fn add_checkpoint(env: Env, actor: Address, product_id: u64, kind: CheckpointKind, location: String, note: String) -> Result<u32, Error> {
+        count_mutation(&env);
+        let product = read_product(&env, product_id)?;
+        require_permission(&env, &product, &actor, ACTION_CHECKPOINT)?;
+        log_activity(&env, &product, &actor, symbol_short!("CHECKPT"));
+
+        if !validation::non_empty(&location) || !validation::max_len(&location, validation::MAX_ORIGIN_LEN) {
+            return Err(Error::InvalidInput);
+        }
+        if !validation::max_len(&note, MAX_NOTE_LEN) {
+            return Err(Error::NoteTooLong);
+        }
+        if kind == CheckpointKind::Delivery {
+            require_no_excursion(&env, product_id)?;
+        }
+
+        let count: u32 = env.storage().persistent().get(&DataKey::CheckpointCount(product_id)).unwrap_or(0);
+        let seq = count + 1;
+
+        let mut actor_count: u64 = env.storage().persistent().get(&TrackingKey::ActorCheckpointCount(actor.clone())).unwrap_or(0);
+        actor_count += 1;
+        env.storage().persistent().set(&TrackingKey::ActorCheckpointIndex(actor.clone(), actor_count), &(product_id, seq));
+        env.storage().persistent().set(&TrackingKey::ActorCheckpointCount(actor.clone()), &actor_count);
+
+        let checkpoint = Checkpoint {
+            seq,
+            kind,
+            actor,
+            location,
+            note,
+            timestamp: env.ledger().timestamp(),
+        };
+        env.storage().persistent().set(&DataKey::Checkpoint(product_id, seq), &checkpoint);
+        env.storage().persistent().set(&DataKey::CheckpointCount(product_id), &seq);
+        audit(&env, product_id, symbol_short!("checkpt"), &checkpoint.actor, seq.into());
+        events::checkpoint_added(&env, product_id, seq, kind, &checkpoint.location);
+        follow_route(&env, product_id, &checkpoint.location);
+
+        let mut seen: Vec<CheckpointKind> = env.storage().persistent().get(&TrackingKey::SeenKinds(product_id)).unwrap_or(Vec::new(&env));
+        if !seen.contains(kind) {
+            seen.push_back(kind);
+            env.storage().persistent().set(&TrackingKey::SeenKinds(product_id), &seen);
+        }
+
+        match kind {
+            CheckpointKind::Delivery => mark_delivered(&env, product_id),
+            CheckpointKind::Exception => {
+                env.storage().persistent().set(&DataKey::OpenException(product_id), &true);
+                raise_alert(&env, product_id, ALERT_EXCEPTION);
+            }
+            CheckpointKind::CustomsIn => storage::set_status(&env, product_id, Some(ProductStatus::InCustoms)),
+            _ => storage::set_status(&env, product_id, Some(ProductStatus::InTransit)),
+        }
+        flag_if_overdue(&env, product_id);
+        Ok(seq)
+    }
+
+    /// Mark a product delivered without a checkpoint. Blocked while a
+    /// temperature excursion is unacknowledged.
+    pub fn confirm_delivery(env: Env, actor: Address, product_id: u64) -> Result<(), Error> {
+        count_mutation(&env);
+        let product = read_product(&env, product_id)?;
+        require_authorized(&env, &product, &actor)?;
+        log_activity(&env, &product, &actor, symbol_short!("DELIVER"));
+        require_no_excursion(&env, product_id)?;
+        audit(&env, product_id, symbol_short!("deliver"), &actor, 0);
+        mark_delivered(&env, product_id);
+        Ok(())
+    }
+
+    /// The product's journey status. Products registered before statuses
+    /// were tracked read as `Registered` until their next checkpoint.
+    pub fn get_status(env: Env, product_id: u64) -> Result<ProductStatus, Error> {
+        read_product(&env, product_id)?;
+        Ok(storage::get_status(&env, product_id).unwrap_or(ProductStatus::Registered))
+    }
+
+    /// Products currently in `status`, in the order they reached it.
+    /// Slots vacated by later status changes are skipped, so a page may hold
+    /// fewer than `limit` items.
+    pub fn get_products_by_status(env: Env, status: ProductStatus, start: u64, limit: u64) -> Vec<Product> {
+        let limit = page_limit(&env, limit);
+        let count: u64 = env.storage().persistent().get(&IndexKey::StatusSlots(status)).unwrap_or(0);
+        products_from_index(&env, count, start, limit, |i| IndexKey::StatusIndex(status, i))
+    }
+
+    /// How many products are in each status right now
+    pub fn get_status_counts(env: Env) -> Vec<(ProductStatus, u64)> {
+        let mut counts = Vec::new(&env);
+        for status in [ProductStatus::Registered, ProductStatus::InTransit, ProductStatus::InCustoms, ProductStatus::Delivered] {
+            let count: u64 = env.storage().persistent().get(&IndexKey::StatusCount(status)).unwrap_or(0);
+            counts.push_back((status, count));
+        }
+        counts
+    }
+
+    /// True once the product has been delivered
+    pub fn is_delivered(env: Env, product_id: u64) -> bool {
+        env.storage().persistent().get(&DataKey::Delivered(product_id)).unwrap_or(false)
+    }
+
+    /// Set the promised delivery time. Once the goods are with a custodian,
+    /// moving an existing deadline also needs the custodian's signature.
+    pub fn set_delivery_deadline(env: Env, owner: Address, product_id: u64, deadline: u64) -> Result<(), Error> {
+        count_mutation(&env);
+        let product = read_product(&env, product_id)?;
+        require_owner(&env, &product, &owner)?;
+        if deadline <= env.ledger().timestamp() {
+            return Err(Error::InvalidTimestamp);
+        }
+        if env.storage().persistent().has(&TrackingKey::DeliveryDeadline(product_id)) {
+            if let Some(custodian) = storage::get_custodian(&env, product_id) {
+                custodian.require_auth();
+            }
+        }
+        env.storage().persistent().set(&TrackingKey::DeliveryDeadline(product_id), &deadline);
+        clear_alert(&env, product_id, ALERT_OVERDUE);
+        audit(&env, product_id, symbol_short!("deadline"), &owner, 0);
+        Ok(())
+    }
+
+    /// Revise the estimated arrival time. The last `MAX_ETA_HISTORY`
+    /// revisions are kept, oldest dropped first.
+    pub fn update_eta(env: Env, actor: Address, product_id: u64, eta: u64) -> Result<(), Error> {
+        count_mutation(&env);
+        let product = read_product(&env, product_id)?;
+        require_authorized(&env, &product, &actor)?;
+        log_activity(&env, &product, &actor, symbol_short!("ETA"));
+        let now = env.ledger().timestamp();
+        if eta < now {
+            return Err(Error::InvalidTimestamp);
+        }
+
+        let mut history = Self::get_eta_history(env.clone(), product_id);
+        if history.len() >= MAX_ETA_HISTORY {
+            history.pop_front();
+        }
+        audit(&env, product_id, symbol_short!("eta"), &actor, 0);
+        history.push_back(EtaRevision { eta, actor, timestamp: now });
+        env.storage().persistent().set(&TrackingKey::Eta(product_id), &eta);
+        env.storage().persistent().set(&TrackingKey::EtaHistory(product_id), &history);
+        flag_if_overdue(&env, product_id);
+        Ok(())
+    }
+
+    /// Get the current ETA, if one has been given
+    pub fn get_eta(env: Env, product_id: u64) -> Option<u64> {
+        env.storage().persistent().get(&TrackingKey::Eta(product_id))
+    }
+
+    /// Get the non-owner activity on a product, oldest first, with a 0-based `start`
+    pub fn get_activity_log(env: Env, product_id: u64, start: u32, limit: u32) -> Vec<ActivityEntry> {
+        let count: u32 = env.storage().persistent().get(&TrackingKey::ActivityCount(product_id)).unwrap_or(0);
+        let mut entries = Vec::new(&env);
+        let end_index = start.saturating_add(limit).min(count);
+        for seq in start.saturating_add(1)..=end_index {
+            if let Some(entry) = env.storage().persistent().get(&TrackingKey::ActivityLog(product_id, seq)) {
+                entries.push_back(entry);
+            }
+        }
+        entries
+    }
+
+    /// Get a product's audit log, oldest first, with a 0-based `start`
+    pub fn get_audit_log(env: Env, product_id: u64, start: u32, limit: u32) -> Vec<AuditEntry> {
+        let count = Self::get_audit_count(env.clone(), product_id);
+        let mut entries = Vec::new(&env);
+        let end_index = start.saturating_add(limit).min(count);
+        for seq in start.saturating_add(1)..=end_index {
+            if let Some(entry) = env.storage().persistent().get(&TrackingKey::AuditLog(product_id, seq)) {
+                entries.push_back(entry);
+            }
+        }
+        entries
+    }
+
+    /// Number of entries in a product's audit log
+    pub fn get_audit_count(env: Env, product_id: u64) -> u32 {
+        env.storage().persistent().get(&TrackingKey::AuditCount(product_id)).unwrap_or(0)
+    }
+
+    /// Number of actor calls not logged because the activity log was full
+    pub fn get_activity_overflow(env: Env, product_id: u64) -> u32 {
+        env.storage().persistent().get(&TrackingKey::ActivityOverflow(product_id)).unwrap_or(0)
+    }
+
+    /// Get the recent ETA revisions, oldest first
+    pub fn get_eta_history(env: Env, product_id: u64) -> Vec<EtaRevision> {
+        env.storage().persistent().get(&TrackingKey::EtaHistory(product_id)).unwrap_or(Vec::new(&env))
+    }
+
+    /// Get the promised delivery time, if one is set
+    pub fn get_delivery_deadline(env: Env, product_id: u64) -> Option<u64> {
+        env.storage().persistent().get(&TrackingKey::DeliveryDeadline(product_id))
+    }
+
+    /// True when the deadline has passed and the product is not delivered
+    pub fn is_overdue(env: Env, product_id: u64) -> bool {
+        match Self::get_delivery_deadline(env.clone(), product_id) {
+            Some(deadline) => env.ledger().timestamp() > deadline && !Self::is_delivered(env, product_id),
+            None => false,
+        }
+    }
+
+    /// Get the overdue products among a page of the owner's index. Pages are
+    /// read like `get_products_by_owner` and then filtered, so a page may hold
+    /// fewer than `limit` items.
+    pub fn get_overdue_products_by_owner(env: Env, owner: Address, start: u64, limit: u64) -> Vec<Product> {
+        let limit = page_limit(&env, limit);
+        let count: u64 = env.storage().persistent().get(&DataKey::OwnerProductCount(owner.clone())).unwrap_or(0);
+        let page = products_from_index(&env, count, start, limit, |i| DataKey::OwnerProductIndex(owner.clone(), i));
+        let mut overdue = Vec::new(&env);
+        for product in page.iter() {
+            if Self::is_overdue(env.clone(), product.id) {
+                touch_product(&env, &product);
+                overdue.push_back(product);
+            }
+        }
+        overdue
+    }
+
+    /// Plan the route as an ordered list of location names. The route can
+    /// be replaced until the first checkpoint is logged.
+    pub fn set_route(env: Env, owner: Address, product_id: u64, waypoints: Vec<String>) -> Result<(), Error> {
+        count_mutation(&env);
+        let product = read_product(&env, product_id)?;
+        require_owner(&env, &product, &owner)?;
+        if Self::get_checkpoint_count(env.clone(), product_id) > 0 {
+            return Err(Error::InvalidInput);
+        }
+        if waypoints.is_empty() || waypoints.len() > MAX_ROUTE_WAYPOINTS {
+            return Err(Error::InvalidInput);
+        }
+        for waypoint in waypoints.iter() {
+            if !validation::non_empty(&waypoint) || !validation::max_len(&waypoint, validation::MAX_ORIGIN_LEN) {
+                return Err(Error::InvalidInput);
+            }
+        }
+        env.storage().persistent().set(&TrackingKey::Route(product_id), &waypoints);
+        audit(&env, product_id, symbol_short!("route"), &owner, 0);
+        Ok(())
+    }
+
+    /// Get the planned route; empty when none is set
+    pub fn get_route(env: Env, product_id: u64) -> Vec<String> {
+        env.storage().persistent().get(&TrackingKey::Route(product_id)).unwrap_or(Vec::new(&env))
+    }
+
+    /// Number of waypoints reached in order so far
+    pub fn get_route_progress(env: Env, product_id: u64) -> u32 {
+        env.storage().persistent().get(&TrackingKey::RouteProgress(product_id)).unwrap_or(0)
+    }
+
+    /// True once a checkpoint was logged somewhere other than the next waypoint
+    pub fn has_route_deviation(env: Env, product_id: u64) -> bool {
+        env.storage().persistent().get(&TrackingKey::RouteDeviation(product_id)).unwrap_or(false)
+    }
+
+    /// Clear the deviation flag after the owner has reviewed it
+    pub fn clear_deviation(env: Env, owner: Address, product_id: u64) -> Result<(), Error> {
+        count_mutation(&env);
+        let product = read_product(&env, product_id)?;
+        require_owner(&env, &product, &owner)?;
+        env.storage().persistent().remove(&TrackingKey::RouteDeviation(product_id));
+        clear_alert(&env, product_id, ALERT_ROUTE_DEVIATION);
+        audit(&env, product_id, symbol_short!("deviation"), &owner, 0);
+        Ok(())
+    }
+
+    /// Bitmask of the product's raised `ALERT_*` conditions; 0 when none
+    pub fn get_alerts(env: Env, product_id: u64) -> u32 {
+        storage::get_alerts(&env, product_id)
+    }
+
+    /// Products with at least one alert raised, in the order they first
+    /// raised one. Slots of products whose alerts all cleared are skipped,
+    /// so a page may hold fewer than `limit` items.
+    pub fn get_products_with_alerts(env: Env, start: u64, limit: u64) -> Vec<Product> {
+        let limit = page_limit(&env, limit);
+        let count: u64 = env.storage().instance().get(&IndexKey::AlertSlots).unwrap_or(0);
+        products_from_index(&env, count, start, limit, IndexKey::AlertIndex)
+    }
+
+    /// True while an Exception checkpoint has not been cleared by the owner
+    pub fn has_open_exception(env: Env, product_id: u64) -> bool {
+        env.storage().persistent().get(&DataKey::OpenException(product_id)).unwrap_or(false)
+    }
+
+    /// Clear a product's open exception once it has been resolved
+    pub fn clear_exception(env: Env, owner: Address, product_id: u64) -> Result<(), Error> {
+        count_mutation(&env);
+        let product = read_product(&env, product_id)?;
+        require_owner(&env, &product, &owner)?;
+        env.storage().persistent().remove(&DataKey::OpenException(product_id));
+        clear_alert(&env, product_id, ALERT_EXCEPTION);
+        audit(&env, product_id, symbol_short!("exception"), &owner, 0);
+        Ok(())
+    }
+
+    /// Get a single checkpoint by its sequence number, showing the corrected
+    /// location if one was appended
+    pub fn get_checkpoint(env: Env, product_id: u64, seq: u32) -> Result<Checkpoint, Error> {
+        read_checkpoint(&env, product_id, seq).ok_or(Error::EventNotFound)
+    }
+
+    /// Get a checkpoint exactly as first recorded, ignoring any correction
+    pub fn get_original_checkpoint(env: Env, product_id: u64, seq: u32) -> Result<Checkpoint, Error> {
+        env.storage()
+            .persistent()
+            .get(&DataKey::Checkpoint(product_id, seq))
+            .ok_or(Error::EventNotFound)
+    }
+
+    /// Mark a checkpoint disputed. The checkpoint itself is kept as recorded.
+    pub fn dispute_checkpoint(env: Env, owner: Address, product_id: u64, seq: u32, reason: String) -> Result<(), Error> {
+        count_mutation(&env);
+        let product = read_product(&env, product_id)?;
+        require_owner(&env, &product, &owner)?;
+        Self::get_original_checkpoint(env.clone(), product_id, seq)?;
+        if !validation::non_empty(&reason) {
+            return Err(Error::InvalidInput);
+        }
+        if !validation::max_len(&reason, MAX_NOTE_LEN) {
+            return Err(Error::NoteTooLong);
+        }
+        if env.storage().persistent().has(&TrackingKey::CheckpointDispute(product_id, seq)) {
+            return Err(Error::InvalidInput);
+        }
+        env.storage().persistent().set(&TrackingKey::CheckpointDispute(product_id, seq), &reason);
+        let open: u32 = env.storage().persistent().get(&TrackingKey::OpenDisputes(product_id)).unwrap_or(0);
+        env.storage().persistent().set(&TrackingKey::OpenDisputes(product_id), &(open + 1));
+        raise_alert(&env, product_id, ALERT_DISPUTE);
+        audit(&env, product_id, symbol_short!("dispute"), &owner, seq.into());
+        Ok(())
+    }
+
+    /// Get the reason a checkpoint was disputed, if it was
+    pub fn get_checkpoint_dispute(env: Env, product_id: u64, seq: u32) -> Option<String> {
+        env.storage().persistent().get(&TrackingKey::CheckpointDispute(product_id, seq))
+    }
+
+    /// Link a corrected location to a checkpoint. Callable once per
+    /// checkpoint, by its author or the owner; reads then show the correction.
+    pub fn append_correction(env: Env, actor: Address, product_id: u64, seq: u32, corrected_location: String) -> Result<(), Error> {
+        count_mutation(&env);
+        require_not_paused(&env)?;
+        let product = read_product(&env, product_id)?;
+        let original = Self::get_original_checkpoint(env.clone(), product_id, seq)?;
+        actor.require_auth();
+        if actor != product.owner && actor != original.actor {
+            return Err(Error::Unauthorized);
+        }
+        if !validation::non_empty(&corrected_location) || !validation::max_len(&corrected_location, validation::MAX_ORIGIN_LEN) {
+            return Err(Error::InvalidInput);
+        }
+        if env.storage().persistent().has(&TrackingKey::CheckpointCorrection(product_id, seq)) {
+            return Err(Error::InvalidInput);
+        }
+        audit(&env, product_id, symbol_short!("correct"), &actor, seq.into());
+        let correction = CheckpointCorrection {
+            seq,
+            actor,
+            location: corrected_location,
+            timestamp: env.ledger().timestamp(),
+        };
+        env.storage().persistent().set(&TrackingKey::CheckpointCorrection(product_id, seq), &correction);
+        if env.storage().persistent().has(&TrackingKey::CheckpointDispute(product_id, seq)) {
+            let open: u32 = env.storage().persistent().get(&TrackingKey::OpenDisputes(product_id)).unwrap_or(0);
+            let open = open.saturating_sub(1);
+            env.storage().persistent().set(&TrackingKey::OpenDisputes(product_id), &open);
+            if open == 0 {
+                clear_alert(&env, product_id, ALERT_DISPUTE);
+            }
+        }
+        Ok(())
+    }
+
+    /// Get the correction linked to a checkpoint, if any
+    pub fn get_checkpoint_correction(env: Env, product_id: u64, seq: u32) -> Option<CheckpointCorrection> {
+        env.storage().persistent().get(&TrackingKey::CheckpointCorrection(product_id, seq))
+    }
+
+    /// Get a product's checkpoints in chronological order, with a 0-based `start`
+    pub fn get_checkpoints(env: Env, product_id: u64, start: u32, limit: u32) -> Vec<Checkpoint> {
+        let count = Self::get_checkpoint_count(env.clone(), product_id);
+        let mut checkpoints = Vec::new(&env);
+        let end_index = start.saturating_add(limit).min(count);
+        for seq in start.saturating_add(1)..=end_index {
+            if let Some(cp) = read_checkpoint(&env, product_id, seq) {
+                checkpoints.push_back(cp);
+            }
+        }
+        checkpoints
+    }
+
+    /// Number of checkpoints recorded for a product
+    pub fn get_checkpoint_count(env: Env, product_id: u64) -> u32 {
+        env.storage().persistent().get(&DataKey::CheckpointCount(product_id)).unwrap_or(0)
+    }
+
+    /// Get every checkpoint an actor has logged, across products, in the order
+    /// they were logged, with a 0-based `start`
+    pub fn get_checkpoints_by_actor(env: Env, actor: Address, start: u64, limit: u64) -> Vec<CheckpointRef> {
+        let count: u64 = env.storage().persistent().get(&TrackingKey::ActorCheckpointCount(actor.clone())).unwrap_or(0);
+        let mut refs = Vec::new(&env);
+        let end_index = start.saturating_add(limit).min(count);
+        for i in start.saturating_add(1)..=end_index {
+            let Some((product_id, seq)) = env.storage().persistent().get::<TrackingKey, (u64, u32)>(&TrackingKey::ActorCheckpointIndex(actor.clone(), i)) else {
+                continue;
+            };
+            if let Some(cp) = read_checkpoint(&env, product_id, seq) {
+                refs.push_back(CheckpointRef {
+                    product_id,
+                    seq,
+                    location: cp.location,
+                    timestamp: cp.timestamp,
+                });
+            }
+        }
+        refs
+    }
+
+    /// Get the most recent checkpoint, if any
+    pub fn get_latest_checkpoint(env: Env, product_id: u64) -> Option<Checkpoint> {
+        let count = Self::get_checkpoint_count(env.clone(), product_id);
+        if count == 0 {
+            return None;
+        }
+        read_checkpoint(&env, product_id, count)
+    }
+}
+
+#[contractimpl]
+impl ChainLogisticsContract {
+    /// Append a temperature reading (hundredths of a degree Celsius) taken at
+    /// `timestamp`. Readings dated after the current ledger time are rejected.
+    pub fn record_temperature(env: Env, actor: Address, product_id: u64, temp_centi_c: i32, timestamp: u64) -> Result<u32, Error> {
+        count_mutation(&env);
+        let product = read_product(&env, product_id)?;
+        require_permission(&env, &product, &actor, ACTION_INSPECT)?;
+        log_activity(&env, &product, &actor, symbol_short!("TEMP"));
+        if timestamp > env.ledger().timestamp() {
+            return Err(Error::InvalidTimestamp);
+        }
+
+        let mut stats = Self::get_temperature_stats(env.clone(), product_id);
+        if stats.count == 0 {
+            stats.min_centi_c = temp_centi_c;
+            stats.max_centi_c = temp_centi_c;
+        } else {
+            stats.min_centi_c = stats.min_centi_c.min(temp_centi_c);
+            stats.max_centi_c = stats.max_centi_c.max(temp_centi_c);
+        }
+        stats.count += 1;
+        check_temperature_limits(&env, product_id, temp_centi_c);
+
+        audit(&env, product_id, symbol_short!("temp"), &actor, stats.count.into());
+        let reading = TempReading {
+            seq: stats.count,
+            actor,
+            temp_centi_c,
+            timestamp,
+        };
+        env.storage().persistent().set(&DataKey::TempReading(product_id, stats.count), &reading);
+        env.storage().persistent().set(&DataKey::TempStats(product_id), &stats);
+        Ok(stats.count)
+    }
+
+    /// Get a product's temperature readings in recorded order, with a 0-based `start`
+    pub fn get_temperature_readings(env: Env, product_id: u64, start: u32, limit: u32) -> Vec<TempReading> {
+        let count = Self::get_temperature_stats(env.clone(), product_id).count;
+        let mut readings = Vec::new(&env);
+        let end_index = start.saturating_add(limit).min(count);
+        for seq in start.saturating_add(1)..=end_index {
+            if let Some(r) = env.storage().persistent().get::<DataKey, TempReading>(&DataKey::TempReading(product_id, seq)) {
+                readings.push_back(r);
+            }
+        }
+        readings
+    }
+
+    /// Set the inclusive range a product's temperature must stay within
+    pub fn set_temperature_limits(env: Env, owner: Address, product_id: u64, min_centi_c: i32, max_centi_c: i32) -> Result<(), Error> {
+        count_mutation(&env);
+        let product = read_product(&env, product_id)?;
+        require_owner(&env, &product, &owner)?;
+        if min_centi_c > max_centi_c {
+            return Err(Error::InvalidInput);
+        }
+        env.storage().persistent().set(&TrackingKey::TempLimits(product_id), &(min_centi_c, max_centi_c));
+        audit(&env, product_id, symbol_short!("templimit"), &owner, 0);
+        Ok(())
+    }
+
+    /// Number of readings that fell outside the product's limits
+    pub fn get_breach_count(env: Env, product_id: u64) -> u32 {
+        env.storage().persistent().get(&TrackingKey::BreachCount(product_id)).unwrap_or(0)
+    }
+
+    /// True while a breach is waiting for the owner's acknowledgment
+    pub fn has_excursion(env: Env, product_id: u64) -> bool {
+        env.storage().persistent().get(&TrackingKey::Excursion(product_id)).unwrap_or(false)
+    }
+
+    /// Acknowledge an excursion so the product can be delivered. The breach
+    /// count is kept.
+    pub fn acknowledge_excursion(env: Env, owner: Address, product_id: u64) -> Result<(), Error> {
+        count_mutation(&env);
+        let product = read_product(&env, product_id)?;
+        require_owner(&env, &product, &owner)?;
+        env.storage().persistent().remove(&TrackingKey::Excursion(product_id));
+        clear_alert(&env, product_id, ALERT_EXCURSION);
+        audit(&env, product_id, symbol_short!("excursion"), &owner, 0);
+        Ok(())
+    }
+
+    /// Append one reading to a sensor channel (temperature, humidity, shock,
+    /// ...) and return the channel's new reading count. A product may use up
+    /// to `MAX_CHANNELS_PER_PRODUCT` distinct channels.
+    pub fn record_reading(env: Env, actor: Address, product_id: u64, channel: Symbol, value: i64, timestamp: u64) -> Result<u32, Error> {
+        count_mutation(&env);
+        let product = read_product(&env, product_id)?;
+        require_permission(&env, &product, &actor, ACTION_INSPECT)?;
+        log_activity(&env, &product, &actor, symbol_short!("READING"));
+        let mut readings = Vec::new(&env);
+        readings.push_back(SensorReading {
+            kind: channel.clone(),
+            value,
+            timestamp,
+        });
+        append_sensor_readings(&env, product_id, &readings)?;
+        audit(&env, product_id, symbol_short!("reading"), &actor, 0);
+        Ok(read_channel_stats(&env, product_id, &channel).count)
+    }
+
+    /// Append up to `MAX_SENSOR_BATCH` readings under one authorization, each
+    /// to the channel named by its `kind`. Timestamps must not decrease,
+    /// within the batch or relative to the last stored reading; any invalid
+    /// entry rejects the whole batch. Returns the number of readings written.
+    pub fn record_readings_batch(env: Env, actor: Address, product_id: u64, readings: Vec<SensorReading>) -> Result<u32, Error> {
+        count_mutation(&env);
+        let product = read_product(&env, product_id)?;
+        require_permission(&env, &product, &actor, ACTION_INSPECT)?;
+        log_activity(&env, &product, &actor, symbol_short!("READING"));
+        if readings.len() > MAX_SENSOR_BATCH {
+            return Err(Error::BatchTooLarge);
+        }
+        append_sensor_readings(&env, product_id, &readings)?;
+        audit(&env, product_id, symbol_short!("reading"), &actor, 0);
+        Ok(readings.len())
+    }
+
+    /// Get one channel's readings in recorded order, with a 0-based `start`
+    pub fn get_readings(env: Env, product_id: u64, channel: Symbol, start: u32, limit: u32) -> Vec<SensorReading> {
+        let count = read_channel_stats(&env, product_id, &channel).count;
+        let mut readings = Vec::new(&env);
+        let end_index = start.saturating_add(limit).min(count);
+        for seq in start.saturating_add(1)..=end_index {
+            if let Some(r) = env.storage().persistent().get::<TrackingKey, SensorReading>(&TrackingKey::ChannelReading(product_id, channel.clone(), seq)) {
+                readings.push_back(r);
+            }
+        }
+        readings
+    }
+
+    /// Running min/max/count of one channel; all zero when it has no readings
+    pub fn get_channel_stats(env: Env, product_id: u64, channel: Symbol) -> ChannelStats {
+        read_channel_stats(&env, product_id, &channel)
+    }
+
+    /// Channels the product has readings on, in first-use order
+    pub fn get_channels(env: Env, product_id: u64) -> Vec<Symbol> {
+        env.storage().persistent().get(&TrackingKey::ProductChannels(product_id)).unwrap_or(Vec::new(&env))
+    }
+
+    /// Running min/max/count of a product's readings; all zero when none exist
+    pub fn get_temperature_stats(env: Env, product_id: u64) -> TempStats {
+        env.storage()
+            .persistent()
+            .get(&DataKey::TempStats(product_id))
+            .unwrap_or(TempStats {
+                count: 0,
+                min_centi_c: 0,
+                max_centi_c: 0,
+            })
+    }
+}
+
+#[contractimpl]
+impl ChainLogisticsContract {
+    /// Hand physical custody to a carrier while the owner keeps title. Both
+    /// parties must sign; an existing custodian is replaced. Products locked
+    /// against transfer (recalled, claim-locked, still disputable) stay put.
+    pub fn set_custodian(env: Env, owner: Address, product_id: u64, custodian: Address) -> Result<(), Error> {
+        count_mutation(&env);
+        let product = read_product(&env, product_id)?;
+        require_owner(&env, &product, &owner)?;
+        require_movable(&env, &product)?;
+        if custodian == owner {
+            return Err(Error::InvalidInput);
+        }
+        require_not_blacklisted(&env, &custodian)?;
+        custodian.require_auth();
+        audit(&env, product_id, symbol_short!("custody"), &owner, 0);
+        let from = storage::get_custodian(&env, product_id).unwrap_or(owner);
+        log_custody(&env, product_id, &from, &custodian, CustodyRole::Custodian);
+        env.storage().persistent().set(&TrackingKey::Custodian(product_id), &custodian);
+        Ok(())
+    }
+
+    /// Return custody to the owner
+    pub fn release_custody(env: Env, custodian: Address, product_id: u64) -> Result<(), Error> {
+        count_mutation(&env);
+        require_not_paused(&env)?;
+        let product = read_product(&env, product_id)?;
+        custodian.require_auth();
+        if storage::get_custodian(&env, product_id).as_ref() != Some(&custodian) {
+            return Err(Error::Unauthorized);
+        }
+        log_custody(&env, product_id, &custodian, &product.owner, CustodyRole::Custodian);
+        env.storage().persistent().remove(&TrackingKey::Custodian(product_id));
+        audit(&env, product_id, symbol_short!("release"), &custodian, 0);
+        Ok(())
+    }
+
+    /// Get the carrier currently holding the product, if any
+    pub fn get_custodian(env: Env, product_id: u64) -> Option<Address> {
+        storage::get_custodian(&env, product_id)
+    }
+
+    /// Get the product's chain of custody oldest first, with a 0-based `start`
+    pub fn get_custody_log(env: Env, product_id: u64, start: u32, limit: u32) -> Vec<CustodyEntry> {
+        let count: u32 = env.storage().persistent().get(&TrackingKey::CustodyCount(product_id)).unwrap_or(0);
+        let mut entries = Vec::new(&env);
+        let end_index = start.saturating_add(limit).min(count);
+        for seq in start.saturating_add(1)..=end_index {
+            if let Some(entry) = env.storage().persistent().get::<TrackingKey, CustodyEntry>(&TrackingKey::CustodyLog(product_id, seq)) {
+                entries.push_back(entry);
+            }
+        }
+        entries
+    }
+
+    /// Nominate the address allowed to sign for delivery besides the owner
+    pub fn set_consignee(env: Env, owner: Address, product_id: u64, consignee: Address) -> Result<(), Error> {
+        count_mutation(&env);
+        let product = read_product(&env, product_id)?;
+        require_owner(&env, &product, &owner)?;
+        env.storage().persistent().set(&TrackingKey::Consignee(product_id), &consignee);
+        audit(&env, product_id, symbol_short!("consignee"), &owner, 0);
+        Ok(())
+    }
+
+    /// Get the nominated consignee, if any
+    pub fn get_consignee(env: Env, product_id: u64) -> Option<Address> {
+        env.storage().persistent().get(&TrackingKey::Consignee(product_id))
+    }
+
+    /// Record proof of delivery signed by the owner or the consignee. This
+    /// marks the product delivered and can only happen once.
+    pub fn submit_pod(env: Env, recipient: Address, product_id: u64, evidence_hash: BytesN<32>) -> Result<(), Error> {
+        count_mutation(&env);
+        require_not_paused(&env)?;
+        let product = read_product(&env, product_id)?;
+        recipient.require_auth();
+        if product.owner != recipient && Self::get_consignee(env.clone(), product_id).as_ref() != Some(&recipient) {
+            return Err(Error::Unauthorized);
+        }
+        if env.storage().persistent().has(&TrackingKey::Pod(product_id)) {
+            return Err(Error::AlreadyDelivered);
+        }
+        require_no_excursion(&env, product_id)?;
+
+        let pod = ProofOfDelivery {
+            evidence_hash,
+            recipient,
+            timestamp: env.ledger().timestamp(),
+        };
+        env.storage().persistent().set(&TrackingKey::Pod(product_id), &pod);
+        audit(&env, product_id, symbol_short!("pod"), &pod.recipient, 0);
+        mark_delivered(&env, product_id);
+        Ok(())
+    }
+
+    /// Get the product's proof of delivery, if submitted
+    pub fn get_pod(env: Env, product_id: u64) -> Option<ProofOfDelivery> {
+        env.storage().persistent().get(&TrackingKey::Pod(product_id))
+    }
+
+    /// Allow or forbid `transfer_product` while the goods are in custody
+    pub fn set_transfer_while_in_custody(env: Env, owner: Address, product_id: u64, allowed: bool) -> Result<(), Error> {
+        count_mutation(&env);
+        let product = read_product(&env, product_id)?;
+        require_owner(&env, &product, &owner)?;
+        env.storage().persistent().set(&TrackingKey::TransferInCustody(product_id), &allowed);
+        audit(&env, product_id, symbol_short!("heldxfer"), &owner, 0);
+        Ok(())
+    }
+}
+
+#[contractimpl]
+impl ChainLogisticsContract {
+    /// Transfer up to the configured batch size of products to `new_owner` at once.
+    /// Every product is checked first, so one failure transfers nothing.
+    pub fn transfer_products(env: Env, owner: Address, product_ids: Vec<u64>, new_owner: Address) -> Result<(), Error> {
+        count_mutation(&env);
+        require_not_paused(&env)?;
+        if product_ids.is_empty() {
+            return Err(Error::InvalidInput);
+        }
+        if product_ids.len() > read_config(&env).max_batch_size {
+            return Err(Error::BatchTooLarge);
+        }
+        owner.require_auth();
+
+        let mut products = Vec::new(&env);
+        for (i, product_id) in product_ids.iter().enumerate() {
+            if product_ids.first_index_of(product_id) != Some(i as u32) {
+                return Err(Error::InvalidInput);
+            }
+            let product = read_product(&env, product_id)?;
+            if product.owner != owner {
+                return Err(Error::Unauthorized);
+            }
+            require_transferable(&env, &product, &new_owner)?;
+            products.push_back(product);
+        }
+
+        new_owner.require_auth();
+        for mut product in products.iter() {
+            reassign_owner(&env, &mut product, new_owner.clone(), String::from_str(&env, ""));
+        }
+        Ok(())
+    }
+
+    /// Hand over every product `owner` holds to `new_owner`, walking the
+    /// owner's index from `start` in chunks of at most the configured batch
+    /// size. Products that cannot move right now (pending or scheduled
+    /// transfers, custody, claims, recalls and the like) are skipped and
+    /// counted rather than failing the chunk.
+    pub fn transfer_all(env: Env, owner: Address, new_owner: Address, start: u64, limit: u64) -> Result<BulkTransferResult, Error> {
+        count_mutation(&env);
+        require_not_paused(&env)?;
+        if limit == 0 || limit > read_config(&env).max_batch_size as u64 {
+            return Err(Error::BatchTooLarge);
+        }
+        if owner == new_owner {
+            return Err(Error::SelfTransfer);
+        }
+        require_not_blacklisted(&env, &owner)?;
+        owner.require_auth();
+        new_owner.require_auth();
+
+        let count: u64 = env.storage().persistent().get(&DataKey::OwnerProductCount(owner.clone())).unwrap_or(0);
+        let end_index = start.saturating_add(limit).min(count);
+        let mut result = BulkTransferResult {
+            moved: 0,
+            skipped: 0,
+            next_start: end_index.max(start),
+        };
+        for i in start.saturating_add(1)..=end_index {
+            // Moved products leave a tombstone, so later chunks keep their offsets
+            let Some(product_id) = env.storage().persistent().get::<DataKey, u64>(&DataKey::OwnerProductIndex(owner.clone(), i)) else {
+                continue;
+            };
+            let Some(mut product) = storage::get_product(&env, product_id) else {
+                continue;
+            };
+            if product.owner != owner {
+                continue;
+            }
+            let pending = env.storage().persistent().has(&TransferKey::Pending(product_id))
+                || env.storage().persistent().has(&TransferKey::Scheduled(product_id));
+            if pending || require_transferable(&env, &product, &new_owner).is_err() {
+                result.skipped += 1;
+                continue;
+            }
+            reassign_owner(&env, &mut product, new_owner.clone(), String::from_str(&env, ""));
+            result.moved += 1;
+        }
+        Ok(result)
+    }
+
+    /// Approve an agent (marketplace, 3PL) to transfer the product on the
+    /// owner's behalf. Replaces any earlier agent; cleared on transfer.
+    pub fn approve_transfer_agent(env: Env, owner: Address, product_id: u64, agent: Address) -> Result<(), Error> {
+        count_mutation(&env);
+        let product = read_product(&env, product_id)?;
+        require_owner(&env, &product, &owner)?;
+        if agent == owner {
+            return Err(Error::InvalidInput);
+        }
+        env.storage().persistent().set(&TransferKey::Agent(product_id), &agent);
+        audit(&env, product_id, symbol_short!("agent"), &owner, 0);
+        Ok(())
+    }
+
+    /// Withdraw the agent's approval
+    pub fn revoke_transfer_agent(env: Env, owner: Address, product_id: u64) -> Result<(), Error> {
+        count_mutation(&env);
+        let product = read_product(&env, product_id)?;
+        require_owner(&env, &product, &owner)?;
+        env.storage().persistent().remove(&TransferKey::Agent(product_id));
+        audit(&env, product_id, symbol_short!("unagent"), &owner, 0);
+        Ok(())
+    }
+
+    /// Get the approved agent, if any
+    pub fn get_approved_agent(env: Env, product_id: u64) -> Option<Address> {
+        env.storage().persistent().get(&TransferKey::Agent(product_id))
+    }
+
+    /// Transfer as the approved agent; the agent's signature suffices
+    pub fn transfer_from(env: Env, agent: Address, product_id: u64, new_owner: Address) -> Result<(), Error> {
+        count_mutation(&env);
+        let mut product = read_product(&env, product_id)?;
+        require_permission(&env, &product, &agent, ACTION_TRANSFER)?;
+        if product.owner == new_owner {
+            return Err(Error::SelfTransfer);
+        }
+        require_not_blacklisted(&env, &new_owner)?;
+        reassign_owner(&env, &mut product, new_owner, String::from_str(&env, ""));
+        Ok(())
+    }
+
+    /// Propose handing the product to `new_owner`, who completes it with
+    /// `accept_transfer` before `expires_at`, if given. A new proposal
+    /// replaces any pending one.
+    pub fn propose_transfer(env: Env, owner: Address, product_id: u64, new_owner: Address, expires_at: Option<u64>) -> Result<(), Error> {
+        count_mutation(&env);
+        let product = read_product(&env, product_id)?;
+        require_owner(&env, &product, &owner)?;
+        if new_owner == owner {
+            return Err(Error::SelfTransfer);
+        }
+        require_not_blacklisted(&env, &new_owner)?;
+        let now = env.ledger().timestamp();
+        if expires_at.is_some_and(|t| t <= now) {
+            return Err(Error::InvalidTimestamp);
+        }
+        audit(&env, product_id, symbol_short!("propose"), &owner, 0);
+        let pending = PendingTransfer {
+            from: owner,
+            to: new_owner,
+            proposed_at: now,
+            expires_at: expires_at.unwrap_or(0),
+        };
+        storage::put_pending_transfer(&env, product_id, &pending);
+        Ok(())
+    }
+
+    /// Complete a pending transfer; only the proposed recipient signs
+    pub fn accept_transfer(env: Env, new_owner: Address, product_id: u64) -> Result<(), Error> {
+        count_mutation(&env);
+        require_not_paused(&env)?;
+        let mut product = read_product(&env, product_id)?;
+        new_owner.require_auth();
+        let pending = Self::get_pending_transfer(env.clone(), product_id).ok_or(Error::NoPendingTransfer)?;
+        if pending.to != new_owner {
+            return Err(Error::Unauthorized);
+        }
+        if pending.expires_at != 0 && env.ledger().timestamp() >= pending.expires_at {
+            return Err(Error::TransferExpired);
+        }
+        require_transferable(&env, &product, &new_owner)?;
+        reassign_owner(&env, &mut product, new_owner, String::from_str(&env, ""));
+        Ok(())
+    }
+
+    /// Get the product's owners oldest first, with a 0-based `start`. The
+    /// first record is the registration.
+    pub fn get_transfer_history(env: Env, product_id: u64, start: u32, limit: u32) -> Vec<TransferRecord> {
+        let records = Self::get_transfer_count(env.clone(), product_id).saturating_add(1);
+        let mut history = Vec::new(&env);
+        let end_index = start.saturating_add(limit).min(records);
+        for seq in start..end_index {
+            if let Some(record) = env.storage().persistent().get::<TransferKey, TransferRecord>(&TransferKey::History(product_id, seq)) {
+                history.push_back(record);
+            }
+        }
+        history
+    }
+
+    /// Number of transfers an address has sent, across all products
+    pub fn get_transfer_count_by_owner(env: Env, owner: Address) -> u64 {
+        env.storage().persistent().get(&TransferKey::SentCount(owner)).unwrap_or(0)
+    }
+
+    /// Number of completed transfers, not counting the registration
+    pub fn get_transfer_count(env: Env, product_id: u64) -> u32 {
+        env.storage().persistent().get(&TransferKey::HistoryCount(product_id)).unwrap_or(0)
+    }
+
+    /// Withdraw the pending proposal
+    pub fn cancel_transfer(env: Env, owner: Address, product_id: u64) -> Result<(), Error> {
+        count_mutation(&env);
+        let product = read_product(&env, product_id)?;
+        require_owner(&env, &product, &owner)?;
+        if !env.storage().persistent().has(&TransferKey::Pending(product_id)) {
+            return Err(Error::NoPendingTransfer);
+        }
+        storage::clear_pending_transfer(&env, product_id);
+        audit(&env, product_id, symbol_short!("cancel"), &owner, 0);
+        Ok(())
+    }
+
+    /// Proposals waiting for `recipient` to accept, oldest first. Expired
+    /// proposals are left out, so a page may hold fewer than `limit` items.
+    pub fn get_pending_transfers_for(env: Env, recipient: Address, start: u64, limit: u64) -> Vec<IncomingTransfer> {
+        let limit = page_limit(&env, limit);
+        let count: u64 = env.storage().persistent().get(&TransferKey::IncomingTransferCount(recipient.clone())).unwrap_or(0);
+        let now = env.ledger().timestamp();
+        let mut inbox = Vec::new(&env);
+        let end_index = start.saturating_add(limit).min(count);
+        for i in start.saturating_add(1)..=end_index {
+            let Some(product_id) = env
+                .storage()
+                .persistent()
+                .get::<TransferKey, u64>(&TransferKey::IncomingTransferIndex(recipient.clone(), i))
+            else {
+                continue;
+            };
+            match Self::get_pending_transfer(env.clone(), product_id) {
+                Some(pending) if pending.to == recipient && (pending.expires_at == 0 || now < pending.expires_at) => {
+                    inbox.push_back(IncomingTransfer {
+                        product_id,
+                        from: pending.from,
+                        proposed_at: pending.proposed_at,
+                        expires_at: pending.expires_at,
+                    });
+                }
+                _ => {}
+            }
+        }
+        inbox
+    }
+
+    /// Get the transfer awaiting acceptance, if any, including its expiry
+    /// so callers can show the time left
+    pub fn get_pending_transfer(env: Env, product_id: u64) -> Option<PendingTransfer> {
+        env.storage().persistent().get(&TransferKey::Pending(product_id))
+    }
+}
+
+#[contractimpl]
+impl ChainLogisticsContract {
+    /// Schedule ownership to pass to `new_owner` at `not_before`. Both
+    /// parties sign now; anyone may execute it once the time has come.
+    pub fn schedule_transfer(env: Env, owner: Address, product_id: u64, new_owner: Address, not_before: u64) -> Result<(), Error> {
+        count_mutation(&env);
+        let product = read_product(&env, product_id)?;
+        require_owner(&env, &product, &owner)?;
+        require_transferable(&env, &product, &new_owner)?;
+        new_owner.require_auth();
+
+        audit(&env, product_id, symbol_short!("schedule"), &owner, 0);
+        let scheduled = ScheduledTransfer {
+            from: owner,
+            to: new_owner,
+            not_before,
+        };
+        env.storage().persistent().set(&TransferKey::Scheduled(product_id), &scheduled);
+        Ok(())
+    }
+
+    /// Carry out a scheduled transfer whose time has come. The caller need
+    /// not be a party to it.
+    pub fn execute_scheduled_transfer(env: Env, caller: Address, product_id: u64) -> Result<(), Error> {
+        count_mutation(&env);
+        require_not_paused(&env)?;
+        caller.require_auth();
+        let mut product = read_product(&env, product_id)?;
+        let scheduled = Self::get_scheduled_transfer(env.clone(), product_id).ok_or(Error::NoPendingTransfer)?;
+        if env.ledger().timestamp() < scheduled.not_before {
+            return Err(Error::TooEarly);
+        }
+        require_transferable(&env, &product, &scheduled.to)?;
+        reassign_owner(&env, &mut product, scheduled.to, String::from_str(&env, ""));
+        Ok(())
+    }
+
+    /// Cancel a scheduled transfer before it is executed
+    pub fn cancel_scheduled_transfer(env: Env, owner: Address, product_id: u64) -> Result<(), Error> {
+        count_mutation(&env);
+        let product = read_product(&env, product_id)?;
+        require_owner(&env, &product, &owner)?;
+        if !env.storage().persistent().has(&TransferKey::Scheduled(product_id)) {
+            return Err(Error::NoPendingTransfer);
+        }
+        env.storage().persistent().remove(&TransferKey::Scheduled(product_id));
+        audit(&env, product_id, symbol_short!("cancelsch"), &owner, 0);
+        Ok(())
+    }
+
+    /// Get the scheduled transfer, if any
+    pub fn get_scheduled_transfer(env: Env, product_id: u64) -> Option<ScheduledTransfer> {
+        env.storage().persistent().get(&TransferKey::Scheduled(product_id))
+    }
+}
+
+#[contractimpl]
+impl ChainLogisticsContract {
+    /// Lock the product behind a secret for a recipient not yet known.
+    /// Whoever presents the preimage of `claim_hash` before `expires_at`
+    /// becomes the owner via `redeem_claim`.
+    pub fn create_claim(env: Env, owner: Address, product_id: u64, claim_hash: BytesN<32>, expires_at: u64) -> Result<(), Error> {
+        count_mutation(&env);
+        let product = read_product(&env, product_id)?;
+        require_owner(&env, &product, &owner)?;
+        require_unlocked(&env, &product)?;
+        let now = env.ledger().timestamp();
+        if expires_at <= now {
+            return Err(Error::InvalidTimestamp);
+        }
+        let claim = TransferClaim {
+            claim_hash,
+            created_at: now,
+            expires_at,
+        };
+        env.storage().persistent().set(&TransferKey::Claim(product_id), &claim);
+        audit(&env, product_id, symbol_short!("claim"), &owner, 0);
+        Ok(())
+    }
+
+    /// Take ownership by presenting the claim's preimage. Expired claims fail
+    /// with `ClaimExpired`.
+    pub fn redeem_claim(env: Env, claimant: Address, product_id: u64, preimage: Bytes) -> Result<(), Error> {
+        count_mutation(&env);
+        require_not_paused(&env)?;
+        claimant.require_auth();
+        let mut product = read_product(&env, product_id)?;
+        let claim = Self::get_claim(env.clone(), product_id).ok_or(Error::NoPendingTransfer)?;
+        if env.ledger().timestamp() >= claim.expires_at {
+            return Err(Error::ClaimExpired);
+        }
+        if env.crypto().sha256(&preimage) != claim.claim_hash {
+            return Err(Error::InvalidClaim);
+        }
+        env.storage().persistent().remove(&TransferKey::Claim(product_id));
+        require_transferable(&env, &product, &claimant)?;
+        reassign_owner(&env, &mut product, claimant, String::from_str(&env, ""));
+        Ok(())
+    }
+
+    /// Unlock the product once an unredeemed claim has expired
+    pub fn revoke_claim(env: Env, owner: Address, product_id: u64) -> Result<(), Error> {
+        count_mutation(&env);
+        let product = read_product(&env, product_id)?;
+        require_owner(&env, &product, &owner)?;
+        let claim = Self::get_claim(env.clone(), product_id).ok_or(Error::NoPendingTransfer)?;
+        if env.ledger().timestamp() < claim.expires_at {
+            return Err(Error::TooEarly);
+        }
+        env.storage().persistent().remove(&TransferKey::Claim(product_id));
+        audit(&env, product_id, symbol_short!("unclaim"), &owner, 0);
+        Ok(())
+    }
+
+    /// Give up a product abandoned or destroyed off-chain. It becomes
+    /// inactive, leaves the owner, status and alert indexes, loses its
+    /// actors, pending handovers and dispute window, and ends its histories
+    /// with an entry to the contract itself. It stays readable but rejects
+    /// every later change.
+    pub fn renounce_ownership(env: Env, owner: Address, product_id: u64) -> Result<(), Error> {
+        count_mutation(&env);
+        let mut product = read_product(&env, product_id)?;
+        require_owner(&env, &product, &owner)?;
+
+        if product.active {
+            product.active = false;
+            let active_products: u64 = env.storage().instance().get(&DataKey::ActiveProducts).unwrap_or(0);
+            env.storage().instance().set(&DataKey::ActiveProducts, &active_products.saturating_sub(1));
+            let inactive: u64 = env.storage().instance().get(&StatsKey::InactiveProducts).unwrap_or(0);
+            env.storage().instance().set(&StatsKey::InactiveProducts, &(inactive + 1));
+            update_origin_stats(&env, &product.origin, |stats| stats.active = stats.active.saturating_sub(1));
+        }
+        storage::remove_owner_index(&env, &owner, product_id);
+        storage::remove_owner_origin_index(&env, &owner, &product.origin, product_id);
+        adjust_owned(&env, &owner, false);
+        push_recent_activity(&env, symbol_short!("renounce"), product_id, &owner);
+        audit(&env, product_id, symbol_short!("renounce"), &owner, 0);
+        storage::clear_auth(&env, product_id);
+        storage::clear_pending_transfer(&env, product_id);
+        for key in [
+            TransferKey::Scheduled(product_id),
+            TransferKey::Agent(product_id),
+            TransferKey::Claim(product_id),
+            TransferKey::Window(product_id),
+        ] {
+            env.storage().persistent().remove(&key);
+        }
+        env.storage().persistent().remove(&TrackingKey::Custodian(product_id));
+        storage::set_status(&env, product_id, None);
+        storage::set_alerts(&env, product_id, 0);
+
+        let nobody = env.current_contract_address();
+        log_custody(&env, product_id, &owner, &nobody, CustodyRole::Owner);
+        let seq = Self::get_transfer_count(env.clone(), product_id) + 1;
+        record_transfer(&env, product_id, seq, &owner, &nobody, String::from_str(&env, "renounced"));
+        env.storage().persistent().set(&TransferKey::HistoryCount(product_id), &seq);
+
+        env.storage().persistent().set(&TransferKey::Renounced(product_id), &true);
+        write_product(&env, &product);
+        Ok(())
+    }
+
+    /// True once the owner has renounced the product
+    pub fn is_renounced(env: Env, product_id: u64) -> bool {
+        env.storage().persistent().get(&TransferKey::Renounced(product_id)).unwrap_or(false)
+    }
+
+    /// Get the open claim, if any
+    pub fn get_claim(env: Env, product_id: u64) -> Option<TransferClaim> {
+        env.storage().persistent().get(&TransferKey::Claim(product_id))
     }
 }
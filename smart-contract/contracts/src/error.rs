@@ -10,22 +10,46 @@ pub enum Error {
     InvalidInput = 4,
     EventNotFound = 5,
 
-    InvalidProductId = 6,
-    InvalidProductName = 7,
     InvalidOrigin = 8,
-    InvalidCategory = 9,
-
-    ProductIdTooLong = 10,
-    ProductNameTooLong = 11,
-    OriginTooLong = 12,
-    CategoryTooLong = 13,
-    DescriptionTooLong = 14,
 
     TooManyTags = 15,
-    TagTooLong = 16,
     TooManyCertifications = 17,
     TooManyMediaHashes = 18,
 
-    TooManyCustomFields = 19,
-    CustomFieldValueTooLong = 20,
-}
\ No newline at end of file
+    DuplicateSerial = 21,
+    AlreadyInitialized = 22,
+    InvalidTimestamp = 23,
+    InvalidGtin = 24,
+    UnitMismatch = 25,
+    OriginNotAllowed = 26,
+    MetadataTooLong = 27,
+    NoteTooLong = 28,
+    TooManyNotes = 29,
+    TemperatureExcursion = 30,
+    BatchTooLarge = 31,
+    ProductInCustody = 32,
+    AlreadyDelivered = 33,
+    TooManyChannels = 34,
+    NoPendingTransfer = 35,
+    TransferExpired = 36,
+    MemoTooLong = 37,
+    ProductInactive = 38,
+    ProductRecalled = 39,
+    SelfTransfer = 40,
+    TooEarly = 41,
+    InvalidClaim = 42,
+    ProductLocked = 43,
+    ProductRenounced = 44,
+    RequirementsNotMet = 45,
+    TooManyActors = 46,
+    NotInitialized = 47,
+    ContractPaused = 48,
+    NotRegistrar = 49,
+    AddressBlacklisted = 50,
+    ProductBurned = 51,
+    ProductArchived = 52,
+    ContractSunset = 53,
+    RateLimited = 54,
+    ClaimExpired = 55,
+    FeePaymentFailed = 56,
+}
@@ -0,0 +1,59 @@
+use soroban_sdk::{events::Topics, Address, Env, IntoVal, String, Symbol, Val};
+
+use crate::storage;
+use crate::types::{CheckpointKind, ProductStatus, Role};
+
+/// First topic of every product event.
+pub const PRODUCT: &str = "product";
+pub const REGISTERED: &str = "registered";
+pub const TRANSFERRED: &str = "transferred";
+pub const AUTH: &str = "auth";
+pub const STATUS: &str = "status";
+pub const CHECKPOINT: &str = "checkpoint";
+/// First topic of events about an owner rather than one product.
+pub const OWNER: &str = "owner";
+
+fn topic(env: &Env, name: &str) -> Symbol {
+    Symbol::new(env, name)
+}
+
+/// Publishes `(event_seq, payload)` as the event data, where `event_seq` is
+/// the contract-wide seq of the call publishing it. The payloads documented
+/// below are the second element.
+fn publish<T: Topics, D: IntoVal<Env, Val>>(env: &Env, topics: T, payload: D) {
+    env.events().publish(topics, (storage::get_event_seq(env), payload.into_val(env)));
+}
+
+/// Topics `("product", "registered", owner)`, data payload `(id, origin, created_at)`.
+pub fn product_registered(env: &Env, owner: &Address, product_id: u64, origin: &String, created_at: u64) {
+    publish(env, (topic(env, PRODUCT), topic(env, REGISTERED), owner.clone()), (product_id, origin.clone(), created_at));
+}
+
+/// Topics `("product", "transferred", id)`, payload `(old_owner, new_owner, timestamp)`.
+pub fn product_transferred(env: &Env, product_id: u64, old_owner: &Address, new_owner: &Address) {
+    publish(env, (topic(env, PRODUCT), topic(env, TRANSFERRED), product_id), (old_owner.clone(), new_owner.clone(), env.ledger().timestamp()));
+}
+
+/// Topics `("product", "auth", id)`, payload `(actor, granted, role)`. A
+/// revocation carries the role the actor held until then.
+pub fn product_auth(env: &Env, product_id: u64, actor: &Address, granted: bool, role: Role) {
+    publish(env, (topic(env, PRODUCT), topic(env, AUTH), product_id), (actor.clone(), granted, role));
+}
+
+/// Topics `("owner", "auth", owner)`, payload `(actor, granted)`, for grants
+/// covering every product the owner holds.
+pub fn owner_auth(env: &Env, owner: &Address, actor: &Address, granted: bool) {
+    publish(env, (topic(env, OWNER), topic(env, AUTH), owner.clone()), (actor.clone(), granted));
+}
+
+/// Topics `("product", "status", id)`, payload `(old, new)`; `new` is `None`
+/// once the product is burned.
+pub fn status_changed(env: &Env, product_id: u64, old: ProductStatus, new: Option<ProductStatus>) {
+    publish(env, (topic(env, PRODUCT), topic(env, STATUS), product_id), (old, new));
+}
+
+/// Topics `("product", "checkpoint", id)`, payload `(seq, kind, location)`.
+/// The note stays in storage; read it with `get_checkpoint`.
+pub fn checkpoint_added(env: &Env, product_id: u64, seq: u32, kind: CheckpointKind, location: &String) {
+    publish(env, (topic(env, PRODUCT), topic(env, CHECKPOINT), product_id), (seq, kind, location.clone()));
+}
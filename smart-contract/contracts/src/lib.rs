@@ -5,10 +5,11 @@ mod types;
 mod storage;
 mod error;
 mod validation;
+mod events;
 
 #[cfg(test)]
 mod test;
 
 pub use contract::*;
 pub use types::*;
-pub use error::*;
\ No newline at end of file
+pub use error::*;
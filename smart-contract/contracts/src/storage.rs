@@ -1,92 +1,834 @@
-use soroban_sdk::{contracttype, Address, String};
+use soroban_sdk::{contracttype, Address, Env, Map, String, Symbol, Vec};
+
+use crate::events;
+use crate::types::{PendingTransfer, Product, ProductStatus, Role};
 
 #[contracttype]
 #[derive(Clone)]
 pub enum DataKey {
+    // Contract admin
+    Admin,
+
     // Global counters
     TotalProducts,
     ActiveProducts,
-    
+
     // Product storage: ProductId -> Product
     Product(u64),
-    
+
     // Global Index: Index -> ProductId
     AllProductsIndex(u64),
-    
-    // Owner Index: (Owner, Index) -> ProductId
+
+    // Owner Index: (Owner, Index) -> ProductId, tombstoned on transfer like
+    // the origin index
     OwnerProductIndex(Address, u64),
     OwnerProductCount(Address),
-    
+    // ProductId -> slot currently held in its owner's index
+    OwnerIndexPos(u64),
+
     // Origin Index: (Origin, Index) -> ProductId
-    OriginProductIndex(String, u64), 
+    // Slots are tombstoned (removed) when a product leaves an origin, so
+    // OriginProductCount is the number of slots ever used, not live entries.
+    OriginProductIndex(String, u64),
     OriginProductCount(String),
+    // ProductId -> slot currently held in its origin's index
+    OriginIndexPos(u64),
+
+    // ProductId -> origins appended after the primary origin, in order
+    OriginChain(u64),
+    // Secondary origin index over appended chain entries: (Origin, Index) -> ProductId
+    AnyOriginIndex(String, u64),
+    AnyOriginCount(String),
+
+    // Serial Index: Serial -> ProductId, and ProductId -> Serial
+    SerialIndex(String),
+    ProductSerial(u64),
+
+    // Tag Index: (Tag, Index) -> ProductId, tombstoned like the origin index
+    TagProductIndex(Symbol, u64),
+    TagProductCount(Symbol),
+    // ProductId -> (Tag -> slot in that tag's index)
+    ProductTags(u64),
+
+    // GTIN Index: (GTIN, Index) -> ProductId; several units may share a GTIN
+    GtinIndex(String, u64),
+    GtinCount(String),
+    // ProductId -> (GTIN, slot in that GTIN's index)
+    ProductGtin(u64),
+
+    // Lot Index: (Lot, Index) -> ProductId
+    LotIndex(String, u64),
+    LotCount(String),
+    // ProductId -> (Lot, slot in that lot's index)
+    ProductLot(u64),
+
+    // ProductId -> Quantity
+    Quantity(u64),
+
+    // ProductId -> Vec<MediaRef>
+    Media(u64),
+
+    // Origin whitelist: Origin -> slot, (Index) -> Origin, slot count,
+    // and the instance flag that turns enforcement on
+    AllowedOrigin(String),
+    AllowedOriginIndex(u64),
+    AllowedOriginCount,
+    OriginWhitelistEnforced,
+
+    // Templates: TemplateId -> ProductTemplate, with a per-creator index
+    Template(u64),
+    TemplateCount,
+    OwnerTemplateIndex(Address, u64),
+    OwnerTemplateCount(Address),
+
+    // Approved certifiers: Certifier -> bool
+    Certifier(Address),
+    // Certifications: (ProductId, Seq) -> Certification, with a per-product count
+    Certification(u64, u32),
+    CertificationCount(u64),
+
+    // Verified brands: Brand -> display name
+    Brand(Address),
+    // ProductId -> Brand that attested manufacturing it
+    BrandClaim(u64),
+    // (ProductId, Brand) -> true when the brand publicly disowns the product
+    Disavowed(u64, Address),
+
+    // Notes: (ProductId, Seq) -> ProductNote, with a per-product count
+    Note(u64, u32),
+    NoteCount(u64),
+
+    // Tracking events: (ProductId, Seq) -> Checkpoint, with a per-product count
+    Checkpoint(u64, u32),
+    CheckpointCount(u64),
+    // ProductId -> true once a Delivery checkpoint is recorded
+    Delivered(u64),
+    // ProductId -> true while an Exception checkpoint is unresolved
+    OpenException(u64),
+
+    // Temperature log: (ProductId, Seq) -> TempReading, and the running
+    // TempStats whose count doubles as the sequence counter
+    TempReading(u64, u32),
+    TempStats(u64),
+
+    // Authorized actors: (ProductId, Actor) -> bool
+    Auth(u64, Address),
+}
+
+/// Keys for tracking and telemetry state. Kept apart from `DataKey`, which
+/// is at the 50-variant limit of a contract union type.
+#[contracttype]
+#[derive(Clone)]
+pub enum TrackingKey {
+    // ProductId -> (min, max) allowed temperature in hundredths of a degree
+    TempLimits(u64),
+    // ProductId -> number of out-of-range readings ever recorded
+    BreachCount(u64),
+    // ProductId -> true while a temperature excursion is unacknowledged
+    Excursion(u64),
+    // Sensor channels: (ProductId, Channel, Seq) -> SensorReading, the
+    // channel's ChannelStats (whose count is the sequence counter), the
+    // product's channel list, and the newest timestamp across all channels
+    ChannelReading(u64, Symbol, u32),
+    ChannelStats(u64, Symbol),
+    ProductChannels(u64),
+    LastReadingAt(u64),
+    // ProductId -> carrier physically holding the goods
+    Custodian(u64),
+    // ProductId -> true when the owner may transfer while goods are in custody
+    TransferInCustody(u64),
+    // Custody log: (ProductId, Seq) -> CustodyEntry, with a per-product count
+    CustodyLog(u64, u32),
+    CustodyCount(u64),
+    // ProductId -> promised delivery time
+    DeliveryDeadline(u64),
+    // ProductId -> address nominated to sign for delivery
+    Consignee(u64),
+    // ProductId -> ProofOfDelivery
+    Pod(u64),
+    // ProductId -> Vec<CheckpointKind> of every kind logged so far
+    SeenKinds(u64),
+    // Planned route: ProductId -> Vec<String> of waypoints, how many have
+    // been reached in order, and whether a checkpoint strayed from the plan
+    Route(u64),
+    RouteProgress(u64),
+    RouteDeviation(u64),
+    // (ProductId, Seq) -> owner's reason for disputing that checkpoint
+    CheckpointDispute(u64, u32),
+    // (ProductId, Seq) -> CheckpointCorrection
+    CheckpointCorrection(u64, u32),
+    // Actor Index: (Actor, Index) -> (ProductId, Seq) of checkpoints they logged
+    ActorCheckpointIndex(Address, u64),
+    ActorCheckpointCount(Address),
+    // ProductId -> current ETA, and ProductId -> Vec<EtaRevision> of recent revisions
+    Eta(u64),
+    EtaHistory(u64),
+    // (ProductId, Seq) -> ActivityEntry, ProductId -> entries written, and
+    // ProductId -> calls made after the log filled up
+    ActivityLog(u64, u32),
+    ActivityCount(u64),
+    ActivityOverflow(u64),
+    // (ProductId, Seq) -> AuditEntry and ProductId -> entries written; never trimmed
+    AuditLog(u64, u32),
+    AuditCount(u64),
+    // ProductId -> disputed checkpoints still waiting for a correction
+    OpenDisputes(u64),
+}
+
+/// Keys for authorization state beyond the per-actor `DataKey::Auth` flag.
+#[contracttype]
+#[derive(Clone)]
+pub enum AuthKey {
+    // ProductId -> Vec<Address> of actors currently holding a grant
+    Actors(u64),
+    // (ProductId, Actor) -> Role; grants without one predate roles and act as Updater
+    Role(u64, Address),
+    // (ProductId, Actor) -> last timestamp the grant is valid; absent for permanent grants
+    Expiry(u64, Address),
+    // (Owner, Actor) -> true when the actor may act on all of the owner's products
+    Global(Address, Address),
+    // Owner -> Vec<Address> of globally authorized actors
+    GlobalActors(Address),
+    // (Actor, Seq) -> ProductId the actor was granted on; revoked slots are removed
+    ActorProductIndex(Address, u64),
+    // Actor -> number of ActorProductIndex slots ever written
+    ActorProductCount(Address),
+    // (ProductId, Actor) -> the actor's slot in ActorProductIndex
+    ActorIndexPos(u64, Address),
+    // (ProductId, Actor) -> Role offered but not yet accepted
+    Invite(u64, Address),
+    // Owner -> true when actors may only be added by invitation
+    InviteOnly(Address),
+    // Operator -> Vec<Symbol> of scopes the admin granted contract-wide
+    OperatorScopes(Address),
+    // Vec<Address> of operators holding at least one scope
+    Operators,
+    // ProductId -> true when every transfer should drop the actor grants
+    ClearOnTransfer(u64),
+    // ProductId -> Vec<Address> sharing ownership with the primary owner
+    CoOwners(u64),
+    // ProductId -> owners that must sign a transfer; absent means all of them
+    CoOwnerThreshold(u64),
+}
+
+/// Keys for ownership transfer state.
+#[contracttype]
+#[derive(Clone)]
+pub enum TransferKey {
+    // ProductId -> PendingTransfer awaiting the recipient's acceptance
+    Pending(u64),
+    // ProductId -> open TransferClaim; locks the product until redeemed or revoked
+    Claim(u64),
+    // ProductId -> TransferWindow of the latest transfer; locks the product
+    // until final
+    Window(u64),
+    // ProductId -> ScheduledTransfer waiting for its time
+    Scheduled(u64),
+    // ProductId -> agent the owner approved to transfer on their behalf
+    Agent(u64),
+    // (ProductId, Seq) -> TransferRecord, seq 0 being the registration, and
+    // the number of transfers since
+    History(u64, u32),
+    HistoryCount(u64),
+    // (ProductId, Seq) -> admin who forced that transfer
+    ForcedBy(u64, u32),
+    // ProductId -> Vec<CheckpointKind> that must be logged before a transfer
+    Requirements(u64),
+    // ProductId -> true once the owner has renounced the product
+    Renounced(u64),
+    // Instance counter of completed transfers, and per-sender persistent counts
+    TotalTransfers,
+    SentCount(Address),
+    // Recipient inbox: (Recipient, Index) -> ProductId with a proposal to
+    // them, tombstoned when it is accepted, cancelled or replaced, the
+    // recipient's slot count, and ProductId -> slot held
+    IncomingTransferIndex(Address, u64),
+    IncomingTransferCount(Address),
+    IncomingTransferPos(u64),
+}
+
+/// Keys for registration fee arrangements between users.
+#[contracttype]
+#[derive(Clone)]
+pub enum FeeKey {
+    // Beneficiary -> Sponsorship covering their registration fees
+    Sponsorship(Address),
+}
+
+/// Keys for contract-wide settings managed by the admin.
+#[contracttype]
+#[derive(Clone)]
+pub enum AdminKey {
+    // Instance: address proposed to take over as admin
+    PendingAdmin,
+    // Instance: true while the admin has paused state changes
+    Paused,
+    // Instance: Config; defaults apply until the admin sets one
+    Config,
+    // Instance: RegistrationFee; registration is free while absent
+    Fee,
+    // Vec<Address> of vetted registrars
+    Registrars,
+    // Instance: true when only registrars may register products
+    RegistrationRestricted,
+    // Address -> true while the admin has blacklisted it
+    Blacklisted(Address),
+    // Instance: data layout version, advanced by `migrate`
+    ContractVersion,
+    // Instance: true once the deployment is frozen for good
+    Sunset,
+    // Instance: deployment that replaces this one after a sunset
+    Successor,
+    // Address -> (window start, registrations in that window)
+    RegWindow(Address),
+    // Address -> true when registration rate limits do not apply to it
+    RateLimitExempt(Address),
+    // Instance: human-readable deployment name shown by get_contract_info
+    Name,
+}
+
+/// Keys for secondary listing indexes. Kept apart from `DataKey`, which is
+/// at the 50-variant limit of a contract union type.
+#[contracttype]
+#[derive(Clone)]
+pub enum IndexKey {
+    // Registration day index: (Day, Index) -> ProductId, where Day is the
+    // ledger timestamp divided by a day's seconds, with a per-day slot count
+    DayIndex(u64, u64),
+    DayCount(u64),
+    // Owner-and-origin index: (Owner, Origin, Index) -> ProductId, tombstoned
+    // when either changes, with its slot count and ProductId -> slot held
+    OwnerOriginIndex(Address, String, u64),
+    OwnerOriginCount(Address, String),
+    OwnerOriginPos(u64),
+    // Origins in the order first seen: (Index) -> Origin, the instance slot
+    // count, and Origin -> its slot once listed; entries are never removed
+    OriginList(u64),
+    OriginListCount,
+    OriginSeen(String),
+    // Recent-activity ring buffer: (Slot) -> ActivityItem, and the instance
+    // count of items ever written, which is the newest item's seq
+    RecentEvent(u32),
+    RecentHead,
+    // Status index: ProductId -> ProductStatus, (Status, Index) -> ProductId
+    // tombstoned when the product moves on, the status's slot count,
+    // ProductId -> slot held, and the status's live product count
+    Status(u64),
+    StatusIndex(ProductStatus, u64),
+    StatusSlots(ProductStatus),
+    StatusPos(u64),
+    StatusCount(ProductStatus),
+    // Alert index: ProductId -> bitmask of raised alerts, (Index) -> ProductId
+    // for products with any alert, tombstoned once all clear, the slot
+    // count, and ProductId -> slot held
+    Alerts(u64),
+    AlertIndex(u64),
+    AlertSlots,
+    AlertPos(u64),
+    // Instance: Vec<(Origin, registrations, first-seen slot)> of the busiest
+    // origins, best first
+    TopOrigins,
+}
+
+/// Keys for running totals behind the statistics views.
+#[contracttype]
+#[derive(Clone)]
+pub enum StatsKey {
+    // Instance: products that still exist but are no longer active
+    InactiveProducts,
+    // Instance: addresses that have registered at least one product
+    TotalOwners,
+    // Instance: timestamp of the newest registration
+    LastRegistrationAt,
+    // Address -> products it has registered
+    RegisteredBy(Address),
+    // Origin -> OriginStats
+    Origin(String),
+    // Address -> products it holds now, and products transferred to it
+    Owned(Address),
+    Received(Address),
+    // Instance: mutations recorded so far across all products
+    EventSeq,
+    // Ledger day -> transfers made that day; registrations per day are the
+    // day index's `IndexKey::DayCount`
+    DailyTransfers(u64),
+}
+
+/// Keys recording how a product left the registry.
+#[contracttype]
+#[derive(Clone)]
+pub enum LifecycleKey {
+    // ProductId -> true once the product has been burned
+    Burned(u64),
+}
+
+pub fn put_product(env: &Env, product: &Product) {
+    env.storage()
+        .persistent()
+        .set(&DataKey::Product(product.id), product);
+}
+
+pub fn get_product(env: &Env, product_id: u64) -> Option<Product> {
+    env.storage().persistent().get(&DataKey::Product(product_id))
+}
+
+pub fn remove_product(env: &Env, product_id: u64) {
+    env.storage().persistent().remove(&DataKey::Product(product_id));
+}
+
+/// Bumps a persistent entry to live `extend_to` more ledgers, if it exists.
+pub fn extend_if_present<K>(env: &Env, key: &K, extend_to: u32)
+where
+    K: soroban_sdk::IntoVal<Env, soroban_sdk::Val>,
+{
+    if env.storage().persistent().has(key) {
+        env.storage().persistent().extend_ttl(key, extend_to, extend_to);
+    }
+}
+
+/// Keeps a product's record, its index slots and its grants from being
+/// archived for another `extend_to` ledgers.
+pub fn extend_product_ttl(env: &Env, product: &Product, extend_to: u32) {
+    let id = product.id;
+    extend_if_present(env, &DataKey::Product(id), extend_to);
+    extend_if_present(env, &DataKey::AllProductsIndex(id), extend_to);
+    if let Some(pos) = env.storage().persistent().get::<DataKey, u64>(&DataKey::OwnerIndexPos(id)) {
+        extend_if_present(env, &DataKey::OwnerIndexPos(id), extend_to);
+        extend_if_present(env, &DataKey::OwnerProductIndex(product.owner.clone(), pos), extend_to);
+    }
+    if let Some(pos) = env.storage().persistent().get::<DataKey, u64>(&DataKey::OriginIndexPos(id)) {
+        extend_if_present(env, &DataKey::OriginIndexPos(id), extend_to);
+        extend_if_present(env, &DataKey::OriginProductIndex(product.origin.clone(), pos), extend_to);
+    }
+    extend_if_present(env, &AuthKey::Actors(id), extend_to);
+    for actor in get_actors(env, id).iter() {
+        extend_if_present(env, &DataKey::Auth(id, actor.clone()), extend_to);
+        extend_if_present(env, &AuthKey::Role(id, actor.clone()), extend_to);
+        extend_if_present(env, &AuthKey::Expiry(id, actor.clone()), extend_to);
+        if let Some(pos) = env.storage().persistent().get::<AuthKey, u64>(&AuthKey::ActorIndexPos(id, actor.clone())) {
+            extend_if_present(env, &AuthKey::ActorIndexPos(id, actor.clone()), extend_to);
+            extend_if_present(env, &AuthKey::ActorProductIndex(actor, pos), extend_to);
+        }
+    }
+}
+
+pub fn append_owner_index(env: &Env, owner: &Address, product_id: u64) {
+    let mut owner_count: u64 = env.storage().persistent().get(&DataKey::OwnerProductCount(owner.clone())).unwrap_or(0);
+    owner_count += 1;
+    env.storage().persistent().set(&DataKey::OwnerProductIndex(owner.clone(), owner_count), &product_id);
+    env.storage().persistent().set(&DataKey::OwnerProductCount(owner.clone()), &owner_count);
+    env.storage().persistent().set(&DataKey::OwnerIndexPos(product_id), &owner_count);
+}
+
+pub fn remove_owner_index(env: &Env, owner: &Address, product_id: u64) {
+    if let Some(pos) = env.storage().persistent().get::<DataKey, u64>(&DataKey::OwnerIndexPos(product_id)) {
+        env.storage().persistent().remove(&DataKey::OwnerProductIndex(owner.clone(), pos));
+        env.storage().persistent().remove(&DataKey::OwnerIndexPos(product_id));
+    }
+}
+
+pub fn append_origin_index(env: &Env, origin: &String, product_id: u64) {
+    let mut origin_count: u64 = env.storage().persistent().get(&DataKey::OriginProductCount(origin.clone())).unwrap_or(0);
+    origin_count += 1;
+    env.storage().persistent().set(&DataKey::OriginProductIndex(origin.clone(), origin_count), &product_id);
+    env.storage().persistent().set(&DataKey::OriginProductCount(origin.clone()), &origin_count);
+    env.storage().persistent().set(&DataKey::OriginIndexPos(product_id), &origin_count);
+}
+
+pub fn remove_origin_index(env: &Env, origin: &String, product_id: u64) {
+    if let Some(pos) = env.storage().persistent().get::<DataKey, u64>(&DataKey::OriginIndexPos(product_id)) {
+        env.storage().persistent().remove(&DataKey::OriginProductIndex(origin.clone(), pos));
+        env.storage().persistent().remove(&DataKey::OriginIndexPos(product_id));
+    }
+}
+
+pub fn append_day_index(env: &Env, day: u64, product_id: u64) {
+    let count: u64 = env.storage().persistent().get(&IndexKey::DayCount(day)).unwrap_or(0) + 1;
+    env.storage().persistent().set(&IndexKey::DayIndex(day, count), &product_id);
+    env.storage().persistent().set(&IndexKey::DayCount(day), &count);
+}
+
+pub fn append_owner_origin_index(env: &Env, owner: &Address, origin: &String, product_id: u64) {
+    let count_key = IndexKey::OwnerOriginCount(owner.clone(), origin.clone());
+    let count: u64 = env.storage().persistent().get(&count_key).unwrap_or(0) + 1;
+    env.storage().persistent().set(&IndexKey::OwnerOriginIndex(owner.clone(), origin.clone(), count), &product_id);
+    env.storage().persistent().set(&count_key, &count);
+    env.storage().persistent().set(&IndexKey::OwnerOriginPos(product_id), &count);
+}
+
+pub fn remove_owner_origin_index(env: &Env, owner: &Address, origin: &String, product_id: u64) {
+    if let Some(pos) = env.storage().persistent().get::<IndexKey, u64>(&IndexKey::OwnerOriginPos(product_id)) {
+        env.storage().persistent().remove(&IndexKey::OwnerOriginIndex(owner.clone(), origin.clone(), pos));
+        env.storage().persistent().remove(&IndexKey::OwnerOriginPos(product_id));
+    }
+}
+
+/// Adds `origin` to the distinct origin list the first time it is used,
+/// and returns its slot in that list.
+pub fn note_origin(env: &Env, origin: &String) -> u64 {
+    if let Some(slot) = env.storage().persistent().get::<IndexKey, u64>(&IndexKey::OriginSeen(origin.clone())) {
+        return slot;
+    }
+    let count: u64 = env.storage().instance().get(&IndexKey::OriginListCount).unwrap_or(0) + 1;
+    env.storage().persistent().set(&IndexKey::OriginList(count), origin);
+    env.storage().persistent().set(&IndexKey::OriginSeen(origin.clone()), &count);
+    env.storage().instance().set(&IndexKey::OriginListCount, &count);
+    count
+}
+
+pub fn get_status(env: &Env, product_id: u64) -> Option<ProductStatus> {
+    env.storage().persistent().get(&IndexKey::Status(product_id))
+}
+
+/// Moves a product into `status`'s index and live count, out of those of
+/// the status it held. `None` takes it out of the status index altogether.
+/// Every change publishes a status event except the first, which the
+/// registration event already implies.
+pub fn set_status(env: &Env, product_id: u64, status: Option<ProductStatus>) {
+    let persistent = env.storage().persistent();
+    let current = get_status(env, product_id);
+    if current == status {
+        return;
+    }
+    if let Some(old) = current {
+        if let Some(pos) = persistent.get::<IndexKey, u64>(&IndexKey::StatusPos(product_id)) {
+            persistent.remove(&IndexKey::StatusIndex(old, pos));
+        }
+        let live: u64 = persistent.get(&IndexKey::StatusCount(old)).unwrap_or(0);
+        persistent.set(&IndexKey::StatusCount(old), &live.saturating_sub(1));
+    }
+    match status {
+        Some(new) => {
+            let slots: u64 = persistent.get::<IndexKey, u64>(&IndexKey::StatusSlots(new)).unwrap_or(0) + 1;
+            persistent.set(&IndexKey::StatusIndex(new, slots), &product_id);
+            persistent.set(&IndexKey::StatusSlots(new), &slots);
+            persistent.set(&IndexKey::StatusPos(product_id), &slots);
+            let live: u64 = persistent.get(&IndexKey::StatusCount(new)).unwrap_or(0);
+            persistent.set(&IndexKey::StatusCount(new), &(live + 1));
+            persistent.set(&IndexKey::Status(product_id), &new);
+        }
+        None => {
+            persistent.remove(&IndexKey::StatusPos(product_id));
+            persistent.remove(&IndexKey::Status(product_id));
+        }
+    }
+    if let Some(old) = current {
+        events::status_changed(env, product_id, old, status);
+    }
+}
+
+pub fn get_event_seq(env: &Env) -> u64 {
+    env.storage().instance().get(&StatsKey::EventSeq).unwrap_or(0)
+}
+
+/// Advances the contract-wide event seq and returns the new value.
+pub fn next_event_seq(env: &Env) -> u64 {
+    let seq = get_event_seq(env) + 1;
+    env.storage().instance().set(&StatsKey::EventSeq, &seq);
+    seq
+}
+
+pub fn get_alerts(env: &Env, product_id: u64) -> u32 {
+    env.storage().persistent().get(&IndexKey::Alerts(product_id)).unwrap_or(0)
+}
+
+/// Stores a product's alert bitmask, entering it in the alert index when
+/// the first alert is raised and tombstoning its slot when the last clears.
+pub fn set_alerts(env: &Env, product_id: u64, alerts: u32) {
+    let persistent = env.storage().persistent();
+    let before = get_alerts(env, product_id);
+    if before == alerts {
+        return;
+    }
+    if alerts == 0 {
+        if let Some(pos) = persistent.get::<IndexKey, u64>(&IndexKey::AlertPos(product_id)) {
+            persistent.remove(&IndexKey::AlertIndex(pos));
+            persistent.remove(&IndexKey::AlertPos(product_id));
+        }
+        persistent.remove(&IndexKey::Alerts(product_id));
+        return;
+    }
+    if before == 0 {
+        let slots: u64 = env.storage().instance().get::<IndexKey, u64>(&IndexKey::AlertSlots).unwrap_or(0) + 1;
+        persistent.set(&IndexKey::AlertIndex(slots), &product_id);
+        persistent.set(&IndexKey::AlertPos(product_id), &slots);
+        env.storage().instance().set(&IndexKey::AlertSlots, &slots);
+    }
+    persistent.set(&IndexKey::Alerts(product_id), &alerts);
+}
+
+/// Stores a proposal and files it in the recipient's inbox, replacing any
+/// proposal the product had before.
+pub fn put_pending_transfer(env: &Env, product_id: u64, pending: &PendingTransfer) {
+    clear_pending_transfer(env, product_id);
+    let persistent = env.storage().persistent();
+    let count_key = TransferKey::IncomingTransferCount(pending.to.clone());
+    let count: u64 = persistent.get(&count_key).unwrap_or(0) + 1;
+    persistent.set(&TransferKey::IncomingTransferIndex(pending.to.clone(), count), &product_id);
+    persistent.set(&count_key, &count);
+    persistent.set(&TransferKey::IncomingTransferPos(product_id), &count);
+    persistent.set(&TransferKey::Pending(product_id), pending);
+}
+
+/// Drops the product's proposal, if any, and its inbox slot.
+pub fn clear_pending_transfer(env: &Env, product_id: u64) {
+    let persistent = env.storage().persistent();
+    if let Some(pending) = persistent.get::<TransferKey, PendingTransfer>(&TransferKey::Pending(product_id)) {
+        if let Some(pos) = persistent.get::<TransferKey, u64>(&TransferKey::IncomingTransferPos(product_id)) {
+            persistent.remove(&TransferKey::IncomingTransferIndex(pending.to, pos));
+            persistent.remove(&TransferKey::IncomingTransferPos(product_id));
+        }
+        persistent.remove(&TransferKey::Pending(product_id));
+    }
+}
+
+pub fn get_product_tags(env: &Env, product_id: u64) -> Map<Symbol, u64> {
+    env.storage()
+        .persistent()
+        .get(&DataKey::ProductTags(product_id))
+        .unwrap_or(Map::new(env))
+}
+
+pub fn put_product_tags(env: &Env, product_id: u64, tags: &Map<Symbol, u64>) {
+    env.storage()
+        .persistent()
+        .set(&DataKey::ProductTags(product_id), tags);
+}
+
+/// Appends the product to the tag's index and returns the slot it took.
+pub fn append_tag_index(env: &Env, tag: &Symbol, product_id: u64) -> u64 {
+    let mut count: u64 = env.storage().persistent().get(&DataKey::TagProductCount(tag.clone())).unwrap_or(0);
+    count += 1;
+    env.storage().persistent().set(&DataKey::TagProductIndex(tag.clone(), count), &product_id);
+    env.storage().persistent().set(&DataKey::TagProductCount(tag.clone()), &count);
+    count
+}
+
+pub fn get_actors(env: &Env, product_id: u64) -> Vec<Address> {
+    env.storage()
+        .persistent()
+        .get(&AuthKey::Actors(product_id))
+        .unwrap_or(Vec::new(env))
+}
+
+/// Grants or revokes an actor, keeping the product's actor list in step.
+pub fn set_auth(env: &Env, product_id: u64, actor: &Address, value: bool) {
+    let mut actors = get_actors(env, product_id);
+    let pos = actors.first_index_of(actor);
+    if value {
+        env.storage()
+            .persistent()
+            .set(&DataKey::Auth(product_id, actor.clone()), &true);
+        if pos.is_none() {
+            actors.push_back(actor.clone());
+            env.storage().persistent().set(&AuthKey::Actors(product_id), &actors);
+            append_actor_index(env, actor, product_id);
+        }
+    } else {
+        drop_grant(env, product_id, actor);
+        if let Some(pos) = pos {
+            actors.remove(pos);
+            env.storage().persistent().set(&AuthKey::Actors(product_id), &actors);
+        }
+    }
+}
+
+/// Grants an actor `role`, replacing any role and expiry it held before,
+/// and publishes a grant event.
+pub fn set_role(env: &Env, product_id: u64, actor: &Address, role: Role) {
+    set_auth(env, product_id, actor, true);
+    env.storage().persistent().set(&AuthKey::Role(product_id, actor.clone()), &role);
+    env.storage().persistent().remove(&AuthKey::Expiry(product_id, actor.clone()));
+    events::product_auth(env, product_id, actor, true, role);
+}
+
+/// Grants `role` until the end of the `expires_at` second.
+pub fn set_role_until(env: &Env, product_id: u64, actor: &Address, role: Role, expires_at: u64) {
+    set_role(env, product_id, actor, role);
+    env.storage().persistent().set(&AuthKey::Expiry(product_id, actor.clone()), &expires_at);
+}
+
+pub fn is_expired(env: &Env, product_id: u64, actor: &Address) -> bool {
+    env.storage()
+        .persistent()
+        .get::<AuthKey, u64>(&AuthKey::Expiry(product_id, actor.clone()))
+        .is_some_and(|expires_at| env.ledger().timestamp() > expires_at)
+}
+
+/// The role granted to `actor`, if any and not expired. Co-owners and
+/// actors the current owner has authorized globally act as Updaters. The
+/// owner and custodian hold no grant.
+pub fn get_role(env: &Env, product_id: u64, actor: &Address) -> Option<Role> {
+    let granted: bool = env.storage().persistent().get(&DataKey::Auth(product_id, actor.clone())).unwrap_or(false);
+    if granted && !is_expired(env, product_id, actor) {
+        return Some(
+            env.storage()
+                .persistent()
+                .get(&AuthKey::Role(product_id, actor.clone()))
+                .unwrap_or(Role::Updater),
+        );
+    }
+    if get_co_owners(env, product_id).contains(actor) {
+        return Some(Role::Updater);
+    }
+    let product = get_product(env, product_id)?;
+    if is_global_actor(env, &product.owner, actor) {
+        return Some(Role::Updater);
+    }
+    None
+}
+
+pub fn get_co_owners(env: &Env, product_id: u64) -> Vec<Address> {
+    env.storage()
+        .persistent()
+        .get(&AuthKey::CoOwners(product_id))
+        .unwrap_or(Vec::new(env))
+}
+
+/// How many of the owners must sign a transfer: all of them unless the
+/// owners agreed on a lower threshold.
+pub fn co_owner_threshold(env: &Env, product_id: u64) -> u32 {
+    let owners = get_co_owners(env, product_id).len() + 1;
+    env.storage()
+        .persistent()
+        .get(&AuthKey::CoOwnerThreshold(product_id))
+        .unwrap_or(owners)
+}
+
+pub fn is_global_actor(env: &Env, owner: &Address, actor: &Address) -> bool {
+    env.storage()
+        .persistent()
+        .get(&AuthKey::Global(owner.clone(), actor.clone()))
+        .unwrap_or(false)
+}
+
+pub fn get_global_actors(env: &Env, owner: &Address) -> Vec<Address> {
+    env.storage()
+        .persistent()
+        .get(&AuthKey::GlobalActors(owner.clone()))
+        .unwrap_or(Vec::new(env))
+}
+
+/// Grants or revokes an actor on every product `owner` holds, keeping the
+/// owner's global actor list in step.
+pub fn set_global_auth(env: &Env, owner: &Address, actor: &Address, value: bool) {
+    let mut actors = get_global_actors(env, owner);
+    let pos = actors.first_index_of(actor);
+    if value {
+        env.storage().persistent().set(&AuthKey::Global(owner.clone(), actor.clone()), &true);
+        if pos.is_none() {
+            actors.push_back(actor.clone());
+            env.storage().persistent().set(&AuthKey::GlobalActors(owner.clone()), &actors);
+            events::owner_auth(env, owner, actor, true);
+        }
+    } else {
+        env.storage().persistent().remove(&AuthKey::Global(owner.clone(), actor.clone()));
+        if let Some(pos) = pos {
+            actors.remove(pos);
+            env.storage().persistent().set(&AuthKey::GlobalActors(owner.clone()), &actors);
+            events::owner_auth(env, owner, actor, false);
+        }
+    }
+}
+
+/// True when the actor's grant covers `role`. The current custodian counts
+/// as an Updater so it can log the leg it is carrying.
+pub fn has_role(env: &Env, product_id: u64, actor: &Address, role: Role) -> bool {
+    if get_role(env, product_id, actor).is_some_and(|granted| granted >= role) {
+        return true;
+    }
+    role <= Role::Updater && get_custodian(env, product_id).as_ref() == Some(actor)
+}
+
+/// Revokes every actor on the product and returns how many there were.
+pub fn clear_auth(env: &Env, product_id: u64) -> u32 {
+    let actors = get_actors(env, product_id);
+    for actor in actors.iter() {
+        drop_grant(env, product_id, &actor);
+    }
+    env.storage().persistent().remove(&AuthKey::Actors(product_id));
+    actors.len()
+}
+
+/// Removes one actor's grant records, leaving the product's actor list alone.
+/// Every revocation ends here, so this is where the revoke event goes out.
+fn drop_grant(env: &Env, product_id: u64, actor: &Address) {
+    let auth_key = DataKey::Auth(product_id, actor.clone());
+    if env.storage().persistent().has(&auth_key) {
+        let role = env.storage().persistent().get(&AuthKey::Role(product_id, actor.clone())).unwrap_or(Role::Updater);
+        events::product_auth(env, product_id, actor, false, role);
+    }
+    env.storage().persistent().remove(&auth_key);
+    env.storage().persistent().remove(&AuthKey::Role(product_id, actor.clone()));
+    env.storage().persistent().remove(&AuthKey::Expiry(product_id, actor.clone()));
+    remove_actor_index(env, actor, product_id);
+}
+
+fn append_actor_index(env: &Env, actor: &Address, product_id: u64) {
+    let count: u64 = env.storage().persistent().get(&AuthKey::ActorProductCount(actor.clone())).unwrap_or(0) + 1;
+    env.storage().persistent().set(&AuthKey::ActorProductIndex(actor.clone(), count), &product_id);
+    env.storage().persistent().set(&AuthKey::ActorProductCount(actor.clone()), &count);
+    env.storage().persistent().set(&AuthKey::ActorIndexPos(product_id, actor.clone()), &count);
+}
+
+fn remove_actor_index(env: &Env, actor: &Address, product_id: u64) {
+    let pos_key = AuthKey::ActorIndexPos(product_id, actor.clone());
+    if let Some(pos) = env.storage().persistent().get::<AuthKey, u64>(&pos_key) {
+        env.storage().persistent().remove(&AuthKey::ActorProductIndex(actor.clone(), pos));
+        env.storage().persistent().remove(&pos_key);
+    }
+}
+
+pub fn get_operator_scopes(env: &Env, operator: &Address) -> Vec<Symbol> {
+    env.storage()
+        .persistent()
+        .get(&AuthKey::OperatorScopes(operator.clone()))
+        .unwrap_or(Vec::new(env))
+}
+
+pub fn get_operators(env: &Env) -> Vec<Address> {
+    env.storage().persistent().get(&AuthKey::Operators).unwrap_or(Vec::new(env))
+}
+
+pub fn is_operator(env: &Env, operator: &Address, scope: &Symbol) -> bool {
+    get_operator_scopes(env, operator).contains(scope)
+}
+
+/// Adds or removes one scope, keeping the operator list to addresses that
+/// still hold at least one.
+pub fn set_operator_scope(env: &Env, operator: &Address, scope: &Symbol, value: bool) {
+    let mut scopes = get_operator_scopes(env, operator);
+    let pos = scopes.first_index_of(scope);
+    match (value, pos) {
+        (true, None) => scopes.push_back(scope.clone()),
+        (false, Some(pos)) => {
+            scopes.remove(pos);
+        }
+        _ => return,
+    }
+    let mut operators = get_operators(env);
+    let listed = operators.first_index_of(operator);
+    if scopes.is_empty() {
+        env.storage().persistent().remove(&AuthKey::OperatorScopes(operator.clone()));
+        if let Some(listed) = listed {
+            operators.remove(listed);
+            env.storage().persistent().set(&AuthKey::Operators, &operators);
+        }
+    } else {
+        env.storage().persistent().set(&AuthKey::OperatorScopes(operator.clone()), &scopes);
+        if listed.is_none() {
+            operators.push_back(operator.clone());
+            env.storage().persistent().set(&AuthKey::Operators, &operators);
+        }
+    }
+}
+
+pub fn get_custodian(env: &Env, product_id: u64) -> Option<Address> {
+    env.storage().persistent().get(&TrackingKey::Custodian(product_id))
+}
+
+/// True for actors granted access by the owner and for the current custodian.
+pub fn is_authorized(env: &Env, product_id: u64, actor: &Address) -> bool {
+    get_role(env, product_id, actor).is_some()
+        || get_custodian(env, product_id).as_ref() == Some(actor)
 }
- use soroban_sdk::{Address, Env, Vec};
- 
- use crate::{DataKey, Product, TrackingEvent};
- 
- pub fn has_product(env: &Env, product_id: &soroban_sdk::String) -> bool {
-     env.storage().persistent()
-         .has(&DataKey::Product(product_id.clone()))
- }
- 
- pub fn put_product(env: &Env, product: &Product) {
-     env.storage()
-         .persistent()
-         .set(&DataKey::Product(product.id.clone()), product);
- }
- 
- pub fn get_product(env: &Env, product_id: &soroban_sdk::String) -> Option<Product> {
-     env.storage()
-         .persistent()
-         .get(&DataKey::Product(product_id.clone()))
- }
- 
- pub fn put_product_event_ids(env: &Env, product_id: &soroban_sdk::String, ids: &Vec<u64>) {
-     env.storage()
-         .persistent()
-         .set(&DataKey::ProductEventIds(product_id.clone()), ids);
- }
- 
- pub fn get_product_event_ids(env: &Env, product_id: &soroban_sdk::String) -> Vec<u64> {
-     env.storage()
-         .persistent()
-         .get(&DataKey::ProductEventIds(product_id.clone()))
-         .unwrap_or(Vec::new(env))
- }
- 
- pub fn put_event(env: &Env, event: &TrackingEvent) {
-     env.storage()
-         .persistent()
-         .set(&DataKey::Event(event.event_id), event);
- }
- 
- pub fn get_event(env: &Env, event_id: u64) -> Option<TrackingEvent> {
-     env.storage().persistent().get(&DataKey::Event(event_id))
- }
- 
- pub fn next_event_id(env: &Env) -> u64 {
-     let mut seq: u64 = env.storage().persistent().get(&DataKey::EventSeq).unwrap_or(0);
-     seq += 1;
-     env.storage().persistent().set(&DataKey::EventSeq, &seq);
-     seq
- }
- 
- pub fn set_auth(env: &Env, product_id: &soroban_sdk::String, actor: &Address, value: bool) {
-     if value {
-         env.storage()
-             .persistent()
-             .set(&DataKey::Auth(product_id.clone(), actor.clone()), &true);
-     } else {
-         env.storage()
-             .persistent()
-             .remove(&DataKey::Auth(product_id.clone(), actor.clone()));
-     }
- }
- 
- pub fn is_authorized(env: &Env, product_id: &soroban_sdk::String, actor: &Address) -> bool {
-     env.storage()
-         .persistent()
-         .get(&DataKey::Auth(product_id.clone(), actor.clone()))
-         .unwrap_or(false)
- }
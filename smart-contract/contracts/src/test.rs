@@ -1,9 +1,8 @@
 #![cfg(test)]
 
 use super::*;
-use soroban_sdk::{testutils::Address as _, Address, Env, String};
-use soroban_sdk::{symbol_short, Address, BytesN, Env, Map, String, Symbol, Vec};
-use soroban_sdk::testutils::Address as _;
+use soroban_sdk::testutils::{Address as _, Events, Ledger};
+use soroban_sdk::{symbol_short, Address, Bytes, BytesN, Env, IntoVal, String, Symbol, Val, Vec};
 
 #[test]
 fn test_register_and_get_product() {
@@ -32,41 +31,6 @@ fn test_register_and_get_product() {
 fn test_pagination() {
     let env = Env::default();
     env.mock_all_auths();
-
-    let id = String::from_str(&env, "COFFEE-ETH-001");
-
-    let tags: Vec<String> = Vec::new(&env);
-    let certs: Vec<BytesN<32>> = Vec::new(&env);
-    let media: Vec<BytesN<32>> = Vec::new(&env);
-    let custom: Map<Symbol, String> = Map::new(&env);
-
-    let created = client.register_product(
-        &owner,
-        &id,
-        &String::from_str(&env, "Organic Coffee Beans"),
-        &String::from_str(&env, ""),
-        &String::from_str(&env, "Yirgacheffe, Ethiopia"),
-        &String::from_str(&env, "Coffee"),
-        &tags,
-        &certs,
-        &media,
-        &custom,
-    );
-
-    assert_eq!(created.id, id);
-    assert_eq!(created.owner, owner);
-    assert!(created.active);
-
-    let p = client.get_product(&id);
-    assert_eq!(p.id, id);
-    assert_eq!(p.owner, owner);
-    assert!(p.active);
-}
-
-#[test]
-fn test_duplicate_product_rejected() {
-    let env = Env::default();
-    env.mock_all_auths();
     let contract_id = env.register_contract(None, ChainLogisticsContract);
     let client = ChainLogisticsContractClient::new(&env, &contract_id);
 
@@ -140,45 +104,10 @@ fn test_stats() {
     let stats = client.get_stats();
     assert_eq!(stats.total_products, 2);
     assert_eq!(stats.active_products, 2);
-    let id = String::from_str(&env, "COFFEE-ETH-001");
-    let tags: Vec<String> = Vec::new(&env);
-    let certs: Vec<BytesN<32>> = Vec::new(&env);
-    let media: Vec<BytesN<32>> = Vec::new(&env);
-    let custom: Map<Symbol, String> = Map::new(&env);
-
-    client.register_product(
-        &owner,
-        &id,
-        &String::from_str(&env, "Organic Coffee Beans"),
-        &String::from_str(&env, ""),
-        &String::from_str(&env, "Yirgacheffe, Ethiopia"),
-        &String::from_str(&env, "Coffee"),
-        &tags,
-        &certs,
-        &media,
-        &custom,
-    );
-
-    let res = client.try_register_product(
-        &owner,
-        &id,
-        &String::from_str(&env, "Duplicate"),
-        &String::from_str(&env, ""),
-        &String::from_str(&env, "Somewhere"),
-        &String::from_str(&env, "Coffee"),
-        &tags,
-        &certs,
-        &media,
-        &custom,
-    );
-    match res {
-        Err(Ok(e)) => assert_eq!(e, Error::ProductAlreadyExists),
-        _ => panic!("expected ProductAlreadyExists"),
-    }
 }
 
 #[test]
-fn test_authorize_add_event_transfer() {
+fn test_authorize_and_transfer() {
     let env = Env::default();
     env.mock_all_auths();
     let contract_id = env.register_contract(None, ChainLogisticsContract);
@@ -188,142 +117,263 @@ fn test_authorize_add_event_transfer() {
     let processor = Address::generate(&env);
     let shipper = Address::generate(&env);
 
-    let id = String::from_str(&env, "COFFEE-ETH-001");
-    let tags: Vec<String> = Vec::new(&env);
-    let certs: Vec<BytesN<32>> = Vec::new(&env);
-    let media: Vec<BytesN<32>> = Vec::new(&env);
-    let custom: Map<Symbol, String> = Map::new(&env);
-
-    client.register_product(
+    let id = client.register_product(
         &farmer,
-        &id,
+        &String::from_str(&env, "Ethiopia"),
         &String::from_str(&env, "Organic Coffee Beans"),
-        &String::from_str(&env, ""),
-        &String::from_str(&env, "Yirgacheffe, Ethiopia"),
-        &String::from_str(&env, "Coffee"),
-        &tags,
-        &certs,
-        &media,
-        &custom,
     );
 
+    assert!(!client.is_authorized(&id, &processor));
     client.add_authorized_actor(&farmer, &id, &processor);
-
-    let h = BytesN::from_array(&env, &[0; 32]);
-    let event_id = client.add_tracking_event(
-        &processor,
-        &id,
-        &symbol_short!("PROC"),
-        &h,
-        &String::from_str(&env, ""),
-    );
-    let ids = client.get_product_event_ids(&id);
-    assert_eq!(ids.len(), 1);
-    assert_eq!(ids.get_unchecked(0), event_id);
+    assert!(client.is_authorized(&id, &processor));
 
     client.transfer_product(&farmer, &id, &shipper);
 
-    let p = client.get_product(&id);
+    let p = client.get_product(&id).unwrap();
     assert_eq!(p.owner, shipper);
+    assert!(client.is_authorized(&id, &shipper));
+    assert!(!client.is_authorized(&id, &farmer));
 }
 
 #[test]
-fn test_register_rejects_empty_id() {
+fn test_unauthorized_cannot_add_authorized_actor() {
     let env = Env::default();
     env.mock_all_auths();
     let contract_id = env.register_contract(None, ChainLogisticsContract);
     let client = ChainLogisticsContractClient::new(&env, &contract_id);
 
     let owner = Address::generate(&env);
-    let id = String::from_str(&env, "");
-
-    let tags: Vec<String> = Vec::new(&env);
-    let certs: Vec<BytesN<32>> = Vec::new(&env);
-    let media: Vec<BytesN<32>> = Vec::new(&env);
-    let custom: Map<Symbol, String> = Map::new(&env);
+    let attacker = Address::generate(&env);
+    let actor = Address::generate(&env);
 
-    let res = client.try_register_product(
+    let id = client.register_product(
         &owner,
-        &id,
+        &String::from_str(&env, "Ethiopia"),
         &String::from_str(&env, "Organic Coffee Beans"),
-        &String::from_str(&env, ""),
-        &String::from_str(&env, "Yirgacheffe, Ethiopia"),
-        &String::from_str(&env, "Coffee"),
-        &tags,
-        &certs,
-        &media,
-        &custom,
     );
+
+    let res = client.try_add_authorized_actor(&attacker, &id, &actor);
     match res {
-        Err(Ok(e)) => assert_eq!(e, Error::InvalidProductId),
-        _ => panic!("expected InvalidProductId"),
+        Err(Ok(e)) => assert_eq!(e, Error::Unauthorized),
+        _ => panic!("expected Unauthorized"),
     }
 }
 
 #[test]
-fn test_register_rejects_empty_origin() {
+fn test_ownership_preserves_authorized_actors() {
     let env = Env::default();
     env.mock_all_auths();
     let contract_id = env.register_contract(None, ChainLogisticsContract);
     let client = ChainLogisticsContractClient::new(&env, &contract_id);
 
     let owner = Address::generate(&env);
-    let id = String::from_str(&env, "COFFEE-ETH-001");
-
-    let tags: Vec<String> = Vec::new(&env);
-    let certs: Vec<BytesN<32>> = Vec::new(&env);
-    let media: Vec<BytesN<32>> = Vec::new(&env);
-    let custom: Map<Symbol, String> = Map::new(&env);
+    let new_owner = Address::generate(&env);
+    let actor = Address::generate(&env);
 
-    let res = client.try_register_product(
+    let id = client.register_product(
         &owner,
-        &id,
+        &String::from_str(&env, "Ethiopia"),
         &String::from_str(&env, "Organic Coffee Beans"),
-        &String::from_str(&env, ""),
-        &String::from_str(&env, ""),
-        &String::from_str(&env, "Coffee"),
-        &tags,
-        &certs,
-        &media,
-        &custom,
     );
+    client.add_authorized_actor(&owner, &id, &actor);
+
+    client.transfer_product(&owner, &id, &new_owner);
+    assert!(client.is_authorized(&id, &actor));
+
+    client.remove_authorized_actor(&new_owner, &id, &actor);
+    assert!(!client.is_authorized(&id, &actor));
+}
+
+#[test]
+fn test_missing_product_errors() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register_contract(None, ChainLogisticsContract);
+    let client = ChainLogisticsContractClient::new(&env, &contract_id);
+
+    let owner = Address::generate(&env);
+    let actor = Address::generate(&env);
+
+    assert_eq!(client.get_product(&42), None);
+    let res = client.try_add_authorized_actor(&owner, &42, &actor);
     match res {
-        Err(Ok(e)) => assert_eq!(e, Error::InvalidOrigin),
-        _ => panic!("expected InvalidOrigin"),
+        Err(Ok(e)) => assert_eq!(e, Error::ProductNotFound),
+        _ => panic!("expected ProductNotFound"),
     }
 }
 
 #[test]
-fn test_unauthorized_cannot_add_authorized_actor() {
+fn test_duplicate_serial_rejected() {
     let env = Env::default();
     env.mock_all_auths();
     let contract_id = env.register_contract(None, ChainLogisticsContract);
     let client = ChainLogisticsContractClient::new(&env, &contract_id);
 
     let owner = Address::generate(&env);
-    let attacker = Address::generate(&env);
-    let actor = Address::generate(&env);
-    let id = String::from_str(&env, "COFFEE-ETH-001");
+    let other = Address::generate(&env);
+    let origin = String::from_str(&env, "Vietnam");
+    let serial = String::from_str(&env, "SN-0001");
 
-    let tags: Vec<String> = Vec::new(&env);
-    let certs: Vec<BytesN<32>> = Vec::new(&env);
-    let media: Vec<BytesN<32>> = Vec::new(&env);
-    let custom: Map<Symbol, String> = Map::new(&env);
+    client.register_product_with_serial(&owner, &origin, &String::from_str(&env, "M"), &serial);
 
-    client.register_product(
-        &owner,
-        &id,
-        &String::from_str(&env, "Organic Coffee Beans"),
-        &String::from_str(&env, ""),
-        &String::from_str(&env, "Yirgacheffe, Ethiopia"),
-        &String::from_str(&env, "Coffee"),
-        &tags,
-        &certs,
-        &media,
-        &custom,
-    );
+    let res = client.try_register_product_with_serial(&other, &origin, &String::from_str(&env, "M"), &serial);
+    match res {
+        Err(Ok(e)) => assert_eq!(e, Error::DuplicateSerial),
+        _ => panic!("expected DuplicateSerial"),
+    }
 
-    let res = client.try_add_authorized_actor(&attacker, &id, &actor);
+    let res = client.try_register_product_with_serial(&owner, &origin, &String::from_str(&env, "M"), &String::from_str(&env, ""));
+    match res {
+        Err(Ok(e)) => assert_eq!(e, Error::InvalidInput),
+        _ => panic!("expected InvalidInput"),
+    }
+
+    assert_eq!(client.get_stats().total_products, 1);
+}
+
+#[test]
+fn test_serial_lookup_after_transfer() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register_contract(None, ChainLogisticsContract);
+    let client = ChainLogisticsContractClient::new(&env, &contract_id);
+
+    let owner = Address::generate(&env);
+    let buyer = Address::generate(&env);
+    let serial = String::from_str(&env, "SN-0001");
+
+    client.register_product(&owner, &String::from_str(&env, "Vietnam"), &String::from_str(&env, "M"));
+    let id = client.register_product_with_serial(&owner, &String::from_str(&env, "Vietnam"), &String::from_str(&env, "M"), &serial);
+    assert_eq!(client.get_product_serial(&1), None);
+
+    client.transfer_product(&owner, &id, &buyer);
+
+    let product = client.get_product_by_serial(&serial).unwrap();
+    assert_eq!(product.id, id);
+    assert_eq!(product.owner, buyer);
+    assert_eq!(client.get_product_serial(&id), Some(serial));
+    assert_eq!(client.get_product_by_serial(&String::from_str(&env, "SN-9999")), None);
+}
+
+#[test]
+fn test_serial_reusable_after_burn() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register_contract(None, ChainLogisticsContract);
+    let client = ChainLogisticsContractClient::new(&env, &contract_id);
+
+    let owner = Address::generate(&env);
+    let origin = String::from_str(&env, "Vietnam");
+    let serial = String::from_str(&env, "SN-0001");
+
+    let first = client.register_product_with_serial(&owner, &origin, &String::from_str(&env, "M"), &serial);
+    client.burn_product(&owner, &first);
+
+    assert_eq!(client.get_product(&first), None);
+    assert_eq!(client.get_product_by_serial(&serial), None);
+    assert_eq!(client.get_all_products(&0, &10).len(), 0);
+
+    let second = client.register_product_with_serial(&owner, &origin, &String::from_str(&env, "M"), &serial);
+    assert_eq!(second, 2);
+    assert_eq!(client.get_product_by_serial(&serial).unwrap().id, second);
+
+    let stats = client.get_stats();
+    assert_eq!(stats.total_products, 2);
+    assert_eq!(stats.active_products, 1);
+}
+
+#[test]
+fn test_update_origin_reindexes() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register_contract(None, ChainLogisticsContract);
+    let client = ChainLogisticsContractClient::new(&env, &contract_id);
+
+    let owner = Address::generate(&env);
+    let kenya = String::from_str(&env, "Kenya");
+    let kenia = String::from_str(&env, "Kenia");
+
+    client.register_product(&owner, &kenya, &String::from_str(&env, "P1")); // ID 1
+    client.register_product(&owner, &kenia, &String::from_str(&env, "P2")); // ID 2 (typo)
+    client.register_product(&owner, &kenia, &String::from_str(&env, "P3")); // ID 3 (typo)
+    assert_eq!(client.get_top_origins(), Vec::from_array(&env, [(kenia.clone(), 2u64), (kenya.clone(), 1)]));
+
+    client.update_origin(&owner, &2, &kenya);
+    assert_eq!(client.get_top_origins(), Vec::from_array(&env, [(kenya.clone(), 2u64), (kenia.clone(), 1)]));
+    assert_eq!(client.get_origin_stats(&kenia).total_registered, 1);
+
+    assert_eq!(client.get_product(&2).unwrap().origin, kenya);
+
+    let fixed = client.get_products_by_origin(&kenya, &0, &10);
+    assert_eq!(fixed.len(), 2);
+    assert_eq!(fixed.get(0).unwrap().id, 1);
+    assert_eq!(fixed.get(1).unwrap().id, 2);
+
+    let typo = client.get_products_by_origin(&kenia, &0, &10);
+    assert_eq!(typo.len(), 1);
+    assert_eq!(typo.get(0).unwrap().id, 3);
+
+    // Updating to the current origin is a no-op and does not duplicate the slot
+    client.update_origin(&owner, &2, &kenya);
+    assert_eq!(client.get_products_by_origin(&kenya, &0, &10).len(), 2);
+
+    let stats = client.get_stats();
+    assert_eq!(stats.total_products, 3);
+    assert_eq!(stats.active_products, 3);
+
+    // An origin left with no products drops off the board
+    client.update_origin(&owner, &3, &kenya);
+    assert_eq!(client.get_top_origins(), Vec::from_array(&env, [(kenya.clone(), 3u64)]));
+}
+
+#[test]
+fn test_origin_pagination_skips_tombstones() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register_contract(None, ChainLogisticsContract);
+    let client = ChainLogisticsContractClient::new(&env, &contract_id);
+
+    let owner = Address::generate(&env);
+    let origin = String::from_str(&env, "Peru");
+    let other = String::from_str(&env, "Chile");
+
+    for _ in 0..5 {
+        client.register_product(&owner, &origin, &String::from_str(&env, "M"));
+    }
+    // Vacate slot 2 of the Peru index
+    client.update_origin(&owner, &2, &other);
+
+    // Slots 1..=3 now hold only products 1 and 3
+    let page1 = client.get_products_by_origin(&origin, &0, &3);
+    assert_eq!(page1.len(), 2);
+    assert_eq!(page1.get(0).unwrap().id, 1);
+    assert_eq!(page1.get(1).unwrap().id, 3);
+
+    let page2 = client.get_products_by_origin(&origin, &3, &3);
+    assert_eq!(page2.len(), 2);
+    assert_eq!(page2.get(0).unwrap().id, 4);
+    assert_eq!(page2.get(1).unwrap().id, 5);
+
+    // Moving the product back appends it after the existing entries
+    client.update_origin(&owner, &2, &origin);
+    let all = client.get_products_by_origin(&origin, &0, &10);
+    assert_eq!(all.len(), 5);
+    assert_eq!(all.get(4).unwrap().id, 2);
+    assert_eq!(client.get_products_by_origin(&other, &0, &10).len(), 0);
+}
+
+#[test]
+fn test_update_origin_requires_owner() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register_contract(None, ChainLogisticsContract);
+    let client = ChainLogisticsContractClient::new(&env, &contract_id);
+
+    let owner = Address::generate(&env);
+    let stranger = Address::generate(&env);
+    let id = client.register_product(&owner, &String::from_str(&env, "Peru"), &String::from_str(&env, "M"));
+
+    let res = client.try_update_origin(&stranger, &id, &String::from_str(&env, "Chile"));
     match res {
         Err(Ok(e)) => assert_eq!(e, Error::Unauthorized),
         _ => panic!("expected Unauthorized"),
@@ -331,251 +381,5174 @@ fn test_unauthorized_cannot_add_authorized_actor() {
 }
 
 #[test]
-fn test_register_rejects_empty_name() {
+fn test_tags_index_products() {
     let env = Env::default();
     env.mock_all_auths();
     let contract_id = env.register_contract(None, ChainLogisticsContract);
     let client = ChainLogisticsContractClient::new(&env, &contract_id);
 
     let owner = Address::generate(&env);
-    let id = String::from_str(&env, "COFFEE-ETH-001");
+    let origin = String::from_str(&env, "Spain");
+    let organic = symbol_short!("organic");
+    let fragile = symbol_short!("fragile");
 
-    let tags: Vec<String> = Vec::new(&env);
-    let certs: Vec<BytesN<32>> = Vec::new(&env);
-    let media: Vec<BytesN<32>> = Vec::new(&env);
-    let custom: Map<Symbol, String> = Map::new(&env);
+    client.register_product(&owner, &origin, &String::from_str(&env, "P1")); // ID 1
+    client.register_product(&owner, &origin, &String::from_str(&env, "P2")); // ID 2
 
-    let res = client.try_register_product(
-        &owner,
-        &id,
-        &String::from_str(&env, ""),
-        &String::from_str(&env, ""),
-        &String::from_str(&env, "Yirgacheffe, Ethiopia"),
-        &String::from_str(&env, "Coffee"),
-        &tags,
-        &certs,
-        &media,
-        &custom,
-    );
-    match res {
-        Err(Ok(e)) => assert_eq!(e, Error::InvalidProductName),
-        _ => panic!("expected InvalidProductName"),
+    client.add_tag(&owner, &1, &organic);
+    client.add_tag(&owner, &1, &fragile);
+    client.add_tag(&owner, &2, &organic);
+    // Idempotent
+    client.add_tag(&owner, &1, &organic);
+
+    let tagged = client.get_products_by_tag(&organic, &0, &10);
+    assert_eq!(tagged.len(), 2);
+    assert_eq!(tagged.get(0).unwrap().id, 1);
+    assert_eq!(tagged.get(1).unwrap().id, 2);
+    assert_eq!(client.get_product_tags(&1).len(), 2);
+
+    client.remove_tag(&owner, &1, &organic);
+
+    let tagged = client.get_products_by_tag(&organic, &0, &10);
+    assert_eq!(tagged.len(), 1);
+    assert_eq!(tagged.get(0).unwrap().id, 2);
+
+    let fragile_products = client.get_products_by_tag(&fragile, &0, &10);
+    assert_eq!(fragile_products.len(), 1);
+    assert_eq!(fragile_products.get(0).unwrap().id, 1);
+    assert_eq!(client.get_product_tags(&1).len(), 1);
+}
+
+#[test]
+fn test_tag_pagination() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register_contract(None, ChainLogisticsContract);
+    let client = ChainLogisticsContractClient::new(&env, &contract_id);
+
+    let owner = Address::generate(&env);
+    let hazmat = symbol_short!("hazmat");
+    for _ in 0..7 {
+        let id = client.register_product(&owner, &String::from_str(&env, "Spain"), &String::from_str(&env, "M"));
+        client.add_tag(&owner, &id, &hazmat);
     }
+
+    let page1 = client.get_products_by_tag(&hazmat, &0, &5);
+    assert_eq!(page1.len(), 5);
+    assert_eq!(page1.get(0).unwrap().id, 1);
+    let page2 = client.get_products_by_tag(&hazmat, &5, &5);
+    assert_eq!(page2.len(), 2);
+    assert_eq!(page2.get(1).unwrap().id, 7);
 }
 
 #[test]
-fn test_register_rejects_empty_category() {
+fn test_too_many_tags_rejected() {
     let env = Env::default();
     env.mock_all_auths();
     let contract_id = env.register_contract(None, ChainLogisticsContract);
     let client = ChainLogisticsContractClient::new(&env, &contract_id);
 
     let owner = Address::generate(&env);
-    let id = String::from_str(&env, "COFFEE-ETH-001");
+    let id = client.register_product(&owner, &String::from_str(&env, "Spain"), &String::from_str(&env, "M"));
 
-    let tags: Vec<String> = Vec::new(&env);
-    let certs: Vec<BytesN<32>> = Vec::new(&env);
-    let media: Vec<BytesN<32>> = Vec::new(&env);
-    let custom: Map<Symbol, String> = Map::new(&env);
+    let names = ["t0", "t1", "t2", "t3", "t4", "t5", "t6", "t7", "t8", "t9"];
+    for name in names.iter() {
+        client.add_tag(&owner, &id, &Symbol::new(&env, name));
+    }
 
-    let res = client.try_register_product(
-        &owner,
-        &id,
-        &String::from_str(&env, "Organic Coffee Beans"),
-        &String::from_str(&env, ""),
-        &String::from_str(&env, "Yirgacheffe, Ethiopia"),
-        &String::from_str(&env, ""),
-        &tags,
-        &certs,
-        &media,
-        &custom,
-    );
+    let res = client.try_add_tag(&owner, &id, &symbol_short!("t10"));
     match res {
-        Err(Ok(e)) => assert_eq!(e, Error::InvalidCategory),
-        _ => panic!("expected InvalidCategory"),
+        Err(Ok(e)) => assert_eq!(e, Error::TooManyTags),
+        _ => panic!("expected TooManyTags"),
     }
+
+    // Freeing a slot lets a new tag in
+    client.remove_tag(&owner, &id, &symbol_short!("t0"));
+    client.add_tag(&owner, &id, &symbol_short!("t10"));
 }
 
 #[test]
-fn test_register_rejects_too_long_description() {
+fn test_certification_lifecycle() {
     let env = Env::default();
     env.mock_all_auths();
     let contract_id = env.register_contract(None, ChainLogisticsContract);
     let client = ChainLogisticsContractClient::new(&env, &contract_id);
 
+    let admin = Address::generate(&env);
     let owner = Address::generate(&env);
-    let id = String::from_str(&env, "COFFEE-ETH-001");
+    let certifier = Address::generate(&env);
+    let organic = symbol_short!("ORGANIC");
+    let evidence = BytesN::from_array(&env, &[7; 32]);
 
-    let tags: Vec<String> = Vec::new(&env);
-    let certs: Vec<BytesN<32>> = Vec::new(&env);
-    let media: Vec<BytesN<32>> = Vec::new(&env);
-    let custom: Map<Symbol, String> = Map::new(&env);
+    client.initialize(&admin, &String::from_str(&env, "ChainLogistics"));
+    client.add_certifier(&admin, &certifier);
 
-    let long_desc = "a".repeat(3000);
-    let res = client.try_register_product(
-        &owner,
-        &id,
-        &String::from_str(&env, "Organic Coffee Beans"),
-        &String::from_str(&env, &long_desc),
-        &String::from_str(&env, "Yirgacheffe, Ethiopia"),
-        &String::from_str(&env, "Coffee"),
-        &tags,
-        &certs,
-        &media,
-        &custom,
-    );
+    env.ledger().with_mut(|li| li.timestamp = 1_000);
+    let id = client.register_product(&owner, &String::from_str(&env, "Ghana"), &String::from_str(&env, "Cocoa"));
+
+    assert!(!client.has_valid_certification(&id, &organic));
+    let seq = client.add_certification(&certifier, &id, &organic, &2_000, &evidence);
+    assert_eq!(seq, 1);
+    assert!(client.has_valid_certification(&id, &organic));
+    assert!(!client.has_valid_certification(&id, &symbol_short!("FAIRTRD")));
+
+    let certs = client.get_certifications(&id, &0, &10);
+    assert_eq!(certs.len(), 1);
+    assert_eq!(certs.get(0).unwrap().certifier, certifier);
+    assert_eq!(certs.get(0).unwrap().issued_at, 1_000);
+
+    // Valid up to, but not at, the expiry second
+    env.ledger().with_mut(|li| li.timestamp = 1_999);
+    assert!(client.has_valid_certification(&id, &organic));
+    env.ledger().with_mut(|li| li.timestamp = 2_000);
+    assert!(!client.has_valid_certification(&id, &organic));
+
+    let res = client.try_add_certification(&certifier, &id, &organic, &2_000, &evidence);
     match res {
-        Err(Ok(e)) => assert_eq!(e, Error::DescriptionTooLong),
-        _ => panic!("expected DescriptionTooLong"),
+        Err(Ok(e)) => assert_eq!(e, Error::InvalidTimestamp),
+        _ => panic!("expected InvalidTimestamp"),
     }
 }
 
 #[test]
-fn test_register_rejects_too_many_tags() {
+fn test_certification_requires_approved_certifier() {
     let env = Env::default();
     env.mock_all_auths();
     let contract_id = env.register_contract(None, ChainLogisticsContract);
     let client = ChainLogisticsContractClient::new(&env, &contract_id);
 
+    let admin = Address::generate(&env);
     let owner = Address::generate(&env);
-    let id = String::from_str(&env, "COFFEE-ETH-001");
+    let certifier = Address::generate(&env);
+    let stranger = Address::generate(&env);
+    let evidence = BytesN::from_array(&env, &[7; 32]);
+
+    client.initialize(&admin, &String::from_str(&env, "ChainLogistics"));
+    let id = client.register_product(&owner, &String::from_str(&env, "Ghana"), &String::from_str(&env, "Cocoa"));
+
+    let res = client.try_add_certification(&certifier, &id, &symbol_short!("CE"), &100, &evidence);
+    match res {
+        Err(Ok(e)) => assert_eq!(e, Error::Unauthorized),
+        _ => panic!("expected Unauthorized"),
+    }
 
-    let mut tags: Vec<String> = Vec::new(&env);
-    for _ in 0..21 {
-        tags.push_back(String::from_str(&env, "t"));
+    // Only the admin may approve certifiers
+    let res = client.try_add_certifier(&stranger, &certifier);
+    match res {
+        Err(Ok(e)) => assert_eq!(e, Error::Unauthorized),
+        _ => panic!("expected Unauthorized"),
     }
-    let certs: Vec<BytesN<32>> = Vec::new(&env);
-    let media: Vec<BytesN<32>> = Vec::new(&env);
-    let custom: Map<Symbol, String> = Map::new(&env);
 
-    let res = client.try_register_product(
-        &owner,
-        &id,
-        &String::from_str(&env, "Organic Coffee Beans"),
-        &String::from_str(&env, ""),
-        &String::from_str(&env, "Yirgacheffe, Ethiopia"),
-        &String::from_str(&env, "Coffee"),
-        &tags,
-        &certs,
-        &media,
-        &custom,
-    );
+    client.add_certifier(&admin, &certifier);
+    client.add_certification(&certifier, &id, &symbol_short!("CE"), &100, &evidence);
+
+    client.remove_certifier(&admin, &certifier);
+    assert!(!client.is_certifier(&certifier));
+    let res = client.try_add_certification(&certifier, &id, &symbol_short!("CE"), &100, &evidence);
     match res {
-        Err(Ok(e)) => assert_eq!(e, Error::TooManyTags),
-        _ => panic!("expected TooManyTags"),
+        Err(Ok(e)) => assert_eq!(e, Error::Unauthorized),
+        _ => panic!("expected Unauthorized"),
+    }
+
+    let res = client.try_initialize(&stranger, &String::from_str(&env, "ChainLogistics"));
+    match res {
+        Err(Ok(e)) => assert_eq!(e, Error::AlreadyInitialized),
+        _ => panic!("expected AlreadyInitialized"),
     }
 }
 
 #[test]
-fn test_register_rejects_tag_too_long() {
+fn test_certification_revocation_is_immediate() {
     let env = Env::default();
     env.mock_all_auths();
     let contract_id = env.register_contract(None, ChainLogisticsContract);
     let client = ChainLogisticsContractClient::new(&env, &contract_id);
 
+    let admin = Address::generate(&env);
     let owner = Address::generate(&env);
-    let id = String::from_str(&env, "COFFEE-ETH-001");
+    let certifier = Address::generate(&env);
+    let other_certifier = Address::generate(&env);
+    let fair_trade = symbol_short!("FAIRTRD");
+    let evidence = BytesN::from_array(&env, &[7; 32]);
 
-    let mut tags: Vec<String> = Vec::new(&env);
-    let long_tag = "t".repeat(100);
-    tags.push_back(String::from_str(&env, &long_tag));
+    client.initialize(&admin, &String::from_str(&env, "ChainLogistics"));
+    client.add_certifier(&admin, &certifier);
+    client.add_certifier(&admin, &other_certifier);
+    let id = client.register_product(&owner, &String::from_str(&env, "Ghana"), &String::from_str(&env, "Cocoa"));
 
-    let certs: Vec<BytesN<32>> = Vec::new(&env);
-    let media: Vec<BytesN<32>> = Vec::new(&env);
-    let custom: Map<Symbol, String> = Map::new(&env);
+    let seq = client.add_certification(&certifier, &id, &fair_trade, &10_000, &evidence);
 
-    let res = client.try_register_product(
-        &owner,
-        &id,
-        &String::from_str(&env, "Organic Coffee Beans"),
-        &String::from_str(&env, ""),
-        &String::from_str(&env, "Yirgacheffe, Ethiopia"),
-        &String::from_str(&env, "Coffee"),
-        &tags,
-        &certs,
-        &media,
-        &custom,
-    );
+    // Another certifier cannot revoke it
+    let res = client.try_revoke_certification(&other_certifier, &id, &seq);
     match res {
-        Err(Ok(e)) => assert_eq!(e, Error::TagTooLong),
-        _ => panic!("expected TagTooLong"),
+        Err(Ok(e)) => assert_eq!(e, Error::Unauthorized),
+        _ => panic!("expected Unauthorized"),
     }
+
+    client.revoke_certification(&certifier, &id, &seq);
+    assert!(!client.has_valid_certification(&id, &fair_trade));
+    assert!(client.get_certifications(&id, &0, &10).get(0).unwrap().revoked);
+}
+
+#[test]
+fn test_gtin_check_digit_helper() {
+    let env = Env::default();
+
+    // Valid GTIN-8, GTIN-12, GTIN-13 and GTIN-14
+    assert!(validation::is_valid_gtin(&String::from_str(&env, "96385074")));
+    assert!(validation::is_valid_gtin(&String::from_str(&env, "036000291452")));
+    assert!(validation::is_valid_gtin(&String::from_str(&env, "4006381333931")));
+    assert!(validation::is_valid_gtin(&String::from_str(&env, "10012345678902")));
+
+    // Wrong check digit
+    assert!(!validation::is_valid_gtin(&String::from_str(&env, "4006381333932")));
+    assert!(!validation::is_valid_gtin(&String::from_str(&env, "10012345678903")));
+
+    // Wrong length and non-digits
+    assert!(!validation::is_valid_gtin(&String::from_str(&env, "")));
+    assert!(!validation::is_valid_gtin(&String::from_str(&env, "400638133393")));
+    assert!(!validation::is_valid_gtin(&String::from_str(&env, "400638133393100")));
+    assert!(!validation::is_valid_gtin(&String::from_str(&env, "40063813339A1")));
+
+    assert_eq!(validation::parse_digits::<3>(&String::from_str(&env, "907")), Some([9, 0, 7]));
+    assert_eq!(validation::parse_digits::<3>(&String::from_str(&env, "9-7")), None);
+    assert_eq!(validation::parse_digits::<3>(&String::from_str(&env, "9070")), None);
 }
 
 #[test]
-fn test_register_rejects_too_many_custom_fields() {
+fn test_products_by_gtin() {
     let env = Env::default();
     env.mock_all_auths();
     let contract_id = env.register_contract(None, ChainLogisticsContract);
     let client = ChainLogisticsContractClient::new(&env, &contract_id);
 
     let owner = Address::generate(&env);
-    let id = String::from_str(&env, "COFFEE-ETH-001");
+    let origin = String::from_str(&env, "Germany");
+    let gtin_a = String::from_str(&env, "4006381333931");
+    let gtin_b = String::from_str(&env, "10012345678902");
+
+    for _ in 0..3 {
+        let id = client.register_product(&owner, &origin, &String::from_str(&env, "Unit"));
+        client.set_gtin(&owner, &id, &gtin_a);
+    }
 
-    let tags: Vec<String> = Vec::new(&env);
-    let certs: Vec<BytesN<32>> = Vec::new(&env);
-    let media: Vec<BytesN<32>> = Vec::new(&env);
+    assert_eq!(client.get_product_gtin(&2), Some(gtin_a.clone()));
+    assert_eq!(client.get_products_by_gtin(&gtin_a, &0, &10).len(), 3);
 
-    let mut custom: Map<Symbol, String> = Map::new(&env);
-    let keys = [
-        "k0", "k1", "k2", "k3", "k4", "k5", "k6", "k7", "k8", "k9", "k10",
-        "k11", "k12", "k13", "k14", "k15", "k16", "k17", "k18", "k19", "k20",
-    ];
-    for i in 0..21u32 {
-        let k = Symbol::new(&env, keys[i as usize]);
-        custom.set(k, String::from_str(&env, "v"));
+    // Moving a unit to another GTIN removes it from the first listing
+    client.set_gtin(&owner, &2, &gtin_b);
+    let units = client.get_products_by_gtin(&gtin_a, &0, &10);
+    assert_eq!(units.len(), 2);
+    assert_eq!(units.get(0).unwrap().id, 1);
+    assert_eq!(units.get(1).unwrap().id, 3);
+    assert_eq!(client.get_products_by_gtin(&gtin_b, &0, &10).get(0).unwrap().id, 2);
+
+    let res = client.try_set_gtin(&owner, &1, &String::from_str(&env, "4006381333932"));
+    match res {
+        Err(Ok(e)) => assert_eq!(e, Error::InvalidGtin),
+        _ => panic!("expected InvalidGtin"),
     }
+    assert_eq!(client.get_product_gtin(&1), Some(gtin_a));
+}
 
-    let res = client.try_register_product(
-        &owner,
-        &id,
-        &String::from_str(&env, "Organic Coffee Beans"),
-        &String::from_str(&env, ""),
-        &String::from_str(&env, "Yirgacheffe, Ethiopia"),
-        &String::from_str(&env, "Coffee"),
-        &tags,
-        &certs,
-        &media,
-        &custom,
-    );
+#[test]
+fn test_lot_lookup_and_recall() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register_contract(None, ChainLogisticsContract);
+    let client = ChainLogisticsContractClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    let maker = Address::generate(&env);
+    let retailer = Address::generate(&env);
+    let origin = String::from_str(&env, "France");
+    let lot = String::from_str(&env, "LOT-2024-07");
+    let other_lot = String::from_str(&env, "LOT-2024-08");
+
+    client.initialize(&admin, &String::from_str(&env, "ChainLogistics"));
+    for _ in 0..4 {
+        client.register_product(&maker, &origin, &String::from_str(&env, "Cheese"));
+    }
+    client.set_lot(&maker, &1, &lot);
+    client.set_lot(&maker, &2, &lot);
+    client.set_lot(&maker, &3, &other_lot);
+    // Product 4 has no lot; product 2 ends up with a different owner
+    client.transfer_product(&maker, &2, &retailer);
+
+    let in_lot = client.get_products_by_lot(&lot, &0, &10);
+    assert_eq!(in_lot.len(), 2);
+    assert_eq!(in_lot.get(1).unwrap().owner, retailer);
+    assert_eq!(client.get_product_lot(&2), Some(lot.clone()));
+    assert_eq!(client.get_product_lot(&4), None);
+
+    assert_eq!(client.recall_by_lot(&admin, &lot, &0, &10), 2);
+    assert!(client.get_product(&1).unwrap().recalled);
+    assert!(client.get_product(&2).unwrap().recalled);
+    assert!(!client.get_product(&3).unwrap().recalled);
+    assert!(!client.get_product(&4).unwrap().recalled);
+
+    // Recalling again touches nothing new
+    assert_eq!(client.recall_by_lot(&admin, &lot, &0, &10), 0);
+
+    let res = client.try_recall_by_lot(&maker, &other_lot, &0, &10);
     match res {
-        Err(Ok(e)) => assert_eq!(e, Error::TooManyCustomFields),
-        _ => panic!("expected TooManyCustomFields"),
+        Err(Ok(e)) => assert_eq!(e, Error::Unauthorized),
+        _ => panic!("expected Unauthorized"),
     }
 }
 
 #[test]
-fn test_register_rejects_custom_field_value_too_long() {
+fn test_recall_by_lot_in_chunks() {
     let env = Env::default();
     env.mock_all_auths();
     let contract_id = env.register_contract(None, ChainLogisticsContract);
     let client = ChainLogisticsContractClient::new(&env, &contract_id);
 
-    let owner = Address::generate(&env);
-    let id = String::from_str(&env, "COFFEE-ETH-001");
+    let admin = Address::generate(&env);
+    let maker = Address::generate(&env);
+    let lot = String::from_str(&env, "LOT-A");
 
-    let tags: Vec<String> = Vec::new(&env);
-    let certs: Vec<BytesN<32>> = Vec::new(&env);
-    let media: Vec<BytesN<32>> = Vec::new(&env);
+    client.initialize(&admin, &String::from_str(&env, "ChainLogistics"));
+    for _ in 0..5 {
+        let id = client.register_product(&maker, &String::from_str(&env, "France"), &String::from_str(&env, "M"));
+        client.set_lot(&maker, &id, &lot);
+    }
+    // Moving product 3 to another lot leaves a hole in LOT-A
+    client.set_lot(&maker, &3, &String::from_str(&env, "LOT-B"));
 
-    let mut custom: Map<Symbol, String> = Map::new(&env);
-    let long_val = "v".repeat(600);
-    custom.set(Symbol::new(&env, "k"), String::from_str(&env, &long_val));
+    assert_eq!(client.recall_by_lot(&admin, &lot, &0, &2), 2);
+    assert_eq!(client.recall_by_lot(&admin, &lot, &2, &2), 1);
+    assert_eq!(client.recall_by_lot(&admin, &lot, &4, &2), 1);
+    assert_eq!(client.recall_by_lot(&admin, &lot, &6, &2), 0);
+    assert!(!client.get_product(&3).unwrap().recalled);
+    assert!(client.get_product(&5).unwrap().recalled);
+}
 
-    let res = client.try_register_product(
-        &owner,
+#[test]
+fn test_quantity_with_unit_and_consume() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register_contract(None, ChainLogisticsContract);
+    let client = ChainLogisticsContractClient::new(&env, &contract_id);
+
+    let owner = Address::generate(&env);
+    let processor = Address::generate(&env);
+    let id = client.register_product(&owner, &String::from_str(&env, "Brazil"), &String::from_str(&env, "Coffee"));
+
+    assert_eq!(client.get_quantity(&id), None);
+    client.set_quantity(&owner, &id, &500, &symbol_short!("KG"));
+    let qty = client.get_quantity(&id).unwrap();
+    assert_eq!(qty.amount, 500);
+    assert_eq!(qty.unit, symbol_short!("KG"));
+
+    client.add_authorized_actor(&owner, &id, &processor);
+    assert_eq!(client.consume(&processor, &id, &120, &symbol_short!("KG")), 380);
+
+    let res = client.try_consume(&processor, &id, &10, &symbol_short!("L"));
+    match res {
+        Err(Ok(e)) => assert_eq!(e, Error::UnitMismatch),
+        _ => panic!("expected UnitMismatch"),
+    }
+
+    let res = client.try_consume(&processor, &id, &381, &symbol_short!("KG"));
+    match res {
+        Err(Ok(e)) => assert_eq!(e, Error::InvalidInput),
+        _ => panic!("expected InvalidInput"),
+    }
+    assert_eq!(client.get_quantity(&id).unwrap().amount, 380);
+}
+
+#[test]
+fn test_merge_requires_matching_units() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register_contract(None, ChainLogisticsContract);
+    let client = ChainLogisticsContractClient::new(&env, &contract_id);
+
+    let owner = Address::generate(&env);
+    let origin = String::from_str(&env, "Brazil");
+    let a = client.register_product(&owner, &origin, &String::from_str(&env, "A"));
+    let b = client.register_product(&owner, &origin, &String::from_str(&env, "B"));
+    let c = client.register_product(&owner, &origin, &String::from_str(&env, "C"));
+
+    client.set_quantity(&owner, &a, &100, &symbol_short!("KG"));
+    client.set_quantity(&owner, &b, &50, &symbol_short!("KG"));
+    client.set_quantity(&owner, &c, &2, &symbol_short!("PALLET"));
+
+    let res = client.try_merge_products(&owner, &a, &c);
+    match res {
+        Err(Ok(e)) => assert_eq!(e, Error::UnitMismatch),
+        _ => panic!("expected UnitMismatch"),
+    }
+
+    assert_eq!(client.merge_products(&owner, &a, &b), 150);
+    assert_eq!(client.get_quantity(&a).unwrap().amount, 150);
+    assert_eq!(client.get_product(&b), None);
+    assert_eq!(client.get_stats().active_products, 2);
+}
+
+#[test]
+fn test_media_append_and_verify() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register_contract(None, ChainLogisticsContract);
+    let client = ChainLogisticsContractClient::new(&env, &contract_id);
+
+    let owner = Address::generate(&env);
+    let warehouse = Address::generate(&env);
+    let stranger = Address::generate(&env);
+    let id = client.register_product(&owner, &String::from_str(&env, "Italy"), &String::from_str(&env, "Shoes"));
+
+    let photo = BytesN::from_array(&env, &[1; 32]);
+    let scan = BytesN::from_array(&env, &[2; 32]);
+
+    client.add_media(&owner, &id, &photo, &symbol_short!("photo"));
+
+    let res = client.try_add_media(&stranger, &id, &scan, &symbol_short!("scan"));
+    match res {
+        Err(Ok(e)) => assert_eq!(e, Error::Unauthorized),
+        _ => panic!("expected Unauthorized"),
+    }
+
+    client.add_authorized_actor(&owner, &id, &warehouse);
+    client.add_media(&warehouse, &id, &scan, &symbol_short!("scan"));
+
+    let media = client.get_media(&id);
+    assert_eq!(media.len(), 2);
+    assert_eq!(media.get(1).unwrap().added_by, warehouse);
+    assert!(client.verify_media(&id, &scan));
+    assert!(!client.verify_media(&id, &BytesN::from_array(&env, &[3; 32])));
+
+    // Actors may append but only the owner may remove
+    let res = client.try_remove_media(&warehouse, &id, &photo);
+    match res {
+        Err(Ok(e)) => assert_eq!(e, Error::Unauthorized),
+        _ => panic!("expected Unauthorized"),
+    }
+    client.remove_media(&owner, &id, &photo);
+    assert!(!client.verify_media(&id, &photo));
+    assert_eq!(client.get_media(&id).len(), 1);
+}
+
+#[test]
+fn test_media_cap() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register_contract(None, ChainLogisticsContract);
+    let client = ChainLogisticsContractClient::new(&env, &contract_id);
+
+    let owner = Address::generate(&env);
+    let id = client.register_product(&owner, &String::from_str(&env, "Italy"), &String::from_str(&env, "Shoes"));
+
+    for i in 0..16u8 {
+        client.add_media(&owner, &id, &BytesN::from_array(&env, &[i; 32]), &symbol_short!("photo"));
+    }
+    // Re-adding a known hash is not a new entry
+    client.add_media(&owner, &id, &BytesN::from_array(&env, &[0; 32]), &symbol_short!("photo"));
+
+    let res = client.try_add_media(&owner, &id, &BytesN::from_array(&env, &[16; 32]), &symbol_short!("photo"));
+    match res {
+        Err(Ok(e)) => assert_eq!(e, Error::TooManyMediaHashes),
+        _ => panic!("expected TooManyMediaHashes"),
+    }
+    assert_eq!(client.get_media(&id).len(), 16);
+}
+
+#[test]
+fn test_origin_whitelist_enforcement_toggle() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register_contract(None, ChainLogisticsContract);
+    let client = ChainLogisticsContractClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    let owner = Address::generate(&env);
+    let kenya = String::from_str(&env, "Kenya");
+    let atlantis = String::from_str(&env, "Atlantis");
+    let metadata = String::from_str(&env, "M");
+
+    client.initialize(&admin, &String::from_str(&env, "ChainLogistics"));
+    client.add_allowed_origin(&admin, &kenya);
+
+    // Not enforced yet: anything goes
+    client.register_product(&owner, &atlantis, &metadata);
+
+    client.set_origin_whitelist_enforced(&admin, &true);
+    assert!(client.is_origin_whitelist_enforced());
+    client.register_product(&owner, &kenya, &metadata);
+    let res = client.try_register_product(&owner, &atlantis, &metadata);
+    match res {
+        Err(Ok(e)) => assert_eq!(e, Error::OriginNotAllowed),
+        _ => panic!("expected OriginNotAllowed"),
+    }
+    let res = client.try_update_origin(&owner, &2, &atlantis);
+    match res {
+        Err(Ok(e)) => assert_eq!(e, Error::OriginNotAllowed),
+        _ => panic!("expected OriginNotAllowed"),
+    }
+
+    client.remove_allowed_origin(&admin, &kenya);
+    let res = client.try_register_product(&owner, &kenya, &metadata);
+    match res {
+        Err(Ok(e)) => assert_eq!(e, Error::OriginNotAllowed),
+        _ => panic!("expected OriginNotAllowed"),
+    }
+
+    client.set_origin_whitelist_enforced(&admin, &false);
+    client.register_product(&owner, &atlantis, &metadata);
+    assert_eq!(client.get_stats().total_products, 3);
+
+    let res = client.try_set_origin_whitelist_enforced(&owner, &true);
+    match res {
+        Err(Ok(e)) => assert_eq!(e, Error::Unauthorized),
+        _ => panic!("expected Unauthorized"),
+    }
+}
+
+#[test]
+fn test_allowed_origins_pagination() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register_contract(None, ChainLogisticsContract);
+    let client = ChainLogisticsContractClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    client.initialize(&admin, &String::from_str(&env, "ChainLogistics"));
+
+    let names = ["Kenya", "Peru", "Chile", "Ghana", "Laos"];
+    for name in names.iter() {
+        client.add_allowed_origin(&admin, &String::from_str(&env, name));
+    }
+    // Duplicate adds don't take a new slot
+    client.add_allowed_origin(&admin, &String::from_str(&env, "Peru"));
+    client.remove_allowed_origin(&admin, &String::from_str(&env, "Chile"));
+
+    let page1 = client.get_allowed_origins(&0, &3);
+    assert_eq!(page1.len(), 2);
+    assert_eq!(page1.get(0).unwrap(), String::from_str(&env, "Kenya"));
+    assert_eq!(page1.get(1).unwrap(), String::from_str(&env, "Peru"));
+
+    let page2 = client.get_allowed_origins(&3, &3);
+    assert_eq!(page2.len(), 2);
+    assert_eq!(page2.get(1).unwrap(), String::from_str(&env, "Laos"));
+    assert!(client.is_origin_allowed(&String::from_str(&env, "Ghana")));
+    assert!(!client.is_origin_allowed(&String::from_str(&env, "Chile")));
+}
+
+#[test]
+fn test_register_validates_origin_bounds() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register_contract(None, ChainLogisticsContract);
+    let client = ChainLogisticsContractClient::new(&env, &contract_id);
+
+    let owner = Address::generate(&env);
+    let metadata = String::from_str(&env, "M");
+
+    let res = client.try_register_product(&owner, &String::from_str(&env, ""), &metadata);
+    match res {
+        Err(Ok(e)) => assert_eq!(e, Error::InvalidOrigin),
+        _ => panic!("expected InvalidOrigin"),
+    }
+
+    let max_origin = "o".repeat(64);
+    let id = client.register_product(&owner, &String::from_str(&env, &max_origin), &metadata);
+
+    let too_long = "o".repeat(65);
+    let res = client.try_register_product(&owner, &String::from_str(&env, &too_long), &metadata);
+    match res {
+        Err(Ok(e)) => assert_eq!(e, Error::InvalidOrigin),
+        _ => panic!("expected InvalidOrigin"),
+    }
+
+    // update_origin applies the same rules
+    let res = client.try_update_origin(&owner, &id, &String::from_str(&env, ""));
+    match res {
+        Err(Ok(e)) => assert_eq!(e, Error::InvalidOrigin),
+        _ => panic!("expected InvalidOrigin"),
+    }
+    let res = client.try_update_origin(&owner, &id, &String::from_str(&env, &too_long));
+    match res {
+        Err(Ok(e)) => assert_eq!(e, Error::InvalidOrigin),
+        _ => panic!("expected InvalidOrigin"),
+    }
+    assert_eq!(client.get_stats().total_products, 1);
+}
+
+#[test]
+fn test_register_validates_metadata_length() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register_contract(None, ChainLogisticsContract);
+    let client = ChainLogisticsContractClient::new(&env, &contract_id);
+
+    let owner = Address::generate(&env);
+    let origin = String::from_str(&env, "Kenya");
+
+    client.register_product(&owner, &origin, &String::from_str(&env, ""));
+
+    let max_metadata = "m".repeat(1024);
+    client.register_product(&owner, &origin, &String::from_str(&env, &max_metadata));
+
+    let too_long = "m".repeat(1025);
+    let res = client.try_register_product(&owner, &origin, &String::from_str(&env, &too_long));
+    match res {
+        Err(Ok(e)) => assert_eq!(e, Error::MetadataTooLong),
+        _ => panic!("expected MetadataTooLong"),
+    }
+    assert_eq!(client.get_stats().total_products, 2);
+}
+
+#[test]
+fn test_register_from_template() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register_contract(None, ChainLogisticsContract);
+    let client = ChainLogisticsContractClient::new(&env, &contract_id);
+
+    let maker = Address::generate(&env);
+    let vietnam = String::from_str(&env, "Vietnam");
+    let thailand = String::from_str(&env, "Thailand");
+    let apparel = symbol_short!("apparel");
+
+    let template_id = client.create_template(&maker, &vietnam, &String::from_str(&env, "T-shirt, size M"), &apparel);
+
+    let first = client.register_from_template(&maker, &template_id, &None);
+    let second = client.register_from_template(&maker, &template_id, &Some(String::from_str(&env, "SN-2")));
+
+    let by_origin = client.get_products_by_origin(&vietnam, &0, &10);
+    assert_eq!(by_origin.len(), 2);
+    assert_eq!(by_origin.get(0).unwrap().id, first);
+    assert_eq!(by_origin.get(1).unwrap().metadata, String::from_str(&env, "T-shirt, size M"));
+    assert_eq!(client.get_products_by_tag(&apparel, &0, &10).len(), 2);
+    assert_eq!(client.get_product_by_serial(&String::from_str(&env, "SN-2")).unwrap().id, second);
+
+    // Editing the template doesn't touch existing products
+    client.update_template(&maker, &template_id, &thailand, &String::from_str(&env, "T-shirt, size L"), &apparel);
+    assert_eq!(client.get_product(&first).unwrap().origin, vietnam);
+
+    let third = client.register_from_template(&maker, &template_id, &None);
+    assert_eq!(client.get_product(&third).unwrap().origin, thailand);
+    assert_eq!(client.get_products_by_origin(&vietnam, &0, &10).len(), 2);
+
+    let res = client.try_register_from_template(&maker, &99, &None);
+    match res {
+        Err(Ok(e)) => assert_eq!(e, Error::InvalidInput),
+        _ => panic!("expected InvalidInput"),
+    }
+}
+
+#[test]
+fn test_templates_editable_only_by_creator() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register_contract(None, ChainLogisticsContract);
+    let client = ChainLogisticsContractClient::new(&env, &contract_id);
+
+    let maker = Address::generate(&env);
+    let other = Address::generate(&env);
+    let origin = String::from_str(&env, "Vietnam");
+    let metadata = String::from_str(&env, "M");
+
+    client.create_template(&maker, &origin, &metadata, &symbol_short!("a"));
+    client.create_template(&other, &origin, &metadata, &symbol_short!("b"));
+    client.create_template(&maker, &origin, &metadata, &symbol_short!("c"));
+
+    let templates = client.get_templates_by_owner(&maker, &0, &10);
+    assert_eq!(templates.len(), 2);
+    assert_eq!(templates.get(0).unwrap().id, 1);
+    assert_eq!(templates.get(1).unwrap().id, 3);
+
+    let res = client.try_update_template(&other, &1, &origin, &String::from_str(&env, "hijacked"), &symbol_short!("a"));
+    match res {
+        Err(Ok(e)) => assert_eq!(e, Error::Unauthorized),
+        _ => panic!("expected Unauthorized"),
+    }
+    assert_eq!(client.get_template(&1).unwrap().metadata, metadata);
+}
+
+#[test]
+fn test_brand_claim_survives_transfer() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register_contract(None, ChainLogisticsContract);
+    let client = ChainLogisticsContractClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    let brand = Address::generate(&env);
+    let impostor = Address::generate(&env);
+    let owner = Address::generate(&env);
+    let buyer = Address::generate(&env);
+
+    client.initialize(&admin, &String::from_str(&env, "ChainLogistics"));
+    client.register_brand(&admin, &brand, &String::from_str(&env, "Acme Watches"));
+    assert_eq!(client.get_brand(&brand), Some(String::from_str(&env, "Acme Watches")));
+
+    let id = client.register_product(&owner, &String::from_str(&env, "Switzerland"), &String::from_str(&env, "Watch"));
+
+    let res = client.try_claim_product(&impostor, &id);
+    match res {
+        Err(Ok(e)) => assert_eq!(e, Error::Unauthorized),
+        _ => panic!("expected Unauthorized"),
+    }
+
+    client.claim_product(&brand, &id);
+    client.transfer_product(&owner, &id, &buyer);
+    assert_eq!(client.get_brand_claim(&id), Some(brand.clone()));
+
+    // The attestation is immutable
+    let res = client.try_claim_product(&brand, &id);
+    match res {
+        Err(Ok(e)) => assert_eq!(e, Error::InvalidInput),
+        _ => panic!("expected InvalidInput"),
+    }
+}
+
+#[test]
+fn test_brand_claim_and_disavow_are_exclusive() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register_contract(None, ChainLogisticsContract);
+    let client = ChainLogisticsContractClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    let brand = Address::generate(&env);
+    let owner = Address::generate(&env);
+
+    client.initialize(&admin, &String::from_str(&env, "ChainLogistics"));
+    client.register_brand(&admin, &brand, &String::from_str(&env, "Acme Watches"));
+
+    let genuine = client.register_product(&owner, &String::from_str(&env, "Switzerland"), &String::from_str(&env, "Watch"));
+    let fake = client.register_product(&owner, &String::from_str(&env, "Switzerland"), &String::from_str(&env, "Watch"));
+
+    client.claim_product(&brand, &genuine);
+    let res = client.try_disavow_product(&brand, &genuine);
+    match res {
+        Err(Ok(e)) => assert_eq!(e, Error::InvalidInput),
+        _ => panic!("expected InvalidInput"),
+    }
+
+    client.disavow_product(&brand, &fake);
+    assert!(client.is_disavowed(&fake, &brand));
+    assert!(!client.is_disavowed(&genuine, &brand));
+    let res = client.try_claim_product(&brand, &fake);
+    match res {
+        Err(Ok(e)) => assert_eq!(e, Error::InvalidInput),
+        _ => panic!("expected InvalidInput"),
+    }
+    assert_eq!(client.get_brand_claim(&fake), None);
+}
+
+#[test]
+fn test_notes_order_and_authorship() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register_contract(None, ChainLogisticsContract);
+    let client = ChainLogisticsContractClient::new(&env, &contract_id);
+
+    let owner = Address::generate(&env);
+    let staff = Address::generate(&env);
+    let stranger = Address::generate(&env);
+    let id = client.register_product(&owner, &String::from_str(&env, "Poland"), &String::from_str(&env, "Pallet"));
+
+    client.add_authorized_actor(&owner, &id, &staff);
+    env.ledger().with_mut(|li| li.timestamp = 100);
+    client.add_note(&staff, &id, &String::from_str(&env, "repacked box 3"));
+    env.ledger().with_mut(|li| li.timestamp = 200);
+    client.add_note(&owner, &id, &String::from_str(&env, "label reprinted"));
+
+    let res = client.try_add_note(&stranger, &id, &String::from_str(&env, "spam"));
+    match res {
+        Err(Ok(e)) => assert_eq!(e, Error::Unauthorized),
+        _ => panic!("expected Unauthorized"),
+    }
+
+    // Revoking the author keeps their notes on record
+    client.remove_authorized_actor(&owner, &id, &staff);
+
+    let notes = client.get_notes(&id, &0, &10);
+    assert_eq!(notes.len(), 2);
+    assert_eq!(notes.get(0).unwrap().author, staff);
+    assert_eq!(notes.get(0).unwrap().timestamp, 100);
+    assert_eq!(notes.get(1).unwrap().text, String::from_str(&env, "label reprinted"));
+
+    let page = client.get_notes(&id, &1, &10);
+    assert_eq!(page.len(), 1);
+    assert_eq!(page.get(0).unwrap().seq, 2);
+}
+
+#[test]
+fn test_note_caps() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+    let contract_id = env.register_contract(None, ChainLogisticsContract);
+    let client = ChainLogisticsContractClient::new(&env, &contract_id);
+
+    let owner = Address::generate(&env);
+    let id = client.register_product(&owner, &String::from_str(&env, "Poland"), &String::from_str(&env, "Pallet"));
+
+    let max_note = "n".repeat(256);
+    client.add_note(&owner, &id, &String::from_str(&env, &max_note));
+    let too_long = "n".repeat(257);
+    let res = client.try_add_note(&owner, &id, &String::from_str(&env, &too_long));
+    match res {
+        Err(Ok(e)) => assert_eq!(e, Error::NoteTooLong),
+        _ => panic!("expected NoteTooLong"),
+    }
+
+    for _ in 1..200 {
+        client.add_note(&owner, &id, &String::from_str(&env, "ok"));
+    }
+    let res = client.try_add_note(&owner, &id, &String::from_str(&env, "one too many"));
+    match res {
+        Err(Ok(e)) => assert_eq!(e, Error::TooManyNotes),
+        _ => panic!("expected TooManyNotes"),
+    }
+    assert_eq!(client.get_notes(&id, &195, &10).len(), 5);
+}
+
+#[test]
+fn test_origin_chain() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register_contract(None, ChainLogisticsContract);
+    let client = ChainLogisticsContractClient::new(&env, &contract_id);
+
+    let owner = Address::generate(&env);
+    let ghana = String::from_str(&env, "Ghana");
+    let belgium = String::from_str(&env, "Belgium");
+    let france = String::from_str(&env, "France");
+
+    let chocolate = client.register_product(&owner, &ghana, &String::from_str(&env, "Chocolate"));
+    let praline = client.register_product(&owner, &belgium, &String::from_str(&env, "Praline"));
+
+    client.append_origin(&owner, &chocolate, &belgium);
+    client.append_origin(&owner, &chocolate, &france);
+
+    let chain = client.get_origin_chain(&chocolate);
+    assert_eq!(chain.len(), 3);
+    assert_eq!(chain.get(0).unwrap(), ghana);
+    assert_eq!(chain.get(1).unwrap(), belgium);
+    assert_eq!(chain.get(2).unwrap(), france);
+    assert_eq!(client.get_product(&chocolate).unwrap().origin, ghana);
+
+    // Primary-origin queries are unchanged...
+    assert_eq!(client.get_products_by_origin(&belgium, &0, &10).len(), 1);
+    // ...while any-origin queries see the chain too
+    let any = client.get_products_by_any_origin(&belgium, &0, &10);
+    assert_eq!(any.len(), 2);
+    assert_eq!(any.get(0).unwrap().id, praline);
+    assert_eq!(any.get(1).unwrap().id, chocolate);
+    assert_eq!(client.get_products_by_any_origin(&ghana, &0, &10).len(), 1);
+
+    let page = client.get_products_by_any_origin(&belgium, &1, &1);
+    assert_eq!(page.len(), 1);
+    assert_eq!(page.get(0).unwrap().id, chocolate);
+
+    // Duplicates anywhere in the chain are rejected
+    for dup in [&ghana, &france] {
+        let res = client.try_append_origin(&owner, &chocolate, dup);
+        match res {
+            Err(Ok(e)) => assert_eq!(e, Error::InvalidInput),
+            _ => panic!("expected InvalidInput"),
+        }
+    }
+    let res = client.try_update_origin(&owner, &chocolate, &france);
+    match res {
+        Err(Ok(e)) => assert_eq!(e, Error::InvalidInput),
+        _ => panic!("expected InvalidInput"),
+    }
+}
+
+#[test]
+fn test_checkpoints() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register_contract(None, ChainLogisticsContract);
+    let client = ChainLogisticsContractClient::new(&env, &contract_id);
+
+    let owner = Address::generate(&env);
+    let carrier = Address::generate(&env);
+    let id = client.register_product(&owner, &String::from_str(&env, "Kenya"), &String::from_str(&env, "Tea"));
+
+    env.ledger().with_mut(|li| li.timestamp = 1_000);
+    let first = client.add_checkpoint(&owner, &id, &CheckpointKind::Pickup, &String::from_str(&env, "Mombasa"), &String::from_str(&env, "Packed"));
+    assert_eq!(first, 1);
+
+    // Strangers cannot log movement until authorized
+    let res = client.try_add_checkpoint(&carrier, &id, &CheckpointKind::Departure, &String::from_str(&env, "At sea"), &String::from_str(&env, ""));
+    match res {
+        Err(Ok(e)) => assert_eq!(e, Error::Unauthorized),
+        _ => panic!("expected Unauthorized"),
+    }
+    client.add_authorized_actor(&owner, &id, &carrier);
+    env.ledger().with_mut(|li| li.timestamp = 2_000);
+    let second = client.add_checkpoint(&carrier, &id, &CheckpointKind::Arrival, &String::from_str(&env, "Rotterdam"), &String::from_str(&env, "Unloaded"));
+    assert_eq!(second, 2);
+
+    let cp = client.get_checkpoint(&id, &2);
+    assert_eq!(cp.seq, 2);
+    assert_eq!(cp.actor, carrier);
+    assert_eq!(cp.location, String::from_str(&env, "Rotterdam"));
+    assert_eq!(cp.timestamp, 2_000);
+    assert_eq!(client.get_checkpoint(&id, &1).timestamp, 1_000);
+
+    let res = client.try_get_checkpoint(&id, &3);
+    match res {
+        Err(Ok(e)) => assert_eq!(e, Error::EventNotFound),
+        _ => panic!("expected EventNotFound"),
+    }
+    let res = client.try_add_checkpoint(&owner, &999, &CheckpointKind::Departure, &String::from_str(&env, "Nowhere"), &String::from_str(&env, ""));
+    match res {
+        Err(Ok(e)) => assert_eq!(e, Error::ProductNotFound),
+        _ => panic!("expected ProductNotFound"),
+    }
+}
+
+#[test]
+fn test_checkpoint_pagination() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+    let contract_id = env.register_contract(None, ChainLogisticsContract);
+    let client = ChainLogisticsContractClient::new(&env, &contract_id);
+
+    let owner = Address::generate(&env);
+    let id = client.register_product(&owner, &String::from_str(&env, "Chile"), &String::from_str(&env, "Grapes"));
+    assert_eq!(client.get_latest_checkpoint(&id), None);
+
+    let location = String::from_str(&env, "Depot");
+    for i in 1..=25u64 {
+        env.ledger().with_mut(|li| li.timestamp = i * 100);
+        client.add_checkpoint(&owner, &id, &CheckpointKind::Arrival, &location, &String::from_str(&env, ""));
+    }
+    assert_eq!(client.get_checkpoint_count(&id), 25);
+
+    let page1 = client.get_checkpoints(&id, &0, &10);
+    let page2 = client.get_checkpoints(&id, &10, &10);
+    let page3 = client.get_checkpoints(&id, &20, &10);
+    assert_eq!(page1.len(), 10);
+    assert_eq!(page2.len(), 10);
+    assert_eq!(page3.len(), 5);
+    assert_eq!(page1.get(0).unwrap().seq, 1);
+    assert_eq!(page2.get(0).unwrap().seq, 11);
+    assert_eq!(page3.get(4).unwrap().seq, 25);
+    assert_eq!(page2.get(9).unwrap().timestamp, 2_000);
+
+    assert_eq!(client.get_checkpoints(&id, &25, &10).len(), 0);
+    assert_eq!(client.get_checkpoints(&id, &u32::MAX, &u32::MAX).len(), 0);
+
+    let latest = client.get_latest_checkpoint(&id).unwrap();
+    assert_eq!(latest.seq, 25);
+    assert_eq!(latest.timestamp, 2_500);
+}
+
+#[test]
+fn test_checkpoint_kind_side_effects() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register_contract(None, ChainLogisticsContract);
+    let client = ChainLogisticsContractClient::new(&env, &contract_id);
+
+    let owner = Address::generate(&env);
+    let carrier = Address::generate(&env);
+    let id = client.register_product(&owner, &String::from_str(&env, "Peru"), &String::from_str(&env, "Avocados"));
+    client.add_authorized_actor(&owner, &id, &carrier);
+    let port = String::from_str(&env, "Callao");
+    let note = String::from_str(&env, "");
+
+    // Ordinary kinds leave the flags untouched
+    for kind in [
+        CheckpointKind::Pickup,
+        CheckpointKind::Departure,
+        CheckpointKind::CustomsIn,
+        CheckpointKind::CustomsOut,
+        CheckpointKind::Arrival,
+    ] {
+        client.add_checkpoint(&carrier, &id, &kind, &port, &note);
+    }
+    assert!(!client.is_delivered(&id));
+    assert!(!client.has_open_exception(&id));
+    assert_eq!(client.get_checkpoint(&id, &3).kind, CheckpointKind::CustomsIn);
+
+    client.add_checkpoint(&carrier, &id, &CheckpointKind::Exception, &port, &String::from_str(&env, "Seal broken"));
+    assert!(client.has_open_exception(&id));
+    assert!(!client.is_delivered(&id));
+
+    // Only the owner clears an exception
+    let res = client.try_clear_exception(&carrier, &id);
+    match res {
+        Err(Ok(e)) => assert_eq!(e, Error::Unauthorized),
+        _ => panic!("expected Unauthorized"),
+    }
+    client.clear_exception(&owner, &id);
+    assert!(!client.has_open_exception(&id));
+
+    client.add_checkpoint(&carrier, &id, &CheckpointKind::Delivery, &String::from_str(&env, "Madrid"), &note);
+    assert!(client.is_delivered(&id));
+    assert!(!client.has_open_exception(&id));
+    assert_eq!(client.get_latest_checkpoint(&id).unwrap().kind, CheckpointKind::Delivery);
+}
+
+#[test]
+fn test_temperature_log() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+    let contract_id = env.register_contract(None, ChainLogisticsContract);
+    let client = ChainLogisticsContractClient::new(&env, &contract_id);
+
+    let owner = Address::generate(&env);
+    let sensor = Address::generate(&env);
+    let id = client.register_product(&owner, &String::from_str(&env, "Norway"), &String::from_str(&env, "Salmon"));
+    env.ledger().with_mut(|li| li.timestamp = 10_000);
+
+    let res = client.try_record_temperature(&sensor, &id, &250, &9_000);
+    match res {
+        Err(Ok(e)) => assert_eq!(e, Error::Unauthorized),
+        _ => panic!("expected Unauthorized"),
+    }
+    client.add_authorized_actor(&owner, &id, &sensor);
+
+    let stats = client.get_temperature_stats(&id);
+    assert_eq!(stats.count, 0);
+
+    let temps: [i32; 8] = [250, 310, -120, 40, 400, -50, 0, 399];
+    for (i, t) in temps.iter().enumerate() {
+        client.record_temperature(&sensor, &id, t, &(9_000 + i as u64));
+    }
+    let stats = client.get_temperature_stats(&id);
+    assert_eq!(stats.count, 8);
+    assert_eq!(stats.min_centi_c, -120);
+    assert_eq!(stats.max_centi_c, 400);
+
+    let page = client.get_temperature_readings(&id, &2, &3);
+    assert_eq!(page.len(), 3);
+    assert_eq!(page.get(0).unwrap().temp_centi_c, -120);
+    assert_eq!(page.get(0).unwrap().seq, 3);
+    assert_eq!(page.get(2).unwrap().timestamp, 9_004);
+
+    // A reading from the future is rejected and leaves the stats alone
+    let res = client.try_record_temperature(&sensor, &id, &-900, &10_001);
+    match res {
+        Err(Ok(e)) => assert_eq!(e, Error::InvalidTimestamp),
+        _ => panic!("expected InvalidTimestamp"),
+    }
+    assert_eq!(client.get_temperature_stats(&id).min_centi_c, -120);
+    client.record_temperature(&owner, &id, &-900, &10_000);
+    assert_eq!(client.get_temperature_stats(&id).min_centi_c, -900);
+}
+
+#[test]
+fn test_temperature_excursion_blocks_delivery() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register_contract(None, ChainLogisticsContract);
+    let client = ChainLogisticsContractClient::new(&env, &contract_id);
+
+    let owner = Address::generate(&env);
+    let id = client.register_product(&owner, &String::from_str(&env, "Denmark"), &String::from_str(&env, "Insulin"));
+    env.ledger().with_mut(|li| li.timestamp = 500);
+
+    let res = client.try_set_temperature_limits(&owner, &id, &800, &200);
+    match res {
+        Err(Ok(e)) => assert_eq!(e, Error::InvalidInput),
+        _ => panic!("expected InvalidInput"),
+    }
+    client.set_temperature_limits(&owner, &id, &200, &800);
+
+    // In range and both boundaries are fine
+    for t in [200, 500, 800] {
+        client.record_temperature(&owner, &id, &t, &500);
+    }
+    assert_eq!(client.get_breach_count(&id), 0);
+    assert!(!client.has_excursion(&id));
+
+    client.record_temperature(&owner, &id, &801, &500);
+    client.record_temperature(&owner, &id, &199, &500);
+    assert_eq!(client.get_breach_count(&id), 2);
+    assert!(client.has_excursion(&id));
+
+    let res = client.try_confirm_delivery(&owner, &id);
+    match res {
+        Err(Ok(e)) => assert_eq!(e, Error::TemperatureExcursion),
+        _ => panic!("expected TemperatureExcursion"),
+    }
+    let res = client.try_add_checkpoint(&owner, &id, &CheckpointKind::Delivery, &String::from_str(&env, "Clinic"), &String::from_str(&env, ""));
+    match res {
+        Err(Ok(e)) => assert_eq!(e, Error::TemperatureExcursion),
+        _ => panic!("expected TemperatureExcursion"),
+    }
+    assert!(!client.is_delivered(&id));
+
+    client.acknowledge_excursion(&owner, &id);
+    assert!(!client.has_excursion(&id));
+    assert_eq!(client.get_breach_count(&id), 2);
+    client.confirm_delivery(&owner, &id);
+    assert!(client.is_delivered(&id));
+}
+
+#[test]
+fn test_sensor_reading_batch() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+    let contract_id = env.register_contract(None, ChainLogisticsContract);
+    let client = ChainLogisticsContractClient::new(&env, &contract_id);
+
+    let owner = Address::generate(&env);
+    let gateway = Address::generate(&env);
+    let origin = String::from_str(&env, "Vietnam");
+    let batched = client.register_product(&owner, &origin, &String::from_str(&env, "Coffee A"));
+    let single = client.register_product(&owner, &origin, &String::from_str(&env, "Coffee B"));
+    client.add_authorized_actor(&owner, &batched, &gateway);
+    client.add_authorized_actor(&owner, &single, &gateway);
+    env.ledger().with_mut(|li| li.timestamp = 5_000);
+
+    let mut readings = Vec::new(&env);
+    for i in 0..60u64 {
+        let kind = if i % 2 == 0 { symbol_short!("temp") } else { symbol_short!("humidity") };
+        readings.push_back(SensorReading { kind, value: i as i64 - 30, timestamp: 1_000 + i * 60 });
+    }
+    assert_eq!(client.record_readings_batch(&gateway, &batched, &readings), 60);
+    for r in readings.iter() {
+        client.record_reading(&gateway, &single, &r.kind, &r.value, &r.timestamp);
+    }
+    for channel in [symbol_short!("temp"), symbol_short!("humidity")] {
+        assert_eq!(client.get_readings(&batched, &channel, &0, &100), client.get_readings(&single, &channel, &0, &100));
+        assert_eq!(client.get_channel_stats(&batched, &channel), client.get_channel_stats(&single, &channel));
+    }
+    assert_eq!(client.get_readings(&batched, &symbol_short!("humidity"), &29, &10).get(0).unwrap().value, 29);
+
+    // One stale timestamp rejects everything, leaving the log untouched
+    let mut bad = Vec::new(&env);
+    bad.push_back(SensorReading { kind: symbol_short!("shock"), value: 1, timestamp: 4_600 });
+    bad.push_back(SensorReading { kind: symbol_short!("shock"), value: 2, timestamp: 4_500 });
+    let res = client.try_record_readings_batch(&gateway, &batched, &bad);
+    match res {
+        Err(Ok(e)) => assert_eq!(e, Error::InvalidTimestamp),
+        _ => panic!("expected InvalidTimestamp"),
+    }
+    let mut stale = Vec::new(&env);
+    stale.push_back(SensorReading { kind: symbol_short!("shock"), value: 1, timestamp: 1_000 });
+    let res = client.try_record_readings_batch(&gateway, &batched, &stale);
+    match res {
+        Err(Ok(e)) => assert_eq!(e, Error::InvalidTimestamp),
+        _ => panic!("expected InvalidTimestamp"),
+    }
+    assert_eq!(client.get_readings(&batched, &symbol_short!("temp"), &0, &100).len(), 30);
+    assert_eq!(client.get_readings(&batched, &symbol_short!("shock"), &0, &100).len(), 0);
+
+    let mut big = Vec::new(&env);
+    for _ in 0..101 {
+        big.push_back(SensorReading { kind: symbol_short!("temp"), value: 0, timestamp: 5_000 });
+    }
+    let res = client.try_record_readings_batch(&gateway, &batched, &big);
+    match res {
+        Err(Ok(e)) => assert_eq!(e, Error::BatchTooLarge),
+        _ => panic!("expected BatchTooLarge"),
+    }
+}
+
+#[test]
+fn test_custody_handoff() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register_contract(None, ChainLogisticsContract);
+    let client = ChainLogisticsContractClient::new(&env, &contract_id);
+
+    let shipper = Address::generate(&env);
+    let trucker = Address::generate(&env);
+    let airline = Address::generate(&env);
+    let buyer = Address::generate(&env);
+    let id = client.register_product(&shipper, &String::from_str(&env, "Japan"), &String::from_str(&env, "Tuna"));
+    let port = String::from_str(&env, "Narita");
+    let note = String::from_str(&env, "");
+
+    assert_eq!(client.get_custodian(&id), None);
+    client.set_custodian(&shipper, &id, &trucker);
+    assert_eq!(client.get_custodian(&id), Some(trucker.clone()));
+    assert!(client.is_authorized(&id, &trucker));
+    client.add_checkpoint(&trucker, &id, &CheckpointKind::Pickup, &port, &note);
+
+    // Handoff to the second carrier drops the first
+    client.set_custodian(&shipper, &id, &airline);
+    assert!(!client.is_authorized(&id, &trucker));
+    let res = client.try_release_custody(&trucker, &id);
+    match res {
+        Err(Ok(e)) => assert_eq!(e, Error::Unauthorized),
+        _ => panic!("expected Unauthorized"),
+    }
+    client.add_checkpoint(&airline, &id, &CheckpointKind::Departure, &port, &note);
+
+    // Title cannot move while goods are held, unless the owner opts in
+    let res = client.try_transfer_product(&shipper, &id, &buyer);
+    match res {
+        Err(Ok(e)) => assert_eq!(e, Error::ProductInCustody),
+        _ => panic!("expected ProductInCustody"),
+    }
+    client.set_transfer_while_in_custody(&shipper, &id, &true);
+    client.transfer_product(&shipper, &id, &buyer);
+    assert_eq!(client.get_product(&id).unwrap().owner, buyer);
+    assert_eq!(client.get_custodian(&id), Some(airline.clone()));
+
+    client.release_custody(&airline, &id);
+    assert_eq!(client.get_custodian(&id), None);
+    assert!(!client.is_authorized(&id, &airline));
+    let res = client.try_add_checkpoint(&airline, &id, &CheckpointKind::Arrival, &port, &note);
+    match res {
+        Err(Ok(e)) => assert_eq!(e, Error::Unauthorized),
+        _ => panic!("expected Unauthorized"),
+    }
+}
+
+#[test]
+fn test_custody_log() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register_contract(None, ChainLogisticsContract);
+    let client = ChainLogisticsContractClient::new(&env, &contract_id);
+
+    let shipper = Address::generate(&env);
+    let carrier = Address::generate(&env);
+    let forwarder = Address::generate(&env);
+    let buyer = Address::generate(&env);
+
+    env.ledger().with_mut(|li| li.timestamp = 100);
+    let id = client.register_product(&shipper, &String::from_str(&env, "Italy"), &String::from_str(&env, "Olive oil"));
+    env.ledger().with_mut(|li| li.timestamp = 200);
+    client.set_custodian(&shipper, &id, &carrier);
+    env.ledger().with_mut(|li| li.timestamp = 300);
+    client.set_custodian(&shipper, &id, &forwarder);
+    env.ledger().with_mut(|li| li.timestamp = 400);
+    client.release_custody(&forwarder, &id);
+    env.ledger().with_mut(|li| li.timestamp = 500);
+    client.transfer_product(&shipper, &id, &buyer);
+
+    let expected = [
+        (&shipper, &shipper, CustodyRole::Owner, 100),
+        (&shipper, &carrier, CustodyRole::Custodian, 200),
+        (&carrier, &forwarder, CustodyRole::Custodian, 300),
+        (&forwarder, &shipper, CustodyRole::Custodian, 400),
+        (&shipper, &buyer, CustodyRole::Owner, 500),
+    ];
+    let mut log = client.get_custody_log(&id, &0, &2);
+    log.append(&client.get_custody_log(&id, &2, &2));
+    log.append(&client.get_custody_log(&id, &4, &2));
+    assert_eq!(log.len(), 5);
+    for (i, (from, to, role, ts)) in expected.iter().enumerate() {
+        let entry = log.get(i as u32).unwrap();
+        assert_eq!(entry.seq, i as u32 + 1);
+        assert_eq!(&entry.from, *from);
+        assert_eq!(&entry.to, *to);
+        assert_eq!(entry.role, *role);
+        assert_eq!(entry.timestamp, *ts);
+    }
+    assert_eq!(client.get_custody_log(&id, &5, &2).len(), 0);
+}
+
+#[test]
+fn test_delivery_deadline() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register_contract(None, ChainLogisticsContract);
+    let client = ChainLogisticsContractClient::new(&env, &contract_id);
+
+    let owner = Address::generate(&env);
+    let origin = String::from_str(&env, "Spain");
+    let late = client.register_product(&owner, &origin, &String::from_str(&env, "Oranges"));
+    let delivered = client.register_product(&owner, &origin, &String::from_str(&env, "Lemons"));
+    let untimed = client.register_product(&owner, &origin, &String::from_str(&env, "Limes"));
+    env.ledger().with_mut(|li| li.timestamp = 1_000);
+
+    let res = client.try_set_delivery_deadline(&owner, &late, &1_000);
+    match res {
+        Err(Ok(e)) => assert_eq!(e, Error::InvalidTimestamp),
+        _ => panic!("expected InvalidTimestamp"),
+    }
+    client.set_delivery_deadline(&owner, &late, &2_000);
+    client.set_delivery_deadline(&owner, &delivered, &2_000);
+    assert_eq!(client.get_delivery_deadline(&late), Some(2_000));
+    assert_eq!(client.get_delivery_deadline(&untimed), None);
+
+    // The deadline second itself is still on time
+    env.ledger().with_mut(|li| li.timestamp = 2_000);
+    assert!(!client.is_overdue(&late));
+    client.confirm_delivery(&owner, &delivered);
+
+    env.ledger().with_mut(|li| li.timestamp = 2_001);
+    assert!(client.is_overdue(&late));
+    assert!(!client.is_overdue(&delivered));
+    assert!(!client.is_overdue(&untimed));
+
+    let overdue = client.get_overdue_products_by_owner(&owner, &0, &10);
+    assert_eq!(overdue.len(), 1);
+    assert_eq!(overdue.get(0).unwrap().id, late);
+
+    env.ledger().with_mut(|li| li.timestamp = 1_000_000);
+    assert!(!client.is_overdue(&delivered));
+}
+
+#[test]
+fn test_proof_of_delivery() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register_contract(None, ChainLogisticsContract);
+    let client = ChainLogisticsContractClient::new(&env, &contract_id);
+
+    let shipper = Address::generate(&env);
+    let carrier = Address::generate(&env);
+    let consignee = Address::generate(&env);
+    let id = client.register_product(&shipper, &String::from_str(&env, "Korea"), &String::from_str(&env, "Monitors"));
+    let signature = BytesN::from_array(&env, &[7u8; 32]);
+
+    client.set_custodian(&shipper, &id, &carrier);
+    assert_eq!(client.get_pod(&id), None);
+
+    // The carrier cannot sign for the goods, nor can an un-nominated consignee
+    for who in [&carrier, &consignee] {
+        let res = client.try_submit_pod(who, &id, &signature);
+        match res {
+            Err(Ok(e)) => assert_eq!(e, Error::Unauthorized),
+            _ => panic!("expected Unauthorized"),
+        }
+    }
+
+    client.set_consignee(&shipper, &id, &consignee);
+    assert_eq!(client.get_consignee(&id), Some(consignee.clone()));
+    env.ledger().with_mut(|li| li.timestamp = 4_200);
+    client.submit_pod(&consignee, &id, &signature);
+
+    let pod = client.get_pod(&id).unwrap();
+    assert_eq!(pod.recipient, consignee);
+    assert_eq!(pod.evidence_hash, signature);
+    assert_eq!(pod.timestamp, 4_200);
+    assert!(client.is_delivered(&id));
+
+    let res = client.try_submit_pod(&shipper, &id, &signature);
+    match res {
+        Err(Ok(e)) => assert_eq!(e, Error::AlreadyDelivered),
+        _ => panic!("expected AlreadyDelivered"),
+    }
+}
+
+#[test]
+fn test_route_plan_and_deviation() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register_contract(None, ChainLogisticsContract);
+    let client = ChainLogisticsContractClient::new(&env, &contract_id);
+
+    let owner = Address::generate(&env);
+    let origin = String::from_str(&env, "Mexico");
+    let on_plan = client.register_product(&owner, &origin, &String::from_str(&env, "Limes"));
+    let strayed = client.register_product(&owner, &origin, &String::from_str(&env, "Chilies"));
+    let note = String::from_str(&env, "");
+
+    let mut route = Vec::new(&env);
+    for stop in ["Veracruz", "Houston", "Chicago"] {
+        route.push_back(String::from_str(&env, stop));
+    }
+    client.set_route(&owner, &on_plan, &route);
+    client.set_route(&owner, &strayed, &route);
+    assert_eq!(client.get_route(&on_plan), route);
+
+    for stop in route.iter() {
+        client.add_checkpoint(&owner, &on_plan, &CheckpointKind::Arrival, &stop, &note);
+    }
+    assert_eq!(client.get_route_progress(&on_plan), 3);
+    assert!(!client.has_route_deviation(&on_plan));
+
+    // Skipping Houston is flagged but still written
+    client.add_checkpoint(&owner, &strayed, &CheckpointKind::Departure, &route.get(0).unwrap(), &note);
+    client.add_checkpoint(&owner, &strayed, &CheckpointKind::Arrival, &route.get(2).unwrap(), &note);
+    assert!(client.has_route_deviation(&strayed));
+    assert_eq!(client.get_route_progress(&strayed), 1);
+    assert_eq!(client.get_checkpoint_count(&strayed), 2);
+    client.clear_deviation(&owner, &strayed);
+    assert!(!client.has_route_deviation(&strayed));
+
+    // The plan is frozen once tracking starts
+    let res = client.try_set_route(&owner, &strayed, &route);
+    match res {
+        Err(Ok(e)) => assert_eq!(e, Error::InvalidInput),
+        _ => panic!("expected InvalidInput"),
+    }
+}
+
+#[test]
+fn test_checkpoint_dispute_and_correction() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register_contract(None, ChainLogisticsContract);
+    let client = ChainLogisticsContractClient::new(&env, &contract_id);
+
+    let owner = Address::generate(&env);
+    let carrier = Address::generate(&env);
+    let other = Address::generate(&env);
+    let id = client.register_product(&owner, &String::from_str(&env, "India"), &String::from_str(&env, "Spices"));
+    client.add_authorized_actor(&owner, &id, &carrier);
+    client.add_authorized_actor(&owner, &id, &other);
+
+    let typo = String::from_str(&env, "Mumbia");
+    let fixed = String::from_str(&env, "Mumbai");
+    let note = String::from_str(&env, "");
+    client.add_checkpoint(&carrier, &id, &CheckpointKind::Departure, &typo, &note);
+    client.add_checkpoint(&carrier, &id, &CheckpointKind::Arrival, &String::from_str(&env, "Dubai"), &note);
+
+    // Only the owner disputes
+    let reason = String::from_str(&env, "Misspelled port");
+    let res = client.try_dispute_checkpoint(&carrier, &id, &1, &reason);
+    match res {
+        Err(Ok(e)) => assert_eq!(e, Error::Unauthorized),
+        _ => panic!("expected Unauthorized"),
+    }
+    client.dispute_checkpoint(&owner, &id, &1, &reason);
+    client.dispute_checkpoint(&owner, &id, &2, &String::from_str(&env, "Time looks wrong"));
+    assert_eq!(client.get_checkpoint_dispute(&id, &1), Some(reason));
+
+    // Only the author or the owner corrects
+    let res = client.try_append_correction(&other, &id, &1, &fixed);
+    match res {
+        Err(Ok(e)) => assert_eq!(e, Error::Unauthorized),
+        _ => panic!("expected Unauthorized"),
+    }
+    client.append_correction(&carrier, &id, &1, &fixed);
+    assert_eq!(client.get_checkpoint(&id, &1).location, fixed);
+    assert_eq!(client.get_checkpoints(&id, &0, &1).get(0).unwrap().location, fixed);
+    assert_eq!(client.get_original_checkpoint(&id, &1).location, typo);
+    assert_eq!(client.get_checkpoint_correction(&id, &1).unwrap().actor, carrier);
+
+    // Disputed without correction reads as recorded
+    assert_eq!(client.get_checkpoint_correction(&id, &2), None);
+    assert_eq!(client.get_latest_checkpoint(&id).unwrap().location, String::from_str(&env, "Dubai"));
+    client.append_correction(&owner, &id, &2, &String::from_str(&env, "Jebel Ali"));
+    assert_eq!(client.get_latest_checkpoint(&id).unwrap().location, String::from_str(&env, "Jebel Ali"));
+}
+
+#[test]
+fn test_checkpoints_by_actor() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register_contract(None, ChainLogisticsContract);
+    let client = ChainLogisticsContractClient::new(&env, &contract_id);
+
+    let carrier = Address::generate(&env);
+    let origin = String::from_str(&env, "Egypt");
+    let mut ids = [0u64; 3];
+    for (i, name) in ["Cotton", "Dates", "Linen"].iter().enumerate() {
+        let owner = Address::generate(&env);
+        ids[i] = client.register_product(&owner, &origin, &String::from_str(&env, name));
+        client.add_authorized_actor(&owner, &ids[i], &carrier);
+    }
+
+    let note = String::from_str(&env, "");
+    let stops = ["Alexandria", "Piraeus"];
+    let mut ts = 0u64;
+    for stop in stops {
+        for id in ids {
+            ts += 10;
+            env.ledger().with_mut(|li| li.timestamp = ts);
+            client.add_checkpoint(&carrier, &id, &CheckpointKind::Arrival, &String::from_str(&env, stop), &note);
+        }
+    }
+    assert_eq!(client.get_checkpoints_by_actor(&Address::generate(&env), &0, &10).len(), 0);
+
+    let mut all = client.get_checkpoints_by_actor(&carrier, &0, &4);
+    assert_eq!(all.len(), 4);
+    all.append(&client.get_checkpoints_by_actor(&carrier, &4, &4));
+    assert_eq!(all.len(), 6);
+    for (i, r) in all.iter().enumerate() {
+        assert_eq!(r.product_id, ids[i % 3]);
+        assert_eq!(r.seq, i as u32 / 3 + 1);
+        assert_eq!(r.location, String::from_str(&env, stops[i / 3]));
+        assert_eq!(r.timestamp, (i as u64 + 1) * 10);
+    }
+}
+
+#[test]
+fn test_sensor_channels() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register_contract(None, ChainLogisticsContract);
+    let client = ChainLogisticsContractClient::new(&env, &contract_id);
+
+    let owner = Address::generate(&env);
+    let id = client.register_product(&owner, &String::from_str(&env, "Taiwan"), &String::from_str(&env, "Chips"));
+    env.ledger().with_mut(|li| li.timestamp = 1_000);
+
+    let humidity = symbol_short!("humidity");
+    let shock = symbol_short!("shock");
+    assert_eq!(client.record_reading(&owner, &id, &humidity, &45, &100), 1);
+    assert_eq!(client.record_reading(&owner, &id, &shock, &3, &200), 1);
+    assert_eq!(client.record_reading(&owner, &id, &humidity, &60, &300), 2);
+    assert_eq!(client.record_reading(&owner, &id, &shock, &-2, &400), 2);
+    assert_eq!(client.record_reading(&owner, &id, &humidity, &38, &500), 3);
+
+    let h = client.get_channel_stats(&id, &humidity);
+    assert_eq!((h.count, h.min, h.max), (3, 38, 60));
+    let s = client.get_channel_stats(&id, &shock);
+    assert_eq!((s.count, s.min, s.max), (2, -2, 3));
+    assert_eq!(client.get_channel_stats(&id, &symbol_short!("light")).count, 0);
+
+    let shocks = client.get_readings(&id, &shock, &0, &10);
+    assert_eq!(shocks.len(), 2);
+    assert_eq!(shocks.get(1).unwrap().timestamp, 400);
+    assert_eq!(client.get_readings(&id, &humidity, &2, &10).get(0).unwrap().value, 38);
+
+    // Any symbol is a channel, up to the per-product cap
+    for ch in ["c3", "c4", "c5", "c6", "c7", "c8"] {
+        client.record_reading(&owner, &id, &Symbol::new(&env, ch), &0, &600);
+    }
+    assert_eq!(client.get_channels(&id).len(), 8);
+    let res = client.try_record_reading(&owner, &id, &symbol_short!("c9"), &0, &600);
+    match res {
+        Err(Ok(e)) => assert_eq!(e, Error::TooManyChannels),
+        _ => panic!("expected TooManyChannels"),
+    }
+    client.record_reading(&owner, &id, &humidity, &50, &700);
+}
+
+#[test]
+fn test_eta_updates() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register_contract(None, ChainLogisticsContract);
+    let client = ChainLogisticsContractClient::new(&env, &contract_id);
+
+    let owner = Address::generate(&env);
+    let carrier = Address::generate(&env);
+    let id = client.register_product(&owner, &String::from_str(&env, "Canada"), &String::from_str(&env, "Lumber"));
+    client.set_custodian(&owner, &id, &carrier);
+    assert_eq!(client.get_eta(&id), None);
+
+    for i in 1..=12u64 {
+        env.ledger().with_mut(|li| li.timestamp = i * 100);
+        client.update_eta(&carrier, &id, &(10_000 + i));
+    }
+    assert_eq!(client.get_eta(&id), Some(10_012));
+
+    // Only the last ten revisions survive
+    let history = client.get_eta_history(&id);
+    assert_eq!(history.len(), 10);
+    assert_eq!(history.get(0).unwrap().eta, 10_003);
+    assert_eq!(history.get(0).unwrap().timestamp, 300);
+    assert_eq!(history.get(9).unwrap().eta, 10_012);
+    assert_eq!(history.get(9).unwrap().actor, carrier);
+
+    env.ledger().with_mut(|li| li.timestamp = 5_000);
+    let res = client.try_update_eta(&carrier, &id, &4_999);
+    match res {
+        Err(Ok(e)) => assert_eq!(e, Error::InvalidTimestamp),
+        _ => panic!("expected InvalidTimestamp"),
+    }
+    client.update_eta(&owner, &id, &5_000);
+    assert_eq!(client.get_eta(&id), Some(5_000));
+
+    let res = client.try_update_eta(&Address::generate(&env), &id, &6_000);
+    match res {
+        Err(Ok(e)) => assert_eq!(e, Error::Unauthorized),
+        _ => panic!("expected Unauthorized"),
+    }
+}
+
+#[test]
+fn test_transfer_updates_owner_index() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register_contract(None, ChainLogisticsContract);
+    let client = ChainLogisticsContractClient::new(&env, &contract_id);
+
+    let alice = Address::generate(&env);
+    let bob = Address::generate(&env);
+    let origin = String::from_str(&env, "Brazil");
+    let first = client.register_product(&alice, &origin, &String::from_str(&env, "Beans"));
+    let second = client.register_product(&alice, &origin, &String::from_str(&env, "Sugar"));
+
+    client.transfer_product(&alice, &first, &bob);
+    let alices = client.get_products_by_owner(&alice, &0, &10);
+    let bobs = client.get_products_by_owner(&bob, &0, &10);
+    assert_eq!(alices.len(), 1);
+    assert_eq!(alices.get(0).unwrap().id, second);
+    assert_eq!(bobs.len(), 1);
+    assert_eq!(bobs.get(0).unwrap().id, first);
+
+    // Back and forth: each owner lists the product exactly once
+    client.transfer_product(&bob, &first, &alice);
+    client.transfer_product(&alice, &second, &bob);
+    client.transfer_product(&alice, &first, &bob);
+    assert_eq!(client.get_products_by_owner(&alice, &0, &10).len(), 0);
+    let bobs = client.get_products_by_owner(&bob, &0, &10);
+    assert_eq!(bobs.len(), 2);
+    assert_eq!(bobs.get(0).unwrap().id, second);
+    assert_eq!(bobs.get(1).unwrap().id, first);
+}
+
+#[test]
+fn test_two_step_transfer() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register_contract(None, ChainLogisticsContract);
+    let client = ChainLogisticsContractClient::new(&env, &contract_id);
+
+    let seller = Address::generate(&env);
+    let buyer = Address::generate(&env);
+    let other_buyer = Address::generate(&env);
+    let stranger = Address::generate(&env);
+    let id = client.register_product(&seller, &String::from_str(&env, "Chile"), &String::from_str(&env, "Wine"));
+
+    let res = client.try_accept_transfer(&buyer, &id);
+    match res {
+        Err(Ok(e)) => assert_eq!(e, Error::NoPendingTransfer),
+        _ => panic!("expected NoPendingTransfer"),
+    }
+
+    client.propose_transfer(&seller, &id, &buyer, &None);
+    // Proposing again replaces the first recipient
+    client.propose_transfer(&seller, &id, &other_buyer, &None);
+    let pending = client.get_pending_transfer(&id).unwrap();
+    assert_eq!(pending.from, seller);
+    assert_eq!(pending.to, other_buyer);
+
+    for who in [&buyer, &stranger] {
+        let res = client.try_accept_transfer(who, &id);
+        match res {
+            Err(Ok(e)) => assert_eq!(e, Error::Unauthorized),
+            _ => panic!("expected Unauthorized"),
+        }
+    }
+    assert_eq!(client.get_product(&id).unwrap().owner, seller);
+
+    client.accept_transfer(&other_buyer, &id);
+    assert_eq!(client.get_product(&id).unwrap().owner, other_buyer);
+    assert_eq!(client.get_pending_transfer(&id), None);
+    assert_eq!(client.get_products_by_owner(&other_buyer, &0, &10).len(), 1);
+    assert_eq!(client.get_products_by_owner(&seller, &0, &10).len(), 0);
+
+    // The atomic path still works and clears stale proposals
+    client.propose_transfer(&other_buyer, &id, &stranger, &None);
+    client.transfer_product(&other_buyer, &id, &buyer);
+    assert_eq!(client.get_pending_transfer(&id), None);
+    assert_eq!(client.get_product(&id).unwrap().owner, buyer);
+}
+
+#[test]
+fn test_cancel_and_expire_transfer() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register_contract(None, ChainLogisticsContract);
+    let client = ChainLogisticsContractClient::new(&env, &contract_id);
+
+    let seller = Address::generate(&env);
+    let buyer = Address::generate(&env);
+    let backup = Address::generate(&env);
+    let id = client.register_product(&seller, &String::from_str(&env, "Greece"), &String::from_str(&env, "Feta"));
+    env.ledger().with_mut(|li| li.timestamp = 1_000);
+
+    // Cancel, then the old recipient cannot accept
+    client.propose_transfer(&seller, &id, &buyer, &None);
+    let res = client.try_cancel_transfer(&buyer, &id);
+    match res {
+        Err(Ok(e)) => assert_eq!(e, Error::Unauthorized),
+        _ => panic!("expected Unauthorized"),
+    }
+    client.cancel_transfer(&seller, &id);
+    let res = client.try_accept_transfer(&buyer, &id);
+    match res {
+        Err(Ok(e)) => assert_eq!(e, Error::NoPendingTransfer),
+        _ => panic!("expected NoPendingTransfer"),
+    }
+
+    let res = client.try_propose_transfer(&seller, &id, &buyer, &Some(1_000));
+    match res {
+        Err(Ok(e)) => assert_eq!(e, Error::InvalidTimestamp),
+        _ => panic!("expected InvalidTimestamp"),
+    }
+    client.propose_transfer(&seller, &id, &buyer, &Some(2_000));
+    let pending = client.get_pending_transfer(&id).unwrap();
+    assert_eq!(pending.expires_at, 2_000);
+    assert_eq!(pending.proposed_at, 1_000);
+
+    // Expired from the deadline second onwards
+    env.ledger().with_mut(|li| li.timestamp = 2_000);
+    let res = client.try_accept_transfer(&buyer, &id);
+    match res {
+        Err(Ok(e)) => assert_eq!(e, Error::TransferExpired),
+        _ => panic!("expected TransferExpired"),
+    }
+
+    // Re-propose to someone else after cancelling; one second before expiry is fine
+    client.cancel_transfer(&seller, &id);
+    client.propose_transfer(&seller, &id, &backup, &Some(3_000));
+    assert_eq!(client.get_pending_transfer(&id).unwrap().to, backup);
+    env.ledger().with_mut(|li| li.timestamp = 2_999);
+    client.accept_transfer(&backup, &id);
+    assert_eq!(client.get_product(&id).unwrap().owner, backup);
+}
+
+#[test]
+fn test_transfer_history() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register_contract(None, ChainLogisticsContract);
+    let client = ChainLogisticsContractClient::new(&env, &contract_id);
+
+    let maker = Address::generate(&env);
+    let wholesaler = Address::generate(&env);
+    let retailer = Address::generate(&env);
+    let shopper = Address::generate(&env);
+    let origin = String::from_str(&env, "Portugal");
+    env.ledger().with_mut(|li| li.timestamp = 10);
+    let id = client.register_product(&maker, &origin, &String::from_str(&env, "Cork"));
+    let untouched = client.register_product(&maker, &origin, &String::from_str(&env, "Port"));
+
+    env.ledger().with_mut(|li| li.timestamp = 20);
+    client.transfer_product(&maker, &id, &wholesaler);
+    env.ledger().with_mut(|li| li.timestamp = 30);
+    client.propose_transfer(&wholesaler, &id, &retailer, &None);
+    client.accept_transfer(&retailer, &id);
+    env.ledger().with_mut(|li| li.timestamp = 40);
+    client.transfer_product(&retailer, &id, &shopper);
+
+    assert_eq!(client.get_transfer_count(&id), 3);
+    let mut history = client.get_transfer_history(&id, &0, &2);
+    assert_eq!(history.len(), 2);
+    history.append(&client.get_transfer_history(&id, &2, &2));
+    assert_eq!(history.len(), 4);
+    let owners = [&maker, &wholesaler, &retailer, &shopper];
+    for (i, record) in history.iter().enumerate() {
+        assert_eq!(record.seq, i as u32);
+        assert_eq!(&record.to, owners[i]);
+        assert_eq!(&record.from, owners[i.saturating_sub(1)]);
+        assert_eq!(record.timestamp, (i as u64 + 1) * 10);
+    }
+
+    assert_eq!(client.get_transfer_count(&untouched), 0);
+    let only = client.get_transfer_history(&untouched, &0, &10);
+    assert_eq!(only.len(), 1);
+    assert_eq!(only.get(0).unwrap().to, maker);
+    assert_eq!(client.get_transfer_history(&untouched, &1, &10).len(), 0);
+}
+
+#[test]
+fn test_batch_transfer() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register_contract(None, ChainLogisticsContract);
+    let client = ChainLogisticsContractClient::new(&env, &contract_id);
+
+    let warehouse = Address::generate(&env);
+    let neighbour = Address::generate(&env);
+    let buyer = Address::generate(&env);
+    let origin = String::from_str(&env, "Poland");
+    let mut pallet = Vec::new(&env);
+    for _ in 0..3 {
+        pallet.push_back(client.register_product(&warehouse, &origin, &String::from_str(&env, "Crate")));
+    }
+    let foreign = client.register_product(&neighbour, &origin, &String::from_str(&env, "Crate"));
+
+    // One foreign product rejects the whole pallet
+    let mut mixed = pallet.clone();
+    mixed.push_back(foreign);
+    let res = client.try_transfer_products(&warehouse, &mixed, &buyer);
+    match res {
+        Err(Ok(e)) => assert_eq!(e, Error::Unauthorized),
+        _ => panic!("expected Unauthorized"),
+    }
+    assert_eq!(client.get_products_by_owner(&warehouse, &0, &10).len(), 3);
+    assert_eq!(client.get_products_by_owner(&buyer, &0, &10).len(), 0);
+
+    client.propose_transfer(&warehouse, &pallet.get(0).unwrap(), &neighbour, &None);
+    client.transfer_products(&warehouse, &pallet, &buyer);
+    assert_eq!(client.get_products_by_owner(&warehouse, &0, &10).len(), 0);
+    assert_eq!(client.get_products_by_owner(&buyer, &0, &10).len(), 3);
+    for id in pallet.iter() {
+        assert_eq!(client.get_product(&id).unwrap().owner, buyer);
+        assert_eq!(client.get_transfer_count(&id), 1);
+    }
+    assert_eq!(client.get_pending_transfer(&pallet.get(0).unwrap()), None);
+
+    let mut too_many = Vec::new(&env);
+    for i in 0..51u64 {
+        too_many.push_back(i + 1);
+    }
+    let res = client.try_transfer_products(&buyer, &too_many, &warehouse);
+    match res {
+        Err(Ok(e)) => assert_eq!(e, Error::BatchTooLarge),
+        _ => panic!("expected BatchTooLarge"),
+    }
+}
+
+#[test]
+fn test_transfer_agent() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register_contract(None, ChainLogisticsContract);
+    let client = ChainLogisticsContractClient::new(&env, &contract_id);
+
+    let seller = Address::generate(&env);
+    let market = Address::generate(&env);
+    let accomplice = Address::generate(&env);
+    let buyer = Address::generate(&env);
+    let origin = String::from_str(&env, "Turkey");
+    let rug = client.register_product(&seller, &origin, &String::from_str(&env, "Rug"));
+    let lamp = client.register_product(&seller, &origin, &String::from_str(&env, "Lamp"));
+
+    client.approve_transfer_agent(&seller, &rug, &market);
+    assert_eq!(client.get_approved_agent(&rug), Some(market.clone()));
+
+    // The agent cannot delegate further
+    let res = client.try_approve_transfer_agent(&market, &rug, &accomplice);
+    match res {
+        Err(Ok(e)) => assert_eq!(e, Error::Unauthorized),
+        _ => panic!("expected Unauthorized"),
+    }
+    let res = client.try_transfer_from(&market, &lamp, &buyer);
+    match res {
+        Err(Ok(e)) => assert_eq!(e, Error::Unauthorized),
+        _ => panic!("expected Unauthorized"),
+    }
+
+    client.transfer_from(&market, &rug, &buyer);
+    assert_eq!(client.get_product(&rug).unwrap().owner, buyer);
+    assert_eq!(client.get_approved_agent(&rug), None);
+    let res = client.try_transfer_from(&market, &rug, &seller);
+    match res {
+        Err(Ok(e)) => assert_eq!(e, Error::Unauthorized),
+        _ => panic!("expected Unauthorized"),
+    }
+
+    client.approve_transfer_agent(&seller, &lamp, &market);
+    client.revoke_transfer_agent(&seller, &lamp);
+    let res = client.try_transfer_from(&market, &lamp, &buyer);
+    match res {
+        Err(Ok(e)) => assert_eq!(e, Error::Unauthorized),
+        _ => panic!("expected Unauthorized"),
+    }
+    assert_eq!(client.get_product(&lamp).unwrap().owner, seller);
+}
+
+#[test]
+fn test_last_transfer_on_product() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register_contract(None, ChainLogisticsContract);
+    let client = ChainLogisticsContractClient::new(&env, &contract_id);
+
+    let first = Address::generate(&env);
+    let second = Address::generate(&env);
+    let third = Address::generate(&env);
+    let id = client.register_product(&first, &String::from_str(&env, "Austria"), &String::from_str(&env, "Skis"));
+    let product = client.get_product(&id).unwrap();
+    assert_eq!(product.previous_owner.len(), 0);
+    assert_eq!(product.transferred_at, None);
+
+    env.ledger().with_mut(|li| li.timestamp = 700);
+    client.transfer_product(&first, &id, &second);
+    let product = client.get_product(&id).unwrap();
+    assert_eq!(product.previous_owner, Vec::from_array(&env, [first.clone()]));
+    assert_eq!(product.transferred_at, Some(700));
+
+    env.ledger().with_mut(|li| li.timestamp = 900);
+    client.approve_transfer_agent(&second, &id, &first);
+    client.transfer_from(&first, &id, &third);
+    let product = client.get_product(&id).unwrap();
+    assert_eq!(product.previous_owner, Vec::from_array(&env, [second]));
+    assert_eq!(product.transferred_at, Some(900));
+}
+
+#[test]
+fn test_transfer_with_memo() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register_contract(None, ChainLogisticsContract);
+    let client = ChainLogisticsContractClient::new(&env, &contract_id);
+
+    let seller = Address::generate(&env);
+    let buyer = Address::generate(&env);
+    let id = client.register_product(&seller, &String::from_str(&env, "Sweden"), &String::from_str(&env, "Furniture"));
+
+    let memo = String::from_str(&env, "PO-2024-00117");
+    client.transfer_product_with_memo(&seller, &id, &buyer, &memo);
+    client.transfer_product(&buyer, &id, &seller);
+
+    let history = client.get_transfer_history(&id, &0, &10);
+    assert_eq!(history.len(), 3);
+    assert_eq!(history.get(0).unwrap().memo, String::from_str(&env, ""));
+    assert_eq!(history.get(1).unwrap().memo, memo);
+    assert_eq!(history.get(2).unwrap().memo, String::from_str(&env, ""));
+
+    let at_cap = String::from_bytes(&env, &[b'x'; 128]);
+    client.transfer_product_with_memo(&seller, &id, &buyer, &at_cap);
+    let over_cap = String::from_bytes(&env, &[b'x'; 129]);
+    let res = client.try_transfer_product_with_memo(&buyer, &id, &seller, &over_cap);
+    match res {
+        Err(Ok(e)) => assert_eq!(e, Error::MemoTooLong),
+        _ => panic!("expected MemoTooLong"),
+    }
+    assert_eq!(client.get_product(&id).unwrap().owner, buyer);
+}
+
+#[test]
+fn test_transfer_guards() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register_contract(None, ChainLogisticsContract);
+    let client = ChainLogisticsContractClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    let owner = Address::generate(&env);
+    let buyer = Address::generate(&env);
+    let origin = String::from_str(&env, "Ireland");
+    client.initialize(&admin, &String::from_str(&env, "ChainLogistics"));
+    let clean = client.register_product(&owner, &origin, &String::from_str(&env, "Butter"));
+    let recalled = client.register_product(&owner, &origin, &String::from_str(&env, "Cream"));
+    let inactive = client.register_product(&owner, &origin, &String::from_str(&env, "Milk"));
+
+    let lot = String::from_str(&env, "LOT-9");
+    client.set_lot(&owner, &recalled, &lot);
+    client.recall_by_lot(&admin, &lot, &0, &10);
+    env.as_contract(&contract_id, || {
+        let mut product = storage::get_product(&env, inactive).unwrap();
+        product.active = false;
+        storage::put_product(&env, &product);
+    });
+
+    let res = client.try_transfer_product(&owner, &recalled, &buyer);
+    match res {
+        Err(Ok(e)) => assert_eq!(e, Error::ProductRecalled),
+        _ => panic!("expected ProductRecalled"),
+    }
+    let res = client.try_transfer_product(&owner, &inactive, &buyer);
+    match res {
+        Err(Ok(e)) => assert_eq!(e, Error::ProductInactive),
+        _ => panic!("expected ProductInactive"),
+    }
+    let res = client.try_transfer_product(&owner, &clean, &owner);
+    match res {
+        Err(Ok(e)) => assert_eq!(e, Error::SelfTransfer),
+        _ => panic!("expected SelfTransfer"),
+    }
+
+    // Batch and agent paths share the same guards
+    let mut batch = Vec::new(&env);
+    batch.push_back(clean);
+    batch.push_back(recalled);
+    let res = client.try_transfer_products(&owner, &batch, &buyer);
+    match res {
+        Err(Ok(e)) => assert_eq!(e, Error::ProductRecalled),
+        _ => panic!("expected ProductRecalled"),
+    }
+    client.approve_transfer_agent(&owner, &inactive, &buyer);
+    let res = client.try_transfer_from(&buyer, &inactive, &buyer);
+    match res {
+        Err(Ok(e)) => assert_eq!(e, Error::ProductInactive),
+        _ => panic!("expected ProductInactive"),
+    }
+
+    client.transfer_product(&owner, &clean, &buyer);
+    assert_eq!(client.get_product(&clean).unwrap().owner, buyer);
+}
+
+#[test]
+fn test_transfer_stats() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register_contract(None, ChainLogisticsContract);
+    let client = ChainLogisticsContractClient::new(&env, &contract_id);
+
+    let alice = Address::generate(&env);
+    let bob = Address::generate(&env);
+    let carol = Address::generate(&env);
+    let origin = String::from_str(&env, "Belgium");
+    assert_eq!(client.get_stats().total_transfers, 0);
+
+    let mut ids = Vec::new(&env);
+    for _ in 0..3 {
+        ids.push_back(client.register_product(&alice, &origin, &String::from_str(&env, "Waffles")));
+    }
+    let first = ids.get(0).unwrap();
+    let second = ids.get(1).unwrap();
+    client.transfer_product(&alice, &first, &bob);
+    client.propose_transfer(&bob, &first, &carol, &None);
+    client.accept_transfer(&carol, &first);
+    client.approve_transfer_agent(&alice, &second, &bob);
+    client.transfer_from(&bob, &second, &carol);
+    ids.remove(0);
+    ids.remove(0);
+    client.transfer_products(&alice, &ids, &bob);
+
+    let stats = client.get_stats();
+    assert_eq!(stats.total_products, 3);
+    assert_eq!(stats.active_products, 3);
+    assert_eq!(stats.total_transfers, 4);
+    assert_eq!(client.get_transfer_count_by_owner(&alice), 3);
+    assert_eq!(client.get_transfer_count_by_owner(&bob), 1);
+    assert_eq!(client.get_transfer_count_by_owner(&carol), 0);
+}
+
+#[test]
+fn test_scheduled_transfer() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register_contract(None, ChainLogisticsContract);
+    let client = ChainLogisticsContractClient::new(&env, &contract_id);
+
+    let seller = Address::generate(&env);
+    let buyer = Address::generate(&env);
+    let keeper = Address::generate(&env);
+    let origin = String::from_str(&env, "Scotland");
+    let cask = client.register_product(&seller, &origin, &String::from_str(&env, "Whisky cask"));
+    let crate_id = client.register_product(&seller, &origin, &String::from_str(&env, "Whisky crate"));
+    env.ledger().with_mut(|li| li.timestamp = 1_000);
+
+    client.schedule_transfer(&seller, &cask, &buyer, &86_400);
+    assert_eq!(client.get_scheduled_transfer(&cask).unwrap().not_before, 86_400);
+
+    env.ledger().with_mut(|li| li.timestamp = 86_399);
+    let res = client.try_execute_scheduled_transfer(&keeper, &cask);
+    match res {
+        Err(Ok(e)) => assert_eq!(e, Error::TooEarly),
+        _ => panic!("expected TooEarly"),
+    }
+    assert_eq!(client.get_product(&cask).unwrap().owner, seller);
+
+    // At the boundary second a third party can execute it
+    env.ledger().with_mut(|li| li.timestamp = 86_400);
+    client.execute_scheduled_transfer(&keeper, &cask);
+    assert_eq!(client.get_product(&cask).unwrap().owner, buyer);
+    assert_eq!(client.get_products_by_owner(&buyer, &0, &10).len(), 1);
+    assert_eq!(client.get_scheduled_transfer(&cask), None);
+
+    client.schedule_transfer(&seller, &crate_id, &buyer, &90_000);
+    client.cancel_scheduled_transfer(&seller, &crate_id);
+    env.ledger().with_mut(|li| li.timestamp = 90_000);
+    let res = client.try_execute_scheduled_transfer(&keeper, &crate_id);
+    match res {
+        Err(Ok(e)) => assert_eq!(e, Error::NoPendingTransfer),
+        _ => panic!("expected NoPendingTransfer"),
+    }
+    assert_eq!(client.get_product(&crate_id).unwrap().owner, seller);
+}
+
+#[test]
+fn test_claimable_transfer() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register_contract(None, ChainLogisticsContract);
+    let client = ChainLogisticsContractClient::new(&env, &contract_id);
+
+    let seller = Address::generate(&env);
+    let guesser = Address::generate(&env);
+    let finder = Address::generate(&env);
+    let origin = String::from_str(&env, "Morocco");
+    let rug = client.register_product(&seller, &origin, &String::from_str(&env, "Rug"));
+    let lamp = client.register_product(&seller, &origin, &String::from_str(&env, "Lamp"));
+    env.ledger().with_mut(|li| li.timestamp = 1_000);
+
+    let secret = Bytes::from_slice(&env, b"pickup code 4471");
+    let hash = env.crypto().sha256(&secret);
+    client.create_claim(&seller, &rug, &hash, &5_000);
+    client.create_claim(&seller, &lamp, &hash, &2_000);
+
+    // Locked against ordinary transfers while the claim is open
+    let res = client.try_transfer_product(&seller, &rug, &finder);
+    match res {
+        Err(Ok(e)) => assert_eq!(e, Error::ProductLocked),
+        _ => panic!("expected ProductLocked"),
+    }
+
+    let res = client.try_redeem_claim(&guesser, &rug, &Bytes::from_slice(&env, b"pickup code 0000"));
+    match res {
+        Err(Ok(e)) => assert_eq!(e, Error::InvalidClaim),
+        _ => panic!("expected InvalidClaim"),
+    }
+    client.redeem_claim(&finder, &rug, &secret);
+    assert_eq!(client.get_product(&rug).unwrap().owner, finder);
+    assert_eq!(client.get_claim(&rug), None);
+
+    // Expired claims cannot be redeemed; the owner revokes only after expiry
+    let res = client.try_revoke_claim(&seller, &lamp);
+    match res {
+        Err(Ok(e)) => assert_eq!(e, Error::TooEarly),
+        _ => panic!("expected TooEarly"),
+    }
+    env.ledger().with_mut(|li| li.timestamp = 2_000);
+    let res = client.try_redeem_claim(&finder, &lamp, &secret);
+    match res {
+        Err(Ok(e)) => assert_eq!(e, Error::ClaimExpired),
+        _ => panic!("expected ClaimExpired"),
+    }
+    client.revoke_claim(&seller, &lamp);
+    client.transfer_product(&seller, &lamp, &finder);
+    assert_eq!(client.get_product(&lamp).unwrap().owner, finder);
+}
+
+#[test]
+fn test_transfer_dispute_window() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register_contract(None, ChainLogisticsContract);
+    let client = ChainLogisticsContractClient::new(&env, &contract_id);
+
+    let sender = Address::generate(&env);
+    let recipient = Address::generate(&env);
+    let next = Address::generate(&env);
+    let origin = String::from_str(&env, "Finland");
+    let damaged = client.register_product(&sender, &origin, &String::from_str(&env, "Glassware"));
+    let fine = client.register_product(&sender, &origin, &String::from_str(&env, "Textiles"));
+    env.ledger().with_mut(|li| li.timestamp = 1_000);
+
+    client.transfer_product_with_window(&sender, &damaged, &recipient, &600);
+    client.transfer_product_with_window(&sender, &fine, &recipient, &600);
+    assert!(!client.get_transfer_finality(&damaged));
+
+    // Nobody moves it during the window, not even the sender
+    let res = client.try_transfer_product(&recipient, &fine, &next);
+    match res {
+        Err(Ok(e)) => assert_eq!(e, Error::ProductLocked),
+        _ => panic!("expected ProductLocked"),
+    }
+    let res = client.try_reject_transfer(&sender, &damaged, &String::from_str(&env, "Mine"));
+    match res {
+        Err(Ok(e)) => assert_eq!(e, Error::Unauthorized),
+        _ => panic!("expected Unauthorized"),
+    }
+
+    env.ledger().with_mut(|li| li.timestamp = 1_599);
+    let reason = String::from_str(&env, "Arrived broken");
+    client.reject_transfer(&recipient, &damaged, &reason);
+    assert_eq!(client.get_product(&damaged).unwrap().owner, sender);
+    assert!(client.get_transfer_finality(&damaged));
+    let history = client.get_transfer_history(&damaged, &2, &1);
+    assert_eq!(history.get(0).unwrap().memo, reason);
+    assert_eq!(history.get(0).unwrap().to, sender);
+
+    // From the end of the window the transfer is final
+    env.ledger().with_mut(|li| li.timestamp = 1_600);
+    assert!(client.get_transfer_finality(&fine));
+    let res = client.try_reject_transfer(&recipient, &fine, &reason);
+    match res {
+        Err(Ok(e)) => assert_eq!(e, Error::TransferExpired),
+        _ => panic!("expected TransferExpired"),
+    }
+    client.transfer_product(&recipient, &fine, &next);
+    assert_eq!(client.get_product(&fine).unwrap().owner, next);
+}
+
+#[test]
+fn test_renounce_ownership() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register_contract(None, ChainLogisticsContract);
+    let client = ChainLogisticsContractClient::new(&env, &contract_id);
+
+    let maker = Address::generate(&env);
+    let owner = Address::generate(&env);
+    let carrier = Address::generate(&env);
+    let inspector = Address::generate(&env);
+    let buyer = Address::generate(&env);
+    let origin = String::from_str(&env, "Iceland");
+    let lost = client.register_product(&maker, &origin, &String::from_str(&env, "Fish"));
+    client.transfer_product_with_window(&maker, &lost, &owner, &10_000);
+    let kept = client.register_product(&owner, &origin, &String::from_str(&env, "Wool"));
+    client.add_authorized_actor(&owner, &lost, &carrier);
+    client.add_authorized_actor(&owner, &lost, &inspector);
+    let seq = client.add_checkpoint(&carrier, &lost, &CheckpointKind::Pickup, &String::from_str(&env, "Reykjavik"), &String::from_str(&env, ""));
+    client.dispute_checkpoint(&owner, &lost, &seq, &String::from_str(&env, "Wrong dock"));
+    client.propose_transfer(&owner, &lost, &buyer, &None);
+    let status = client.get_status(&lost);
+    assert_eq!(client.get_products_by_status(&status, &0, &10).len(), 1);
+    assert_eq!(client.get_products_with_alerts(&0, &10).len(), 1);
+    assert!(!client.get_transfer_finality(&lost));
+
+    client.renounce_ownership(&owner, &lost);
+    assert!(client.is_renounced(&lost));
+    assert!(!client.is_renounced(&kept));
+
+    // Still readable, but inactive and out of the owner's list
+    let product = client.get_product(&lost).unwrap();
+    assert!(!product.active);
+    let owned = client.get_products_by_owner(&owner, &0, &10);
+    assert_eq!(owned.len(), 1);
+    assert_eq!(owned.get(0).unwrap().id, kept);
+    let stats = client.get_stats();
+    assert_eq!(stats.total_products, 2);
+    assert_eq!(stats.active_products, 1);
+    assert!(!client.is_authorized(&lost, &carrier));
+    assert_eq!(client.get_pending_transfer(&lost), None);
+    let history = client.get_transfer_history(&lost, &0, &10);
+    assert_eq!(history.get(history.len() - 1).unwrap().to, contract_id);
+
+    // Gone from the status and alert listings, with no dispute window left
+    assert_eq!(client.get_products_by_status(&status, &0, &10).len(), 0);
+    assert_eq!(client.get_products_with_alerts(&0, &10).len(), 0);
+    assert_eq!(client.get_alerts(&lost), 0);
+    assert!(client.get_transfer_finality(&lost));
+
+    let res = client.try_transfer_product(&owner, &lost, &buyer);
+    match res {
+        Err(Ok(e)) => assert_eq!(e, Error::ProductRenounced),
+        _ => panic!("expected ProductRenounced"),
+    }
+    let res = client.try_accept_transfer(&buyer, &lost);
+    match res {
+        Err(Ok(e)) => assert_eq!(e, Error::NoPendingTransfer),
+        _ => panic!("expected NoPendingTransfer"),
+    }
+    let res = client.try_add_authorized_actor(&owner, &lost, &carrier);
+    match res {
+        Err(Ok(e)) => assert_eq!(e, Error::ProductRenounced),
+        _ => panic!("expected ProductRenounced"),
+    }
+    let res = client.try_add_note(&owner, &lost, &String::from_str(&env, "gone"));
+    match res {
+        Err(Ok(e)) => assert_eq!(e, Error::ProductRenounced),
+        _ => panic!("expected ProductRenounced"),
+    }
+    let res = client.try_renounce_ownership(&owner, &lost);
+    match res {
+        Err(Ok(e)) => assert_eq!(e, Error::ProductRenounced),
+        _ => panic!("expected ProductRenounced"),
+    }
+}
+
+#[test]
+fn test_transfer_requirements() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register_contract(None, ChainLogisticsContract);
+    let client = ChainLogisticsContractClient::new(&env, &contract_id);
+
+    let exporter = Address::generate(&env);
+    let importer = Address::generate(&env);
+    let id = client.register_product(&exporter, &String::from_str(&env, "Ecuador"), &String::from_str(&env, "Bananas"));
+    let port = String::from_str(&env, "Guayaquil");
+    let note = String::from_str(&env, "");
+
+    let mut required = Vec::new(&env);
+    required.push_back(CheckpointKind::CustomsOut);
+    required.push_back(CheckpointKind::Departure);
+    client.set_transfer_requirements(&exporter, &id, &required);
+    assert_eq!(client.get_transfer_requirements(&id), required);
+
+    client.add_checkpoint(&exporter, &id, &CheckpointKind::Departure, &port, &note);
+    let res = client.try_transfer_product(&exporter, &id, &importer);
+    match res {
+        Err(Ok(e)) => assert_eq!(e, Error::RequirementsNotMet),
+        _ => panic!("expected RequirementsNotMet"),
+    }
+    let res = client.try_propose_transfer(&exporter, &id, &importer, &None);
+    assert!(res.is_ok());
+    let res = client.try_accept_transfer(&importer, &id);
+    match res {
+        Err(Ok(e)) => assert_eq!(e, Error::RequirementsNotMet),
+        _ => panic!("expected RequirementsNotMet"),
+    }
+
+    client.add_checkpoint(&exporter, &id, &CheckpointKind::CustomsOut, &port, &note);
+    client.transfer_product(&exporter, &id, &importer);
+    assert_eq!(client.get_product(&id).unwrap().owner, importer);
+
+    // Clearing the list lifts the requirement
+    client.set_transfer_requirements(&importer, &id, &required);
+    client.set_transfer_requirements(&importer, &id, &Vec::new(&env));
+    assert_eq!(client.get_transfer_requirements(&id).len(), 0);
+    client.transfer_product(&importer, &id, &exporter);
+}
+
+#[test]
+fn test_transfer_all_in_chunks() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register_contract(None, ChainLogisticsContract);
+    let client = ChainLogisticsContractClient::new(&env, &contract_id);
+
+    let old_key = Address::generate(&env);
+    let new_key = Address::generate(&env);
+    let buyer = Address::generate(&env);
+    let origin = String::from_str(&env, "Kenya");
+    let meta = String::from_str(&env, "Tea");
+    let mut ids = Vec::new(&env);
+    for _ in 0..5 {
+        ids.push_back(client.register_product(&old_key, &origin, &meta));
+    }
+    // One product has an open proposal and one is with a carrier
+    client.propose_transfer(&old_key, &ids.get(1).unwrap(), &buyer, &None);
+    client.set_custodian(&old_key, &ids.get(3).unwrap(), &Address::generate(&env));
+
+    let first = client.transfer_all(&old_key, &new_key, &0, &3);
+    assert_eq!(first.moved, 2);
+    assert_eq!(first.skipped, 1);
+    assert_eq!(first.next_start, 3);
+
+    let second = client.transfer_all(&old_key, &new_key, &first.next_start, &3);
+    assert_eq!(second.moved, 1);
+    assert_eq!(second.skipped, 1);
+    assert_eq!(second.next_start, 5);
+
+    let done = client.transfer_all(&old_key, &new_key, &second.next_start, &3);
+    assert_eq!(done.moved, 0);
+    assert_eq!(done.skipped, 0);
+
+    assert_eq!(client.get_products_by_owner(&new_key, &0, &10).len(), 3);
+    let left = client.get_products_by_owner(&old_key, &0, &10);
+    assert_eq!(left.len(), 2);
+    assert_eq!(client.get_product(&ids.get(1).unwrap()).unwrap().owner, old_key);
+
+    let res = client.try_transfer_all(&old_key, &new_key, &0, &0);
+    match res {
+        Err(Ok(e)) => assert_eq!(e, Error::BatchTooLarge),
+        _ => panic!("expected BatchTooLarge"),
+    }
+}
+
+#[test]
+fn test_actor_roles() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register_contract(None, ChainLogisticsContract);
+    let client = ChainLogisticsContractClient::new(&env, &contract_id);
+
+    let owner = Address::generate(&env);
+    let auditor = Address::generate(&env);
+    let carrier = Address::generate(&env);
+    let buyer = Address::generate(&env);
+    let id = client.register_product(&owner, &String::from_str(&env, "Chile"), &String::from_str(&env, "Copper"));
+    let depot = String::from_str(&env, "Antofagasta");
+    let note = String::from_str(&env, "");
+
+    client.add_authorized_actor_with_role(&owner, &id, &auditor, &Role::Viewer);
+    client.add_authorized_actor_with_role(&owner, &id, &carrier, &Role::Updater);
+    assert_eq!(client.get_role(&id, &auditor), Some(Role::Viewer));
+    assert_eq!(client.get_role(&id, &buyer), None);
+    assert!(client.is_authorized(&id, &auditor));
+
+    // Higher roles include the lower ones
+    assert!(client.has_role(&id, &carrier, &Role::Viewer));
+    assert!(client.has_role(&id, &carrier, &Role::Updater));
+    assert!(!client.has_role(&id, &carrier, &Role::Transferrer));
+    assert!(!client.has_role(&id, &auditor, &Role::Updater));
+
+    let res = client.try_add_checkpoint(&auditor, &id, &CheckpointKind::Pickup, &depot, &note);
+    match res {
+        Err(Ok(e)) => assert_eq!(e, Error::Unauthorized),
+        _ => panic!("expected Unauthorized"),
+    }
+    client.add_checkpoint(&carrier, &id, &CheckpointKind::Pickup, &depot, &note);
+
+    let res = client.try_transfer_from(&carrier, &id, &buyer);
+    match res {
+        Err(Ok(e)) => assert_eq!(e, Error::Unauthorized),
+        _ => panic!("expected Unauthorized"),
+    }
+
+    // A new role replaces the old one, in either direction
+    client.add_authorized_actor_with_role(&owner, &id, &carrier, &Role::Transferrer);
+    assert_eq!(client.get_role(&id, &carrier), Some(Role::Transferrer));
+    client.add_authorized_actor_with_role(&owner, &id, &auditor, &Role::Viewer);
+    client.add_authorized_actor_with_role(&owner, &id, &carrier, &Role::Viewer);
+    assert!(!client.has_role(&id, &carrier, &Role::Updater));
+    client.add_authorized_actor_with_role(&owner, &id, &carrier, &Role::Transferrer);
+    client.transfer_from(&carrier, &id, &buyer);
+    assert_eq!(client.get_product(&id).unwrap().owner, buyer);
+
+    client.remove_authorized_actor(&buyer, &id, &carrier);
+    assert_eq!(client.get_role(&id, &carrier), None);
+}
+
+#[test]
+fn test_expiring_authorization() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.ledger().with_mut(|li| li.timestamp = 1_000);
+    let contract_id = env.register_contract(None, ChainLogisticsContract);
+    let client = ChainLogisticsContractClient::new(&env, &contract_id);
+
+    let owner = Address::generate(&env);
+    let carrier = Address::generate(&env);
+    let warehouse = Address::generate(&env);
+    let id = client.register_product(&owner, &String::from_str(&env, "Peru"), &String::from_str(&env, "Quinoa"));
+
+    let res = client.try_add_authorized_actor_until(&owner, &id, &carrier, &999);
+    match res {
+        Err(Ok(e)) => assert_eq!(e, Error::InvalidTimestamp),
+        _ => panic!("expected InvalidTimestamp"),
+    }
+
+    client.add_authorized_actor_until(&owner, &id, &carrier, &2_000);
+    client.add_authorized_actor(&owner, &id, &warehouse);
+
+    // Valid through the expiry second itself
+    env.ledger().with_mut(|li| li.timestamp = 2_000);
+    assert!(client.is_authorized(&id, &carrier));
+    env.ledger().with_mut(|li| li.timestamp = 2_001);
+    assert!(!client.is_authorized(&id, &carrier));
+    assert_eq!(client.get_role(&id, &carrier), None);
+    let res = client.try_add_note(&carrier, &id, &String::from_str(&env, "late"));
+    match res {
+        Err(Ok(e)) => assert_eq!(e, Error::Unauthorized),
+        _ => panic!("expected Unauthorized"),
+    }
+
+    // Permanent grants are unaffected
+    env.ledger().with_mut(|li| li.timestamp = 1_000_000);
+    assert!(client.is_authorized(&id, &warehouse));
+
+    assert_eq!(client.purge_expired_auth(&id), 1);
+    assert_eq!(client.purge_expired_auth(&id), 0);
+    assert!(client.is_authorized(&id, &warehouse));
+}
+
+#[test]
+fn test_revoke_all_actors() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register_contract(None, ChainLogisticsContract);
+    let client = ChainLogisticsContractClient::new(&env, &contract_id);
+
+    let owner = Address::generate(&env);
+    let id = client.register_product(&owner, &String::from_str(&env, "Norway"), &String::from_str(&env, "Salmon"));
+    let mut actors = Vec::new(&env);
+    for i in 0..12 {
+        let actor = Address::generate(&env);
+        if i % 2 == 0 {
+            client.add_authorized_actor(&owner, &id, &actor);
+        } else {
+            client.add_authorized_actor_with_role(&owner, &id, &actor, &Role::Viewer);
+        }
+        actors.push_back(actor);
+    }
+
+    let res = client.try_revoke_all_actors(&actors.get(0).unwrap(), &id);
+    match res {
+        Err(Ok(e)) => assert_eq!(e, Error::Unauthorized),
+        _ => panic!("expected Unauthorized"),
+    }
+
+    assert_eq!(client.revoke_all_actors(&owner, &id), 12);
+    for actor in actors.iter() {
+        assert!(!client.is_authorized(&id, &actor));
+        assert_eq!(client.get_role(&id, &actor), None);
+    }
+    assert!(client.is_authorized(&id, &owner));
+    assert_eq!(client.revoke_all_actors(&owner, &id), 0);
+}
+
+#[test]
+fn test_global_actors() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register_contract(None, ChainLogisticsContract);
+    let client = ChainLogisticsContractClient::new(&env, &contract_id);
+
+    let maker = Address::generate(&env);
+    let qa = Address::generate(&env);
+    let retailer = Address::generate(&env);
+    let retailer_qa = Address::generate(&env);
+    let origin = String::from_str(&env, "Germany");
+    let meta = String::from_str(&env, "Drill");
+    let first = client.register_product(&maker, &origin, &meta);
+    let second = client.register_product(&maker, &origin, &meta);
+
+    client.add_global_actor(&maker, &qa);
+    client.add_global_actor(&retailer, &retailer_qa);
+    assert!(client.is_authorized(&first, &qa));
+    assert!(client.is_authorized(&second, &qa));
+    assert!(client.has_role(&first, &qa, &Role::Updater));
+    client.add_note(&qa, &first, &String::from_str(&env, "torque tested"));
+
+    // Access follows the product to its new owner's team
+    client.transfer_product(&maker, &second, &retailer);
+    assert!(!client.is_authorized(&second, &qa));
+    assert!(client.is_authorized(&second, &retailer_qa));
+    assert!(!client.is_authorized(&first, &retailer_qa));
+
+    // Products registered later are covered too
+    let third = client.register_product(&maker, &origin, &meta);
+    assert!(client.is_authorized(&third, &qa));
+
+    let listed = client.get_global_actors(&maker, &0, &10);
+    assert_eq!(listed.len(), 1);
+    assert_eq!(listed.get(0).unwrap(), qa);
+    assert_eq!(client.get_global_actors(&maker, &1, &10).len(), 0);
+
+    client.remove_global_actor(&maker, &qa);
+    assert!(!client.is_authorized(&first, &qa));
+    assert!(!client.is_authorized(&third, &qa));
+}
+
+#[test]
+fn test_products_for_actor() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register_contract(None, ChainLogisticsContract);
+    let client = ChainLogisticsContractClient::new(&env, &contract_id);
+
+    let owner = Address::generate(&env);
+    let carrier = Address::generate(&env);
+    let origin = String::from_str(&env, "Vietnam");
+    let meta = String::from_str(&env, "Coffee");
+    let a = client.register_product(&owner, &origin, &meta);
+    let b = client.register_product(&owner, &origin, &meta);
+    let c = client.register_product(&owner, &origin, &meta);
+    client.register_product(&owner, &origin, &meta);
+
+    client.add_authorized_actor(&owner, &a, &carrier);
+    client.add_authorized_actor_with_role(&owner, &b, &carrier, &Role::Viewer);
+    client.add_authorized_actor(&owner, &c, &carrier);
+    // Changing the role does not add a second slot
+    client.add_authorized_actor_with_role(&owner, &b, &carrier, &Role::Updater);
+
+    let products = client.get_products_for_actor(&carrier, &0, &10);
+    assert_eq!(products.len(), 3);
+    assert_eq!(products.get(0).unwrap().id, a);
+    assert_eq!(products.get(2).unwrap().id, c);
+
+    client.remove_authorized_actor(&owner, &b, &carrier);
+    let products = client.get_products_for_actor(&carrier, &0, &10);
+    assert_eq!(products.len(), 2);
+    assert_eq!(products.get(1).unwrap().id, c);
+    assert_eq!(client.get_products_for_actor(&carrier, &2, &1).get(0).unwrap().id, c);
+
+    // Granting again appends a fresh slot
+    client.add_authorized_actor(&owner, &b, &carrier);
+    let products = client.get_products_for_actor(&carrier, &0, &10);
+    assert_eq!(products.len(), 3);
+    assert_eq!(products.get(2).unwrap().id, b);
+
+    client.revoke_all_actors(&owner, &a);
+    assert_eq!(client.get_products_for_actor(&carrier, &0, &10).len(), 2);
+}
+
+#[test]
+fn test_actor_invitations() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register_contract(None, ChainLogisticsContract);
+    let client = ChainLogisticsContractClient::new(&env, &contract_id);
+
+    let owner = Address::generate(&env);
+    let big_carrier = Address::generate(&env);
+    let courier = Address::generate(&env);
+    let id = client.register_product(&owner, &String::from_str(&env, "Italy"), &String::from_str(&env, "Handbag"));
+
+    // Opting into invitations closes the unilateral path
+    client.set_invitations_required(&owner, &true);
+    let res = client.try_add_authorized_actor(&owner, &id, &big_carrier);
+    match res {
+        Err(Ok(e)) => assert_eq!(e, Error::Unauthorized),
+        _ => panic!("expected Unauthorized"),
+    }
+
+    client.invite_actor(&owner, &id, &big_carrier, &Role::Updater);
+    assert_eq!(client.get_invitation(&id, &big_carrier), Some(Role::Updater));
+    assert!(!client.is_authorized(&id, &big_carrier));
+
+    client.decline_invitation(&big_carrier, &id);
+    assert_eq!(client.get_invitation(&id, &big_carrier), None);
+    assert!(!client.is_authorized(&id, &big_carrier));
+    let res = client.try_accept_invitation(&big_carrier, &id);
+    match res {
+        Err(Ok(e)) => assert_eq!(e, Error::InvalidInput),
+        _ => panic!("expected InvalidInput"),
+    }
+
+    client.invite_actor(&owner, &id, &courier, &Role::Viewer);
+    client.accept_invitation(&courier, &id);
+    assert_eq!(client.get_role(&id, &courier), Some(Role::Viewer));
+    assert_eq!(client.get_invitation(&id, &courier), None);
+
+    client.invite_actor(&owner, &id, &big_carrier, &Role::Transferrer);
+    client.cancel_invitation(&owner, &id, &big_carrier);
+    let res = client.try_accept_invitation(&big_carrier, &id);
+    assert!(res.is_err());
+    assert!(!client.is_authorized(&id, &big_carrier));
+}
+
+#[test]
+fn test_actor_cap() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register_contract(None, ChainLogisticsContract);
+    let client = ChainLogisticsContractClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    let owner = Address::generate(&env);
+    client.initialize(&admin, &String::from_str(&env, "ChainLogistics"));
+    assert_eq!(client.get_max_actors(), 32);
+    client.set_max_actors(&admin, &3);
+
+    let id = client.register_product(&owner, &String::from_str(&env, "Japan"), &String::from_str(&env, "Camera"));
+    let first = Address::generate(&env);
+    let second = Address::generate(&env);
+    let third = Address::generate(&env);
+    let fourth = Address::generate(&env);
+    client.add_authorized_actor(&owner, &id, &first);
+    client.add_authorized_actor(&owner, &id, &second);
+    client.add_authorized_actor_with_role(&owner, &id, &third, &Role::Viewer);
+
+    // At the cap: new actors are refused, existing ones can change role
+    let res = client.try_add_authorized_actor(&owner, &id, &fourth);
+    match res {
+        Err(Ok(e)) => assert_eq!(e, Error::TooManyActors),
+        _ => panic!("expected TooManyActors"),
+    }
+    client.invite_actor(&owner, &id, &fourth, &Role::Viewer);
+    let res = client.try_accept_invitation(&fourth, &id);
+    match res {
+        Err(Ok(e)) => assert_eq!(e, Error::TooManyActors),
+        _ => panic!("expected TooManyActors"),
+    }
+    client.add_authorized_actor_with_role(&owner, &id, &third, &Role::Updater);
+
+    client.remove_authorized_actor(&owner, &id, &second);
+    client.accept_invitation(&fourth, &id);
+    assert!(client.is_authorized(&id, &fourth));
+
+    let res = client.try_set_max_actors(&owner, &10);
+    assert!(res.is_err());
+}
+
+#[test]
+fn test_operators() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register_contract(None, ChainLogisticsContract);
+    let client = ChainLogisticsContractClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    let owner = Address::generate(&env);
+    let customs = Address::generate(&env);
+    client.initialize(&admin, &String::from_str(&env, "ChainLogistics"));
+    let id = client.register_product(&owner, &String::from_str(&env, "Turkey"), &String::from_str(&env, "Carpet"));
+    let border = String::from_str(&env, "Kapikule");
+    let note = String::from_str(&env, "");
+    let checkpoint = Symbol::new(&env, "CHECKPOINT");
+
+    let res = client.try_add_operator(&admin, &customs, &Symbol::new(&env, "TRANSFER"));
+    match res {
+        Err(Ok(e)) => assert_eq!(e, Error::InvalidInput),
+        _ => panic!("expected InvalidInput"),
+    }
+
+    client.add_operator(&admin, &customs, &checkpoint);
+    assert!(client.is_operator(&customs, &checkpoint));
+    assert!(!client.is_operator(&customs, &Symbol::new(&env, "INSPECT")));
+    assert_eq!(client.get_operators(&0, &10).len(), 1);
+
+    // Logs checkpoints on a stranger's product without a grant
+    client.add_checkpoint(&customs, &id, &CheckpointKind::CustomsIn, &border, &note);
+    assert!(!client.is_authorized(&id, &customs));
+    let res = client.try_add_note(&customs, &id, &String::from_str(&env, "sealed"));
+    match res {
+        Err(Ok(e)) => assert_eq!(e, Error::Unauthorized),
+        _ => panic!("expected Unauthorized"),
+    }
+    let res = client.try_transfer_product(&customs, &id, &customs);
+    assert!(res.is_err());
+    assert_eq!(client.get_product(&id).unwrap().owner, owner);
+
+    client.remove_operator(&admin, &customs, &checkpoint);
+    assert_eq!(client.get_operators(&0, &10).len(), 0);
+    let res = client.try_add_checkpoint(&customs, &id, &CheckpointKind::CustomsOut, &border, &note);
+    match res {
+        Err(Ok(e)) => assert_eq!(e, Error::Unauthorized),
+        _ => panic!("expected Unauthorized"),
+    }
+}
+
+#[test]
+fn test_activity_log() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register_contract(None, ChainLogisticsContract);
+    let client = ChainLogisticsContractClient::new(&env, &contract_id);
+
+    let owner = Address::generate(&env);
+    let carrier = Address::generate(&env);
+    let lab = Address::generate(&env);
+    let id = client.register_product(&owner, &String::from_str(&env, "India"), &String::from_str(&env, "Vaccine"));
+    let hub = String::from_str(&env, "Pune");
+    let note = String::from_str(&env, "");
+    client.add_authorized_actor(&owner, &id, &carrier);
+    client.add_authorized_actor(&owner, &id, &lab);
+
+    // Owner calls are not logged
+    client.add_checkpoint(&owner, &id, &CheckpointKind::Pickup, &hub, &note);
+    env.ledger().with_mut(|li| li.timestamp = 100);
+    client.add_checkpoint(&carrier, &id, &CheckpointKind::Departure, &hub, &note);
+    env.ledger().with_mut(|li| li.timestamp = 200);
+    client.record_temperature(&lab, &id, &450, &200);
+    client.add_note(&carrier, &id, &String::from_str(&env, "seal intact"));
+
+    let log = client.get_activity_log(&id, &0, &10);
+    assert_eq!(log.len(), 3);
+    let first = log.get(0).unwrap();
+    assert_eq!(first.seq, 1);
+    assert_eq!(first.actor, carrier);
+    assert_eq!(first.action, symbol_short!("CHECKPT"));
+    assert_eq!(first.timestamp, 100);
+    assert_eq!(log.get(1).unwrap().actor, lab);
+    assert_eq!(log.get(1).unwrap().action, symbol_short!("TEMP"));
+
+    let page = client.get_activity_log(&id, &2, &5);
+    assert_eq!(page.len(), 1);
+    assert_eq!(page.get(0).unwrap().action, symbol_short!("NOTE"));
+    assert_eq!(client.get_activity_overflow(&id), 0);
+}
+
+#[test]
+fn test_clear_actors_on_transfer() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register_contract(None, ChainLogisticsContract);
+    let client = ChainLogisticsContractClient::new(&env, &contract_id);
+
+    let seller = Address::generate(&env);
+    let buyer = Address::generate(&env);
+    let carrier = Address::generate(&env);
+    let origin = String::from_str(&env, "France");
+    let meta = String::from_str(&env, "Wine");
+
+    // Default: grants survive
+    let kept = client.register_product(&seller, &origin, &meta);
+    client.add_authorized_actor(&seller, &kept, &carrier);
+    client.transfer_product(&seller, &kept, &buyer);
+    assert!(client.is_authorized(&kept, &carrier));
+
+    // Per-call variant
+    let cleared = client.register_product(&seller, &origin, &meta);
+    client.add_authorized_actor(&seller, &cleared, &carrier);
+    client.transfer_and_clear_actors(&seller, &cleared, &buyer);
+    assert!(!client.is_authorized(&cleared, &carrier));
+    assert_eq!(client.get_product(&cleared).unwrap().owner, buyer);
+
+    // Preference applies when a two-step transfer completes
+    let proposed = client.register_product(&seller, &origin, &meta);
+    client.add_authorized_actor(&seller, &proposed, &carrier);
+    client.set_clear_actors_on_transfer(&seller, &proposed, &true);
+    client.propose_transfer(&seller, &proposed, &buyer, &None);
+    assert!(client.is_authorized(&proposed, &carrier));
+    client.accept_transfer(&buyer, &proposed);
+    assert!(!client.is_authorized(&proposed, &carrier));
+    assert_eq!(client.get_products_for_actor(&carrier, &0, &10).len(), 1);
+}
+
+#[test]
+fn test_co_ownership() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register_contract(None, ChainLogisticsContract);
+    let client = ChainLogisticsContractClient::new(&env, &contract_id);
+
+    let lead = Address::generate(&env);
+    let partner = Address::generate(&env);
+    let investor = Address::generate(&env);
+    let buyer = Address::generate(&env);
+    let id = client.register_product(&lead, &String::from_str(&env, "Brazil"), &String::from_str(&env, "Turbine"));
+
+    client.add_co_owner(&lead, &id, &partner);
+    client.add_co_owner(&lead, &id, &investor);
+    assert_eq!(client.get_co_owners(&id).len(), 2);
+    assert_eq!(client.get_co_owner_threshold(&id), 3);
+    assert!(client.is_authorized(&id, &partner));
+    client.add_note(&partner, &id, &String::from_str(&env, "inspected"));
+
+    // The primary alone can no longer transfer
+    let res = client.try_transfer_product(&lead, &id, &buyer);
+    match res {
+        Err(Ok(e)) => assert_eq!(e, Error::Unauthorized),
+        _ => panic!("expected Unauthorized"),
+    }
+
+    client.set_co_owner_threshold(&lead, &id, &2);
+    let mut signers = Vec::new(&env);
+    signers.push_back(partner.clone());
+    let res = client.try_transfer_jointly(&signers, &id, &buyer);
+    match res {
+        Err(Ok(e)) => assert_eq!(e, Error::Unauthorized),
+        _ => panic!("expected Unauthorized"),
+    }
+    let mut outsiders = signers.clone();
+    outsiders.push_back(buyer.clone());
+    let res = client.try_transfer_jointly(&outsiders, &id, &buyer);
+    match res {
+        Err(Ok(e)) => assert_eq!(e, Error::Unauthorized),
+        _ => panic!("expected Unauthorized"),
+    }
+
+    // Removing a co-owner lowers a threshold that no longer fits
+    client.remove_co_owner(&lead, &id, &investor);
+    assert!(!client.is_authorized(&id, &investor));
+    client.remove_co_owner(&lead, &id, &partner);
+    assert_eq!(client.get_co_owner_threshold(&id), 1);
+    client.add_co_owner(&lead, &id, &partner);
+    client.set_co_owner_threshold(&lead, &id, &2);
+
+    signers.push_back(lead.clone());
+    client.transfer_jointly(&signers, &id, &buyer);
+    assert_eq!(client.get_product(&id).unwrap().owner, buyer);
+    assert_eq!(client.get_co_owners(&id).len(), 0);
+    assert!(!client.is_authorized(&id, &partner));
+}
+
+#[test]
+fn test_can_matches_enforcement() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+    let contract_id = env.register_contract(None, ChainLogisticsContract);
+    let client = ChainLogisticsContractClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    let owner = Address::generate(&env);
+    let updater = Address::generate(&env);
+    let viewer = Address::generate(&env);
+    let transferrer = Address::generate(&env);
+    let customs = Address::generate(&env);
+    let custodian = Address::generate(&env);
+    let stranger = Address::generate(&env);
+    let buyer = Address::generate(&env);
+    client.initialize(&admin, &String::from_str(&env, "ChainLogistics"));
+    client.add_operator(&admin, &customs, &Symbol::new(&env, "CHECKPOINT"));
+
+    let id = client.register_product(&owner, &String::from_str(&env, "Spain"), &String::from_str(&env, "Olive oil"));
+    client.add_authorized_actor_with_role(&owner, &id, &updater, &Role::Updater);
+    client.add_authorized_actor_with_role(&owner, &id, &viewer, &Role::Viewer);
+    client.add_authorized_actor_with_role(&owner, &id, &transferrer, &Role::Transferrer);
+    client.set_custodian(&owner, &id, &custodian);
+
+    let place = String::from_str(&env, "Valencia");
+    let note = String::from_str(&env, "");
+    let transfer = Symbol::new(&env, "TRANSFER");
+    let checkpoint = Symbol::new(&env, "CHECKPOINT");
+    let inspect = Symbol::new(&env, "INSPECT");
+    let update_meta = Symbol::new(&env, "UPDATE_META");
+
+    let personas = [&owner, &updater, &viewer, &transferrer, &customs, &custodian, &stranger];
+    for actor in personas {
+        let ok = client.try_add_checkpoint(actor, &id, &CheckpointKind::Arrival, &place, &note).is_ok();
+        assert_eq!(client.can(actor, &id, &checkpoint), ok);
+        let ok = client.try_add_note(actor, &id, &String::from_str(&env, "seen")).is_ok();
+        assert_eq!(client.can(actor, &id, &inspect), ok);
+        let ok = client.try_add_tag(actor, &id, &symbol_short!("organic")).is_ok();
+        assert_eq!(client.can(actor, &id, &update_meta), ok);
+    }
+    assert!(client.can(&customs, &id, &checkpoint));
+    assert!(!client.can(&customs, &id, &inspect));
+    assert!(client.can(&custodian, &id, &inspect));
+    assert!(!client.can(&viewer, &id, &checkpoint));
+
+    // In custody nobody can transfer
+    for actor in personas {
+        assert!(!client.can(actor, &id, &transfer));
+        assert!(client.try_transfer_from(actor, &id, &buyer).is_err());
+    }
+    assert!(client.try_transfer_product(&owner, &id, &buyer).is_err());
+
+    client.release_custody(&custodian, &id);
+    for actor in [&updater, &viewer, &customs, &custodian, &stranger] {
+        assert!(!client.can(actor, &id, &transfer));
+        assert!(client.try_transfer_from(actor, &id, &buyer).is_err());
+    }
+    assert!(client.can(&owner, &id, &transfer));
+    assert!(client.can(&transferrer, &id, &transfer));
+    client.transfer_from(&transferrer, &id, &buyer);
+
+    assert!(!client.can(&owner, &id, &update_meta));
+    assert!(!client.can(&buyer, &id, &Symbol::new(&env, "DANCE")));
+    assert!(!client.can(&buyer, &999, &transfer));
+}
+
+#[test]
+fn test_emergency_revoke() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register_contract(None, ChainLogisticsContract);
+    let client = ChainLogisticsContractClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    let leaked = Address::generate(&env);
+    let other = Address::generate(&env);
+    client.initialize(&admin, &String::from_str(&env, "ChainLogistics"));
+    client.add_operator(&admin, &leaked, &Symbol::new(&env, "INSPECT"));
+
+    let origin = String::from_str(&env, "Ghana");
+    let meta = String::from_str(&env, "Cocoa");
+    let mut ids = Vec::new(&env);
+    for _ in 0..5 {
+        // Products of different owners
+        let owner = Address::generate(&env);
+        let id = client.register_product(&owner, &origin, &meta);
+        client.add_authorized_actor(&owner, &id, &leaked);
+        client.add_authorized_actor(&owner, &id, &other);
+        ids.push_back(id);
+    }
+
+    let res = client.try_emergency_revoke(&other, &leaked, &0, &2);
+    match res {
+        Err(Ok(e)) => assert_eq!(e, Error::Unauthorized),
+        _ => panic!("expected Unauthorized"),
+    }
+
+    assert_eq!(client.emergency_revoke(&admin, &leaked, &0, &2), 2);
+    assert!(!client.is_operator(&leaked, &Symbol::new(&env, "INSPECT")));
+    assert!(!client.is_authorized(&ids.get(0).unwrap(), &leaked));
+    assert!(client.is_authorized(&ids.get(2).unwrap(), &leaked));
+
+    // One auth revocation event per product, nothing else
+    let seen = env.events().all().len();
+    assert_eq!(client.emergency_revoke(&admin, &leaked, &2, &2), 2);
+    let events = env.events().all().slice(seen..);
+    assert_eq!(events.len(), 2);
+    for (i, (_, topics, _)) in events.iter().enumerate() {
+        let id = ids.get(i as u32 + 2).unwrap();
+        assert_eq!(topics, (Symbol::new(&env, events::PRODUCT), Symbol::new(&env, events::AUTH), id).into_val(&env));
+    }
+    assert_eq!(client.emergency_revoke(&admin, &leaked, &4, &2), 1);
+    assert_eq!(client.emergency_revoke(&admin, &leaked, &0, &10), 0);
+
+    for id in ids.iter() {
+        assert!(!client.is_authorized(&id, &leaked));
+        assert!(client.is_authorized(&id, &other));
+    }
+    assert_eq!(client.get_products_for_actor(&leaked, &0, &10).len(), 0);
+}
+
+#[test]
+fn test_admin_initialization() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register_contract(None, ChainLogisticsContract);
+    let client = ChainLogisticsContractClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    assert_eq!(client.get_admin(), None);
+    let res = client.try_add_certifier(&admin, &Address::generate(&env));
+    match res {
+        Err(Ok(e)) => assert_eq!(e, Error::NotInitialized),
+        _ => panic!("expected NotInitialized"),
+    }
+
+    client.initialize(&admin, &String::from_str(&env, "ChainLogistics"));
+    assert_eq!(client.get_admin(), Some(admin.clone()));
+    let res = client.try_initialize(&Address::generate(&env), &String::from_str(&env, "ChainLogistics"));
+    match res {
+        Err(Ok(e)) => assert_eq!(e, Error::AlreadyInitialized),
+        _ => panic!("expected AlreadyInitialized"),
+    }
+    assert_eq!(client.get_admin(), Some(admin));
+}
+
+#[test]
+fn test_admin_handover() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register_contract(None, ChainLogisticsContract);
+    let client = ChainLogisticsContractClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    let multisig = Address::generate(&env);
+    let stranger = Address::generate(&env);
+    client.initialize(&admin, &String::from_str(&env, "ChainLogistics"));
+
+    let res = client.try_propose_admin(&stranger, &stranger);
+    match res {
+        Err(Ok(e)) => assert_eq!(e, Error::Unauthorized),
+        _ => panic!("expected Unauthorized"),
+    }
+
+    client.propose_admin(&admin, &stranger);
+    client.cancel_admin_proposal(&admin);
+    assert_eq!(client.get_pending_admin(), None);
+    let res = client.try_accept_admin(&stranger);
+    match res {
+        Err(Ok(e)) => assert_eq!(e, Error::InvalidInput),
+        _ => panic!("expected InvalidInput"),
+    }
+
+    client.propose_admin(&admin, &multisig);
+    assert_eq!(client.get_pending_admin(), Some(multisig.clone()));
+    let res = client.try_accept_admin(&stranger);
+    match res {
+        Err(Ok(e)) => assert_eq!(e, Error::Unauthorized),
+        _ => panic!("expected Unauthorized"),
+    }
+    // The old admin keeps its rights until acceptance
+    client.add_certifier(&admin, &stranger);
+
+    client.accept_admin(&multisig);
+    assert_eq!(client.get_admin(), Some(multisig.clone()));
+    assert_eq!(client.get_pending_admin(), None);
+    let res = client.try_add_certifier(&admin, &stranger);
+    match res {
+        Err(Ok(e)) => assert_eq!(e, Error::Unauthorized),
+        _ => panic!("expected Unauthorized"),
+    }
+    client.remove_certifier(&multisig, &stranger);
+}
+
+#[test]
+fn test_pause_and_unpause() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register_contract(None, ChainLogisticsContract);
+    let client = ChainLogisticsContractClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    let owner = Address::generate(&env);
+    let buyer = Address::generate(&env);
+    let carrier = Address::generate(&env);
+    client.initialize(&admin, &String::from_str(&env, "ChainLogistics"));
+    let origin = String::from_str(&env, "Egypt");
+    let meta = String::from_str(&env, "Cotton");
+    let id = client.register_product(&owner, &origin, &meta);
+
+    let res = client.try_pause(&owner);
+    assert!(res.is_err());
+    client.pause(&admin);
+    assert!(client.is_paused());
+
+    let res = client.try_register_product(&owner, &origin, &meta);
+    match res {
+        Err(Ok(e)) => assert_eq!(e, Error::ContractPaused),
+        _ => panic!("expected ContractPaused"),
+    }
+    let res = client.try_transfer_product(&owner, &id, &buyer);
+    match res {
+        Err(Ok(e)) => assert_eq!(e, Error::ContractPaused),
+        _ => panic!("expected ContractPaused"),
+    }
+    let res = client.try_add_authorized_actor(&owner, &id, &carrier);
+    match res {
+        Err(Ok(e)) => assert_eq!(e, Error::ContractPaused),
+        _ => panic!("expected ContractPaused"),
+    }
+    let res = client.try_add_checkpoint(&owner, &id, &CheckpointKind::Pickup, &origin, &meta);
+    match res {
+        Err(Ok(e)) => assert_eq!(e, Error::ContractPaused),
+        _ => panic!("expected ContractPaused"),
+    }
+    assert!(!client.can(&owner, &id, &Symbol::new(&env, "TRANSFER")));
+
+    // Views keep working
+    assert_eq!(client.get_product(&id).unwrap().owner, owner);
+    assert_eq!(client.get_products_by_owner(&owner, &0, &10).len(), 1);
+    assert_eq!(client.get_stats().total_products, 1);
+
+    client.unpause(&admin);
+    assert!(!client.is_paused());
+    client.register_product(&owner, &origin, &meta);
+    client.add_authorized_actor(&owner, &id, &carrier);
+    client.transfer_product(&owner, &id, &buyer);
+}
+
+#[test]
+fn test_config_limits() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register_contract(None, ChainLogisticsContract);
+    let client = ChainLogisticsContractClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    let owner = Address::generate(&env);
+    let origin = String::from_str(&env, "Morocco");
+    let long_meta = String::from_str(&env, "Argan oil, cold pressed, 250ml");
+
+    // Defaults apply before any configuration
+    let defaults = client.get_config();
+    assert_eq!(defaults.max_metadata_len, 1024);
+    assert_eq!(defaults.max_page_limit, 100);
+    assert_eq!(defaults.max_actors, 32);
+    assert_eq!(defaults.max_batch_size, 50);
+    client.register_product(&owner, &origin, &long_meta);
+
+    client.initialize(&admin, &String::from_str(&env, "ChainLogistics"));
+    let mut tight = defaults.clone();
+    tight.max_metadata_len = 10;
+    tight.max_page_limit = 2;
+    client.set_config(&admin, &tight);
+    assert_eq!(client.get_config(), tight);
+
+    let res = client.try_register_product(&owner, &origin, &long_meta);
+    match res {
+        Err(Ok(e)) => assert_eq!(e, Error::MetadataTooLong),
+        _ => panic!("expected MetadataTooLong"),
+    }
+    client.register_product(&owner, &origin, &String::from_str(&env, "Argan"));
+    client.register_product(&owner, &origin, &String::from_str(&env, "Saffron"));
+    assert_eq!(client.get_products_by_owner(&owner, &0, &50).len(), 2);
+
+    let mut absurd = tight.clone();
+    absurd.max_page_limit = 0;
+    let res = client.try_set_config(&admin, &absurd);
+    match res {
+        Err(Ok(e)) => assert_eq!(e, Error::InvalidInput),
+        _ => panic!("expected InvalidInput"),
+    }
+    let res = client.try_set_config(&owner, &tight);
+    assert!(res.is_err());
+}
+
+#[test]
+fn test_registration_fee() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register_contract(None, ChainLogisticsContract);
+    let client = ChainLogisticsContractClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    let treasury = Address::generate(&env);
+    let maker = Address::generate(&env);
+    let broke = Address::generate(&env);
+    client.initialize(&admin, &String::from_str(&env, "ChainLogistics"));
+    let token_id = env.register_stellar_asset_contract(admin.clone());
+    let token = soroban_sdk::token::Client::new(&env, &token_id);
+    soroban_sdk::token::StellarAssetClient::new(&env, &token_id).mint(&maker, &1_000);
+
+    let origin = String::from_str(&env, "Sweden");
+    let meta = String::from_str(&env, "Timber");
+
+    // Free by default
+    assert_eq!(client.get_fee(), (None, 0));
+    client.register_product(&broke, &origin, &meta);
+
+    client.set_fee(&admin, &token_id, &300, &treasury);
+    assert_eq!(client.get_fee(), (Some(token_id.clone()), 300));
+    client.register_product(&maker, &origin, &meta);
+    assert_eq!(token.balance(&maker), 700);
+    assert_eq!(token.balance(&treasury), 300);
+
+    // A failed payment aborts the registration
+    let res = client.try_register_product(&broke, &origin, &meta);
+    match res {
+        Err(Ok(e)) => assert_eq!(e, Error::FeePaymentFailed),
+        _ => panic!("expected FeePaymentFailed"),
+    }
+    assert_eq!(client.get_stats().total_products, 2);
+
+    client.set_fee(&admin, &token_id, &0, &treasury);
+    assert_eq!(client.get_fee(), (None, 0));
+    client.register_product(&broke, &origin, &meta);
+    assert_eq!(token.balance(&maker), 700);
+}
+
+#[test]
+fn test_registrar_whitelist() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register_contract(None, ChainLogisticsContract);
+    let client = ChainLogisticsContractClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    let vetted = Address::generate(&env);
+    let other = Address::generate(&env);
+    let unknown = Address::generate(&env);
+    client.initialize(&admin, &String::from_str(&env, "ChainLogistics"));
+    let origin = String::from_str(&env, "Switzerland");
+    let meta = String::from_str(&env, "Watch");
+
+    client.add_registrar(&admin, &vetted);
+    client.add_registrar(&admin, &other);
+    client.add_registrar(&admin, &other);
+    assert_eq!(client.get_registrars(&0, &10).len(), 2);
+    assert_eq!(client.get_registrars(&1, &10).get(0).unwrap(), other);
+
+    // Open mode: anyone registers
+    client.register_product(&unknown, &origin, &meta);
+
+    client.set_registration_restricted(&admin, &true);
+    assert!(client.is_registration_restricted());
+    let res = client.try_register_product(&unknown, &origin, &meta);
+    match res {
+        Err(Ok(e)) => assert_eq!(e, Error::NotRegistrar),
+        _ => panic!("expected NotRegistrar"),
+    }
+    client.register_product(&vetted, &origin, &meta);
+
+    client.remove_registrar(&admin, &vetted);
+    assert!(!client.is_registrar(&vetted));
+    let res = client.try_register_product(&vetted, &origin, &meta);
+    match res {
+        Err(Ok(e)) => assert_eq!(e, Error::NotRegistrar),
+        _ => panic!("expected NotRegistrar"),
+    }
+
+    client.set_registration_restricted(&admin, &false);
+    client.register_product(&vetted, &origin, &meta);
+    assert_eq!(client.get_stats().total_products, 3);
+}
+
+#[test]
+fn test_blacklist() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register_contract(None, ChainLogisticsContract);
+    let client = ChainLogisticsContractClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    let seller = Address::generate(&env);
+    let sanctioned = Address::generate(&env);
+    client.initialize(&admin, &String::from_str(&env, "ChainLogistics"));
+    let origin = String::from_str(&env, "Canada");
+    let meta = String::from_str(&env, "Maple syrup");
+    let id = client.register_product(&seller, &origin, &meta);
+    let theirs = client.register_product(&sanctioned, &origin, &meta);
+
+    client.blacklist(&admin, &sanctioned);
+    assert!(client.is_blacklisted(&sanctioned));
+
+    let res = client.try_register_product(&sanctioned, &origin, &meta);
+    match res {
+        Err(Ok(e)) => assert_eq!(e, Error::AddressBlacklisted),
+        _ => panic!("expected AddressBlacklisted"),
+    }
+    let res = client.try_transfer_product(&seller, &id, &sanctioned);
+    match res {
+        Err(Ok(e)) => assert_eq!(e, Error::AddressBlacklisted),
+        _ => panic!("expected AddressBlacklisted"),
+    }
+    let res = client.try_add_authorized_actor(&seller, &id, &sanctioned);
+    match res {
+        Err(Ok(e)) => assert_eq!(e, Error::AddressBlacklisted),
+        _ => panic!("expected AddressBlacklisted"),
+    }
+
+    // Their products stay readable but frozen
+    assert_eq!(client.get_product(&theirs).unwrap().owner, sanctioned);
+    let res = client.try_transfer_product(&sanctioned, &theirs, &seller);
+    match res {
+        Err(Ok(e)) => assert_eq!(e, Error::AddressBlacklisted),
+        _ => panic!("expected AddressBlacklisted"),
+    }
+    let res = client.try_add_tag(&sanctioned, &theirs, &symbol_short!("sweet"));
+    match res {
+        Err(Ok(e)) => assert_eq!(e, Error::AddressBlacklisted),
+        _ => panic!("expected AddressBlacklisted"),
+    }
+
+    client.unblacklist(&admin, &sanctioned);
+    assert!(!client.is_blacklisted(&sanctioned));
+    client.transfer_product(&seller, &id, &sanctioned);
+    client.add_tag(&sanctioned, &theirs, &symbol_short!("sweet"));
+    client.register_product(&sanctioned, &origin, &meta);
+}
+
+#[test]
+fn test_upgrade_and_migrate() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register_contract(None, ChainLogisticsContract);
+    let client = ChainLogisticsContractClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    let stranger = Address::generate(&env);
+    assert_eq!(client.version(), 0);
+    client.initialize(&admin, &String::from_str(&env, "ChainLogistics"));
+    assert_eq!(client.version(), 1);
+
+    let hash = BytesN::from_array(&env, &[7; 32]);
+    let res = client.try_upgrade(&stranger, &hash);
+    match res {
+        Err(Ok(e)) => assert_eq!(e, Error::Unauthorized),
+        _ => panic!("expected Unauthorized"),
+    }
+
+    // Simulate a deployment from before versioning
+    env.as_contract(&contract_id, || {
+        env.storage().instance().remove(&storage::AdminKey::ContractVersion);
+    });
+    assert_eq!(client.version(), 0);
+    let res = client.try_migrate(&stranger);
+    match res {
+        Err(Ok(e)) => assert_eq!(e, Error::Unauthorized),
+        _ => panic!("expected Unauthorized"),
+    }
+    assert_eq!(client.version(), 0);
+
+    assert_eq!(client.migrate(&admin), 1);
+    assert_eq!(client.version(), 1);
+    // Already current
+    assert_eq!(client.migrate(&admin), 1);
+}
+
+/// Last ledger a persistent entry stays live, read from the ledger snapshot.
+fn live_until<K: IntoVal<Env, soroban_sdk::Val>>(env: &Env, key: &K) -> u32 {
+    use soroban_sdk::xdr::{LedgerEntryData, ScVal};
+    use soroban_sdk::TryFromVal;
+    let key = ScVal::try_from_val(env, &key.into_val(env)).unwrap();
+    for (_, (entry, live_until)) in env.to_ledger_snapshot().ledger_entries {
+        if let LedgerEntryData::ContractData(data) = &entry.data {
+            if data.key == key {
+                return live_until.unwrap();
+            }
+        }
+    }
+    panic!("entry not found");
+}
+
+#[test]
+fn test_extend_product_ttl() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register_contract(None, ChainLogisticsContract);
+    let client = ChainLogisticsContractClient::new(&env, &contract_id);
+
+    let owner = Address::generate(&env);
+    let actor = Address::generate(&env);
+    let origin = String::from_str(&env, "Kenya");
+    let kept = client.register_product(&owner, &origin, &String::from_str(&env, "Tea"));
+    let lapsed = client.register_product(&owner, &origin, &String::from_str(&env, "Coffee"));
+    client.add_authorized_actor(&owner, &kept, &actor);
+
+    let res = client.try_extend_product_ttl(&kept, &0);
+    match res {
+        Err(Ok(e)) => assert_eq!(e, Error::InvalidInput),
+        _ => panic!("expected InvalidInput"),
+    }
+    let res = client.try_extend_product_ttl(&99, &100_000);
+    match res {
+        Err(Ok(e)) => assert_eq!(e, Error::ProductNotFound),
+        _ => panic!("expected ProductNotFound"),
+    }
+
+    env.ledger().with_mut(|li| li.sequence_number = 1_000);
+    client.extend_product_ttl(&kept, &100_000);
+    assert_eq!(live_until(&env, &storage::DataKey::Product(kept)), 101_000);
+    assert_eq!(live_until(&env, &storage::DataKey::OwnerProductIndex(owner.clone(), 1)), 101_000);
+    assert_eq!(live_until(&env, &storage::DataKey::OriginProductIndex(origin.clone(), 1)), 101_000);
+    assert_eq!(live_until(&env, &storage::DataKey::Auth(kept, actor.clone())), 101_000);
+    assert_eq!(live_until(&env, &storage::AuthKey::Actors(kept)), 101_000);
+    // Untouched entries keep the default lifetime and would lapse first
+    assert!(live_until(&env, &storage::DataKey::Product(lapsed)) < 50_000);
+
+    env.ledger().with_mut(|li| li.sequence_number = 50_000);
+    assert_eq!(client.get_product(&kept).unwrap().id, kept);
+    assert!(client.is_authorized(&kept, &actor));
+}
+
+#[test]
+fn test_extend_ttl_in_bulk_and_on_read() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register_contract(None, ChainLogisticsContract);
+    let client = ChainLogisticsContractClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    let owner = Address::generate(&env);
+    let origin = String::from_str(&env, "Peru");
+    client.initialize(&admin, &String::from_str(&env, "ChainLogistics"));
+    let first = client.register_product(&owner, &origin, &String::from_str(&env, "Quinoa"));
+    let second = client.register_product(&owner, &origin, &String::from_str(&env, "Cacao"));
+    let third = client.register_product(&owner, &origin, &String::from_str(&env, "Maca"));
+    let default_live_until = live_until(&env, &storage::DataKey::Product(third));
+
+    assert_eq!(client.extend_owner_products_ttl(&owner, &0, &2), 2);
+    assert!(live_until(&env, &storage::DataKey::Product(first)) > 1_000_000);
+    assert!(live_until(&env, &storage::DataKey::Product(second)) > 1_000_000);
+    assert_eq!(live_until(&env, &storage::DataKey::Product(third)), default_live_until);
+
+    // Reads leave TTLs alone until the config opts in
+    env.ledger().with_mut(|li| li.sequence_number = 3_000);
+    client.get_product(&third);
+    assert_eq!(live_until(&env, &storage::DataKey::Product(third)), default_live_until);
+
+    let mut config = client.get_config();
+    config.read_ttl_extend_to = 200_000;
+    client.set_config(&admin, &config);
+    client.get_product(&third);
+    assert_eq!(live_until(&env, &storage::DataKey::Product(third)), 203_000);
+    assert_eq!(live_until(&env, &storage::DataKey::OwnerProductIndex(owner.clone(), 3)), 203_000);
+
+    // Every listing extends the products it returns the same way
+    let listings: [&dyn Fn(); 4] = [
+        &|| assert_eq!(client.get_all_products(&2, &1).len(), 1),
+        &|| assert_eq!(client.get_products_by_origin(&origin, &2, &1).len(), 1),
+        &|| assert_eq!(client.get_all_products_paged(&2, &1).items.len(), 1),
+        &|| assert_eq!(client.get_all_products_cursor(&2, &1).items.len(), 1),
+    ];
+    for (i, list) in listings.iter().enumerate() {
+        let sequence = 5_000 + 1_000 * i as u32;
+        env.ledger().with_mut(|li| li.sequence_number = sequence);
+        list();
+        assert_eq!(live_until(&env, &storage::DataKey::Product(third)), sequence + 200_000);
+    }
+}
+
+#[test]
+fn test_sunset() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register_contract(None, ChainLogisticsContract);
+    let client = ChainLogisticsContractClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    let heir = Address::generate(&env);
+    let owner = Address::generate(&env);
+    let buyer = Address::generate(&env);
+    let successor = Address::generate(&env);
+    let origin = String::from_str(&env, "Japan");
+    client.initialize(&admin, &String::from_str(&env, "ChainLogistics"));
+    let id = client.register_product(&owner, &origin, &String::from_str(&env, "Matcha"));
+    client.propose_admin(&admin, &heir);
+
+    let res = client.try_sunset(&owner, &Some(successor.clone()));
+    assert!(res.is_err());
+    assert!(!client.is_sunset());
+
+    client.pause(&admin);
+    client.sunset(&admin, &Some(successor.clone()));
+    assert!(client.is_sunset());
+    assert_eq!(client.get_successor(), Some(successor));
+
+    // Neither unpausing nor a new admin brings the deployment back
+    for res in [client.try_unpause(&admin), client.try_accept_admin(&heir), client.try_sunset(&admin, &None)] {
+        match res {
+            Err(Ok(e)) => assert_eq!(e, Error::ContractSunset),
+            _ => panic!("expected ContractSunset"),
+        }
+    }
+    assert_eq!(client.get_admin(), Some(admin));
+    let res = client.try_transfer_product(&owner, &id, &buyer);
+    match res {
+        Err(Ok(e)) => assert_eq!(e, Error::ContractSunset),
+        _ => panic!("expected ContractSunset"),
+    }
+    let res = client.try_register_product(&owner, &origin, &String::from_str(&env, "Sencha"));
+    match res {
+        Err(Ok(e)) => assert_eq!(e, Error::ContractSunset),
+        _ => panic!("expected ContractSunset"),
+    }
+
+    // Reads keep working
+    assert_eq!(client.get_product(&id).unwrap().owner, owner);
+    assert_eq!(client.get_products_by_owner(&owner, &0, &10).len(), 1);
+}
+
+#[test]
+fn test_registration_rate_limit() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register_contract(None, ChainLogisticsContract);
+    let client = ChainLogisticsContractClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    let spammer = Address::generate(&env);
+    let registrar = Address::generate(&env);
+    let origin = String::from_str(&env, "Chile");
+    let meta = String::from_str(&env, "Copper");
+    client.initialize(&admin, &String::from_str(&env, "ChainLogistics"));
+
+    let mut config = client.get_config();
+    config.max_registrations_per_window = 2;
+    let res = client.try_set_config(&admin, &config);
+    match res {
+        Err(Ok(e)) => assert_eq!(e, Error::InvalidInput),
+        _ => panic!("expected InvalidInput"),
+    }
+    config.registration_window_secs = 3600;
+    client.set_config(&admin, &config);
+
+    env.ledger().with_mut(|li| li.timestamp = 1_000);
+    client.register_product(&spammer, &origin, &meta);
+    client.register_product(&spammer, &origin, &meta);
+    let res = client.try_register_product(&spammer, &origin, &meta);
+    match res {
+        Err(Ok(e)) => assert_eq!(e, Error::RateLimited),
+        _ => panic!("expected RateLimited"),
+    }
+
+    // The window resets once the period has passed
+    env.ledger().with_mut(|li| li.timestamp = 4_599);
+    assert!(client.try_register_product(&spammer, &origin, &meta).is_err());
+    env.ledger().with_mut(|li| li.timestamp = 4_600);
+    client.register_product(&spammer, &origin, &meta);
+
+    client.set_rate_limit_exempt(&admin, &registrar, &true);
+    assert!(client.is_rate_limit_exempt(&registrar));
+    for _ in 0..5 {
+        client.register_product(&registrar, &origin, &meta);
+    }
+
+    // A limit of zero turns the feature off
+    config.max_registrations_per_window = 0;
+    client.set_config(&admin, &config);
+    for _ in 0..3 {
+        client.register_product(&spammer, &origin, &meta);
+    }
+}
+
+#[test]
+fn test_admin_transfer() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register_contract(None, ChainLogisticsContract);
+    let client = ChainLogisticsContractClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    let holder = Address::generate(&env);
+    let claimant = Address::generate(&env);
+    let buyer = Address::generate(&env);
+    let origin = String::from_str(&env, "Italy");
+    client.initialize(&admin, &String::from_str(&env, "ChainLogistics"));
+    let id = client.register_product(&holder, &origin, &String::from_str(&env, "Parmesan"));
+    client.transfer_product(&holder, &id, &buyer);
+    client.transfer_product(&buyer, &id, &holder);
+
+    let res = client.try_admin_transfer(&admin, &id, &claimant, &String::from_str(&env, ""));
+    match res {
+        Err(Ok(e)) => assert_eq!(e, Error::InvalidInput),
+        _ => panic!("expected InvalidInput"),
+    }
+    let res = client.try_admin_transfer(&admin, &id, &claimant, &String::from_str(&env, "order"));
+    match res {
+        Err(Ok(e)) => assert_eq!(e, Error::InvalidInput),
+        _ => panic!("expected InvalidInput"),
+    }
+    let reason = String::from_str(&env, "Court order 2026-114");
+    let res = client.try_admin_transfer(&holder, &id, &claimant, &reason);
+    match res {
+        Err(Ok(e)) => assert_eq!(e, Error::Unauthorized),
+        _ => panic!("expected Unauthorized"),
+    }
+
+    client.admin_transfer(&admin, &id, &claimant, &reason);
+    assert_eq!(client.get_product(&id).unwrap().owner, claimant);
+    assert_eq!(client.get_products_by_owner(&holder, &0, &10).len(), 0);
+    assert_eq!(client.get_products_by_owner(&claimant, &0, &10).len(), 1);
+
+    let history = client.get_transfer_history(&id, &0, &10);
+    assert_eq!(history.len(), 4);
+    let regular = history.get(2).unwrap();
+    assert!(!regular.forced);
+    assert_eq!(client.get_forced_by(&id, &2), None);
+    let forced = history.get(3).unwrap();
+    assert!(forced.forced);
+    assert_eq!(forced.from, holder);
+    assert_eq!(forced.to, claimant);
+    assert_eq!(forced.memo, reason);
+    assert_eq!(client.get_forced_by(&id, &3), Some(admin));
+}
+
+#[test]
+fn test_contract_info() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register_contract(None, ChainLogisticsContract);
+    let client = ChainLogisticsContractClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    let treasury = Address::generate(&env);
+    let token_id = env.register_stellar_asset_contract(admin.clone());
+
+    let info = client.get_contract_info();
+    assert_eq!(info.name, String::from_str(&env, ""));
+    assert_eq!(info.version, String::from_str(&env, "1.0.0"));
+    assert_eq!(info.data_version, 0);
+    assert!(info.admin.is_empty());
+    assert!(!info.paused);
+    assert!(!info.sunset);
+    assert!(info.fee.is_empty());
+
+    client.initialize(&admin, &String::from_str(&env, "Coffee Traceability"));
+    client.set_fee(&admin, &token_id, &25, &treasury);
+    client.pause(&admin);
+    let info = client.get_contract_info();
+    assert_eq!(info.name, String::from_str(&env, "Coffee Traceability"));
+    assert_eq!(info.data_version, 1);
+    assert_eq!(info.admin, Vec::from_array(&env, [admin.clone()]));
+    assert!(info.paused);
+    assert_eq!(info.fee.get(0).unwrap().amount, 25);
+    assert_eq!(info.fee.get(0).unwrap().recipient, treasury);
+
+    client.set_contract_name(&admin, &String::from_str(&env, "Coffee Traceability v2"));
+    let res = client.try_set_contract_name(&treasury, &String::from_str(&env, "Hijacked"));
+    assert!(res.is_err());
+    client.sunset(&admin, &None);
+    let info = client.get_contract_info();
+    assert_eq!(info.name, String::from_str(&env, "Coffee Traceability v2"));
+    assert!(info.sunset);
+}
+
+#[test]
+fn test_repair_indexes() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register_contract(None, ChainLogisticsContract);
+    let client = ChainLogisticsContractClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    let owner = Address::generate(&env);
+    let origin = String::from_str(&env, "Ghana");
+    client.initialize(&admin, &String::from_str(&env, "ChainLogistics"));
+    let healthy = client.register_product(&owner, &origin, &String::from_str(&env, "Cocoa"));
+    let orphaned = client.register_product(&owner, &origin, &String::from_str(&env, "Shea"));
+    let unlisted = client.register_product(&owner, &origin, &String::from_str(&env, "Cashew"));
+    let dropped = client.register_product(&owner, &origin, &String::from_str(&env, "Yam"));
+
+    // Corrupt the indexes the way a bug or half-finished migration might
+    env.as_contract(&contract_id, || {
+        storage::remove_product(&env, orphaned);
+        storage::remove_owner_index(&env, &owner, unlisted);
+        storage::remove_origin_index(&env, &origin, unlisted);
+        env.storage().persistent().remove(&storage::DataKey::AllProductsIndex(dropped));
+    });
+    assert_eq!(client.get_products_by_owner(&owner, &0, &10).len(), 2);
+    assert_eq!(client.get_all_products(&0, &10).len(), 2);
+
+    let res = client.try_repair_indexes(&owner, &0, &10);
+    match res {
+        Err(Ok(e)) => assert_eq!(e, Error::Unauthorized),
+        _ => panic!("expected Unauthorized"),
+    }
+
+    // Chunked over two calls
+    let first = client.repair_indexes(&admin, &0, &2);
+    assert_eq!(first.checked, 2);
+    assert_eq!(first.fixed, 0);
+    assert_eq!(first.removed, 1);
+    let second = client.repair_indexes(&admin, &first.next_start, &10);
+    assert_eq!(second.checked, 2);
+    assert_eq!(second.fixed, 3);
+    assert_eq!(second.removed, 0);
+    assert_eq!(second.next_start, 4);
+
+    let owned = client.get_products_by_owner(&owner, &0, &10);
+    assert_eq!(owned.len(), 3);
+    assert!(owned.iter().any(|p| p.id == unlisted));
+    assert_eq!(client.get_products_by_origin(&origin, &0, &10).len(), 3);
+    assert_eq!(client.get_all_products(&0, &10).len(), 3);
+    assert!(client.get_all_products(&0, &10).iter().any(|p| p.id == healthy));
+
+    // A second pass finds nothing left to do
+    let again = client.repair_indexes(&admin, &0, &10);
+    assert_eq!(again.checked, 4);
+    assert_eq!(again.fixed + again.removed, 0);
+}
+
+#[test]
+fn test_fee_sponsorship() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register_contract(None, ChainLogisticsContract);
+    let client = ChainLogisticsContractClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    let treasury = Address::generate(&env);
+    let ngo = Address::generate(&env);
+    let farmer = Address::generate(&env);
+    let other_farmer = Address::generate(&env);
+    client.initialize(&admin, &String::from_str(&env, "ChainLogistics"));
+    let token_id = env.register_stellar_asset_contract(admin.clone());
+    let token = soroban_sdk::token::Client::new(&env, &token_id);
+    let minter = soroban_sdk::token::StellarAssetClient::new(&env, &token_id);
+    minter.mint(&ngo, &1_000);
+    token.approve(&ngo, &contract_id, &1_000, &1_000);
+    client.set_fee(&admin, &token_id, &100, &treasury);
+
+    let origin = String::from_str(&env, "Uganda");
+    let meta = String::from_str(&env, "Coffee cherries");
+
+    client.set_sponsor(&ngo, &farmer, &2);
+    assert_eq!(client.get_sponsorship(&farmer), Some(Sponsorship { sponsor: ngo.clone(), remaining: 2 }));
+    client.register_product(&farmer, &origin, &meta);
+    assert_eq!(client.get_sponsorship(&farmer).unwrap().remaining, 1);
+    client.register_product(&farmer, &origin, &meta);
+    assert_eq!(token.balance(&ngo), 800);
+    assert_eq!(token.balance(&treasury), 200);
+    assert_eq!(client.get_sponsorship(&farmer), None);
+
+    // Once the allowance is used up the farmer pays for themselves
+    let res = client.try_register_product(&farmer, &origin, &meta);
+    match res {
+        Err(Ok(e)) => assert_eq!(e, Error::FeePaymentFailed),
+        _ => panic!("expected FeePaymentFailed"),
+    }
+    minter.mint(&farmer, &100);
+    client.register_product(&farmer, &origin, &meta);
+    assert_eq!(token.balance(&farmer), 0);
+    assert_eq!(token.balance(&ngo), 800);
+
+    // Only the sponsor can revoke, and revocation falls back to self-pay
+    client.set_sponsor(&ngo, &other_farmer, &5);
+    let res = client.try_revoke_sponsorship(&farmer, &other_farmer);
+    match res {
+        Err(Ok(e)) => assert_eq!(e, Error::Unauthorized),
+        _ => panic!("expected Unauthorized"),
+    }
+    client.revoke_sponsorship(&ngo, &other_farmer);
+    assert_eq!(client.get_sponsorship(&other_farmer), None);
+    assert!(client.try_register_product(&other_farmer, &origin, &meta).is_err());
+    assert_eq!(token.balance(&ngo), 800);
+}
+
+#[test]
+fn test_listing_only_active() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register_contract(None, ChainLogisticsContract);
+    let client = ChainLogisticsContractClient::new(&env, &contract_id);
+
+    let owner = Address::generate(&env);
+    let origin = String::from_str(&env, "Spain");
+    let meta = String::from_str(&env, "Olive oil");
+    for _ in 0..6 {
+        client.register_product(&owner, &origin, &meta);
+    }
+    env.as_contract(&contract_id, || {
+        for id in [2u64, 3, 5] {
+            let mut product = storage::get_product(&env, id).unwrap();
+            product.active = false;
+            storage::put_product(&env, &product);
+        }
+    });
+    let ids = |products: Vec<Product>| {
+        let mut ids = Vec::new(&env);
+        for p in products.iter() {
+            ids.push_back(p.id);
+        }
+        ids
+    };
+    let expect = |expected: &[u64]| Vec::from_slice(&env, expected);
+
+    // Inactive products are skipped without shrinking the page
+    assert_eq!(ids(client.get_all_products_v2(&0, &2, &true)), expect(&[1, 4]));
+    assert_eq!(ids(client.get_all_products_v2(&4, &2, &true)), expect(&[6]));
+    assert_eq!(ids(client.get_all_products_v2(&0, &2, &false)), expect(&[1, 2]));
+    assert_eq!(ids(client.get_all_products_v2(&6, &2, &true)), expect(&[]));
+
+    assert_eq!(ids(client.get_products_by_owner_v2(&owner, &0, &2, &true)), expect(&[1, 4]));
+    // Continuing at start + limit repeats but never skips
+    assert_eq!(ids(client.get_products_by_owner_v2(&owner, &2, &2, &true)), expect(&[4, 6]));
+    assert_eq!(ids(client.get_products_by_owner_v2(&owner, &0, &10, &false)).len(), 6);
+
+    assert_eq!(ids(client.get_products_by_origin_v2(&origin, &0, &3, &true)), expect(&[1, 4, 6]));
+    assert_eq!(ids(client.get_products_by_origin_v2(&origin, &1, &1, &true)), expect(&[4]));
+}
+
+#[test]
+fn test_paged_listings() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register_contract(None, ChainLogisticsContract);
+    let client = ChainLogisticsContractClient::new(&env, &contract_id);
+
+    let owner = Address::generate(&env);
+    let buyer = Address::generate(&env);
+    let origin = String::from_str(&env, "Greece");
+    let meta = String::from_str(&env, "Feta");
+    for _ in 0..4 {
+        client.register_product(&owner, &origin, &meta);
+    }
+
+    // An exact multiple of the page size ends on a full page
+    let first = client.get_all_products_paged(&0, &2);
+    assert_eq!(first.items.len(), 2);
+    assert_eq!(first.total, 4);
+    assert_eq!(first.next_start, Some(2));
+    let last = client.get_all_products_paged(&first.next_start.unwrap(), &2);
+    assert_eq!(last.items.get(1).unwrap().id, 4);
+    assert_eq!(last.next_start, None);
+
+    // A final partial page
+    client.register_product(&owner, &origin, &meta);
+    let page = client.get_products_by_origin_paged(&origin, &2, &2);
+    assert_eq!(page.next_start, Some(4));
+    let page = client.get_products_by_origin_paged(&origin, &page.next_start.unwrap(), &2);
+    assert_eq!(page.items.len(), 1);
+    assert_eq!(page.items.get(0).unwrap().id, 5);
+    assert_eq!(page.total, 5);
+    assert_eq!(page.next_start, None);
+
+    // Vacated owner slots are skipped but still counted
+    client.transfer_product(&owner, &2, &buyer);
+    let page = client.get_products_by_owner_paged(&owner, &0, &2);
+    assert_eq!(page.items.get(0).unwrap().id, 1);
+    assert_eq!(page.items.get(1).unwrap().id, 3);
+    assert_eq!(page.total, 5);
+    assert_eq!(page.next_start, Some(3));
+    let page = client.get_products_by_owner_paged(&owner, &3, &2);
+    assert_eq!(page.items.len(), 2);
+    assert_eq!(page.next_start, None);
+    let page = client.get_products_by_owner_paged(&buyer, &0, &2);
+    assert_eq!(page.items.len(), 1);
+    assert_eq!(page.next_start, None);
+}
+
+#[test]
+fn test_pagination_guards() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register_contract(None, ChainLogisticsContract);
+    let client = ChainLogisticsContractClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    let owner = Address::generate(&env);
+    let origin = String::from_str(&env, "Norway");
+    let meta = String::from_str(&env, "Salmon");
+    client.initialize(&admin, &String::from_str(&env, "ChainLogistics"));
+    for _ in 0..5 {
+        client.register_product(&owner, &origin, &meta);
+    }
+
+    // start near u64::MAX cannot wrap around to the first slots
+    assert_eq!(client.get_all_products(&u64::MAX, &u64::MAX).len(), 0);
+    assert_eq!(client.get_products_by_owner(&owner, &(u64::MAX - 1), &10).len(), 0);
+    assert_eq!(client.get_products_by_origin(&origin, &u64::MAX, &1).len(), 0);
+    assert_eq!(client.get_products_by_tag(&symbol_short!("fish"), &u64::MAX, &u64::MAX).len(), 0);
+    assert_eq!(client.get_all_products_paged(&u64::MAX, &u64::MAX).next_start, None);
+
+    // A zero limit returns nothing
+    assert_eq!(client.get_all_products(&0, &0).len(), 0);
+    assert_eq!(client.get_products_by_owner(&owner, &0, &0).len(), 0);
+    assert_eq!(client.get_products_by_origin_v2(&origin, &0, &0, &true).len(), 0);
+    let empty = client.get_all_products_paged(&0, &0);
+    assert_eq!(empty.items.len(), 0);
+    assert_eq!(empty.next_start, Some(0));
+
+    // Oversized limits are clamped to the configured cap
+    let mut config = client.get_config();
+    config.max_page_limit = 3;
+    client.set_config(&admin, &config);
+    assert_eq!(client.get_all_products(&0, &u64::MAX).len(), 3);
+    assert_eq!(client.get_products_by_owner(&owner, &0, &u64::MAX).len(), 3);
+    assert_eq!(client.get_products_by_origin(&origin, &0, &u64::MAX).len(), 3);
+    assert_eq!(client.get_all_products_paged(&0, &u64::MAX).next_start, Some(3));
+}
+
+#[test]
+fn test_listing_newest_first() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register_contract(None, ChainLogisticsContract);
+    let client = ChainLogisticsContractClient::new(&env, &contract_id);
+
+    let owner = Address::generate(&env);
+    let other = Address::generate(&env);
+    let origin = String::from_str(&env, "Vietnam");
+    let meta = String::from_str(&env, "Pepper");
+    for _ in 0..5 {
+        client.register_product(&owner, &origin, &meta);
+    }
+    let ids = |products: Vec<Product>| {
+        let mut ids = Vec::new(&env);
+        for p in products.iter() {
+            ids.push_back(p.id);
+        }
+        ids
+    };
+    let expect = |expected: &[u64]| Vec::from_slice(&env, expected);
+
+    assert_eq!(ids(client.get_all_products_desc(&0, &2, &0)), expect(&[5, 4]));
+    let pinned = client.get_stats().total_products;
+
+    // New registrations between fetches do not shift a pinned listing
+    client.register_product(&other, &origin, &meta);
+    client.register_product(&owner, &origin, &meta);
+    assert_eq!(ids(client.get_all_products_desc(&2, &2, &pinned)), expect(&[3, 2]));
+    assert_eq!(ids(client.get_all_products_desc(&4, &2, &pinned)), expect(&[1]));
+    assert_eq!(ids(client.get_all_products_desc(&0, &2, &0)), expect(&[7, 6]));
+    assert_eq!(ids(client.get_all_products_desc(&10, &2, &0)), expect(&[]));
+
+    assert_eq!(ids(client.get_products_by_owner_desc(&owner, &0, &3, &0)), expect(&[7, 5, 4]));
+    assert_eq!(ids(client.get_products_by_owner_desc(&owner, &1, &3, &5)), expect(&[4, 3, 2]));
+    assert_eq!(ids(client.get_products_by_origin_desc(&origin, &0, &3, &0)), expect(&[7, 6, 5]));
+    assert_eq!(ids(client.get_products_by_origin_desc(&origin, &3, &3, &5)), expect(&[2, 1]));
+}
+
+#[test]
+fn test_id_only_listings() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register_contract(None, ChainLogisticsContract);
+    let client = ChainLogisticsContractClient::new(&env, &contract_id);
+
+    let owner = Address::generate(&env);
+    let buyer = Address::generate(&env);
+    let origin = String::from_str(&env, "Mexico");
+    let meta = String::from_str(&env, "Vanilla beans, grade A, cured for twelve weeks in the sun");
+    for _ in 0..8 {
+        client.register_product(&owner, &origin, &meta);
+    }
+    client.transfer_product(&owner, &3, &buyer);
+    let ids = |products: Vec<Product>| {
+        let mut ids = Vec::new(&env);
+        for p in products.iter() {
+            ids.push_back(p.id);
+        }
+        ids
+    };
+
+    assert_eq!(client.get_product_ids(&0, &10), ids(client.get_all_products(&0, &10)));
+    assert_eq!(client.get_product_ids(&2, &3), ids(client.get_all_products(&2, &3)));
+    assert_eq!(client.get_product_ids_by_owner(&owner, &0, &10), ids(client.get_products_by_owner(&owner, &0, &10)));
+    assert_eq!(client.get_product_ids_by_owner(&buyer, &0, &10), Vec::from_array(&env, [3u64]));
+    assert_eq!(client.get_product_ids_by_origin(&origin, &4, &10), ids(client.get_products_by_origin(&origin, &4, &10)));
+
+    // Skipping the product reads roughly halves the CPU cost of a page of 8;
+    // the rest is the index reads and call overhead both listings share
+    env.budget().reset_default();
+    client.get_all_products(&0, &8);
+    let full_cost = env.budget().cpu_instruction_cost();
+    env.budget().reset_default();
+    client.get_product_ids(&0, &8);
+    let ids_cost = env.budget().cpu_instruction_cost();
+    assert!(ids_cost * 3 < full_cost * 2, "ids {} vs full {}", ids_cost, full_cost);
+}
+
+#[test]
+fn test_get_products_batch() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register_contract(None, ChainLogisticsContract);
+    let client = ChainLogisticsContractClient::new(&env, &contract_id);
+
+    let owner = Address::generate(&env);
+    let origin = String::from_str(&env, "Peru");
+    let metadata = String::from_str(&env, "Metadata");
+    for _ in 0..4 {
+        client.register_product(&owner, &origin, &metadata);
+    }
+    client.burn_product(&owner, &2);
+
+    let products = client.get_products(&Vec::from_array(&env, [4u64, 2, 99, 1, 4]));
+    assert_eq!(products.len(), 5);
+    assert_eq!(products.get(0).unwrap().unwrap().id, 4);
+    assert_eq!(products.get(1).unwrap(), None);
+    assert_eq!(products.get(2).unwrap(), None);
+    assert_eq!(products.get(3).unwrap().unwrap().id, 1);
+    assert_eq!(products.get(4).unwrap().unwrap().id, 4);
+
+    assert_eq!(client.get_products(&Vec::new(&env)).len(), 0);
+
+    let mut too_many = Vec::new(&env);
+    for id in 0..101u64 {
+        too_many.push_back(id);
+    }
+    assert_eq!(client.try_get_products(&too_many), Err(Ok(Error::BatchTooLarge)));
+}
+
+#[test]
+fn test_existence_and_count_views() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register_contract(None, ChainLogisticsContract);
+    let client = ChainLogisticsContractClient::new(&env, &contract_id);
+
+    let owner = Address::generate(&env);
+    let buyer = Address::generate(&env);
+    let kenya = String::from_str(&env, "Kenya");
+    let chile = String::from_str(&env, "Chile");
+    let metadata = String::from_str(&env, "Metadata");
+    for i in 0..5 {
+        client.register_product(&owner, if i < 3 { &kenya } else { &chile }, &metadata);
+    }
+
+    assert_eq!(client.get_owner_product_count(&owner), 5);
+    assert_eq!(client.get_owner_product_count(&owner), client.get_products_by_owner(&owner, &0, &10).len() as u64);
+    assert_eq!(client.get_origin_product_count(&kenya), client.get_products_by_origin(&kenya, &0, &10).len() as u64);
+    assert_eq!(client.get_origin_product_count(&chile), 2);
+    assert_eq!(client.get_owner_product_count(&buyer), 0);
+    assert_eq!(client.get_origin_product_count(&String::from_str(&env, "Peru")), 0);
+
+    // Counts are index slots, so they keep vacated slots and match the
+    // paged listings' totals rather than the live item count
+    client.transfer_product(&owner, &2, &buyer);
+    assert_eq!(client.get_owner_product_count(&owner), 5);
+    assert_eq!(client.get_owner_product_count(&owner), client.get_products_by_owner_paged(&owner, &0, &10).total);
+    assert_eq!(client.get_owner_product_count(&buyer), 1);
+    assert_eq!(client.get_products_by_owner(&buyer, &0, &10).len(), 1);
+
+    assert!(client.product_exists(&1));
+    assert!(client.product_exists(&5));
+    assert!(!client.product_exists(&0));
+    assert!(!client.product_exists(&6));
+    client.burn_product(&owner, &1);
+    assert!(!client.product_exists(&1));
+    assert_eq!(client.get_origin_product_count(&kenya), client.get_products_by_origin_paged(&kenya, &0, &10).total);
+}
+
+#[test]
+fn test_products_by_time_range() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register_contract(None, ChainLogisticsContract);
+    let client = ChainLogisticsContractClient::new(&env, &contract_id);
+
+    let owner = Address::generate(&env);
+    let origin = String::from_str(&env, "Ghana");
+    let metadata = String::from_str(&env, "Metadata");
+    let day = 86_400u64;
+    let base = 100 * day;
+    // Day 100: ids 1-2, day 101: ids 3-5, day 103: id 6; day 102 is empty
+    for ts in [base + 100, base + 50_000, base + day + 10, base + day + 20_000, base + day + 80_000, base + 3 * day + 5] {
+        env.ledger().with_mut(|li| li.timestamp = ts);
+        client.register_product(&owner, &origin, &metadata);
+    }
+    let ids = |products: Vec<Product>| {
+        let mut ids = Vec::new(&env);
+        for p in products.iter() {
+            ids.push_back(p.id);
+        }
+        ids
+    };
+    let expect = |ids: &[u64]| Vec::from_slice(&env, ids);
+
+    assert_eq!(ids(client.get_products_by_time_range(&base, &(base + 4 * day), &0, &10)), expect(&[1, 2, 3, 4, 5, 6]));
+    // Bounds inside the first and last day cut registrations either side
+    assert_eq!(ids(client.get_products_by_time_range(&(base + 1_000), &(base + day + 20_000), &0, &10)), expect(&[2, 3, 4]));
+    assert_eq!(ids(client.get_products_by_time_range(&(base + day), &(base + 2 * day - 1), &0, &10)), expect(&[3, 4, 5]));
+    assert_eq!(ids(client.get_products_by_time_range(&(base + 50_000), &(base + 50_000), &0, &10)), expect(&[2]));
+
+    // Paging across days, including skipping a whole inner day
+    assert_eq!(ids(client.get_products_by_time_range(&base, &(base + 4 * day), &0, &3)), expect(&[1, 2, 3]));
+    assert_eq!(ids(client.get_products_by_time_range(&base, &(base + 4 * day), &3, &3)), expect(&[4, 5, 6]));
+    assert_eq!(ids(client.get_products_by_time_range(&(base + 1_000), &(base + 3 * day + 10), &3, &10)), expect(&[5, 6]));
+    assert_eq!(client.get_products_by_time_range(&base, &(base + 4 * day), &6, &3).len(), 0);
+
+    // Burned products are not counted, on full days or partial ones
+    client.burn_product(&owner, &3);
+    assert_eq!(ids(client.get_products_by_time_range(&base, &(base + 4 * day), &2, &2)), expect(&[4, 5]));
+    assert_eq!(ids(client.get_products_by_time_range(&(base + 1_000), &(base + 3 * day + 10), &0, &2)), expect(&[2, 4]));
+    assert_eq!(ids(client.get_products_by_time_range(&(base + 1_000), &(base + 3 * day + 10), &2, &2)), expect(&[5, 6]));
+
+    // Ranges with no registrations
+    assert_eq!(client.get_products_by_time_range(&(base + 2 * day), &(base + 3 * day - 1), &0, &10).len(), 0);
+    assert_eq!(client.get_products_by_time_range(&0, &(base - 1), &0, &10).len(), 0);
+
+    assert_eq!(client.try_get_products_by_time_range(&(base + 1), &base, &0, &10), Err(Ok(Error::InvalidInput)));
+    assert_eq!(client.try_get_products_by_time_range(&0, &(366 * day), &0, &10), Err(Ok(Error::InvalidInput)));
+}
+
+#[test]
+fn test_filtering_by_owner_and_origin() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register_contract(None, ChainLogisticsContract);
+    let client = ChainLogisticsContractClient::new(&env, &contract_id);
+
+    let owner1 = Address::generate(&env);
+    let owner2 = Address::generate(&env);
+    let origin1 = String::from_str(&env, "China");
+    let origin2 = String::from_str(&env, "Germany");
+
+    client.register_product(&owner1, &origin1, &String::from_str(&env, "P1")); // ID 1
+    client.register_product(&owner2, &origin2, &String::from_str(&env, "P2")); // ID 2
+    client.register_product(&owner1, &origin2, &String::from_str(&env, "P3")); // ID 3
+    client.register_product(&owner1, &origin2, &String::from_str(&env, "P4")); // ID 4
+    let ids = |products: Vec<Product>| {
+        let mut ids = Vec::new(&env);
+        for p in products.iter() {
+            ids.push_back(p.id);
+        }
+        ids
+    };
+    let expect = |ids: &[u64]| Vec::from_slice(&env, ids);
+
+    assert_eq!(ids(client.get_products_by_owner_and_origin(&owner1, &origin1, &0, &10)), expect(&[1]));
+    assert_eq!(ids(client.get_products_by_owner_and_origin(&owner1, &origin2, &0, &10)), expect(&[3, 4]));
+    assert_eq!(ids(client.get_products_by_owner_and_origin(&owner2, &origin2, &0, &10)), expect(&[2]));
+    assert_eq!(client.get_products_by_owner_and_origin(&owner2, &origin1, &0, &10).len(), 0);
+    assert_eq!(ids(client.get_products_by_owner_and_origin(&owner1, &origin2, &1, &1)), expect(&[4]));
+
+    // A transfer moves the product between the owners' compound lists
+    client.transfer_product(&owner1, &3, &owner2);
+    assert_eq!(ids(client.get_products_by_owner_and_origin(&owner1, &origin2, &0, &10)), expect(&[4]));
+    assert_eq!(ids(client.get_products_by_owner_and_origin(&owner2, &origin2, &0, &10)), expect(&[2, 3]));
+
+    // So does an origin change, within the same owner
+    client.update_origin(&owner1, &4, &origin1);
+    assert_eq!(client.get_products_by_owner_and_origin(&owner1, &origin2, &0, &10).len(), 0);
+    assert_eq!(ids(client.get_products_by_owner_and_origin(&owner1, &origin1, &0, &10)), expect(&[1, 4]));
+
+    client.renounce_ownership(&owner2, &2);
+    assert_eq!(ids(client.get_products_by_owner_and_origin(&owner2, &origin2, &0, &10)), expect(&[3]));
+}
+
+#[test]
+fn test_distinct_origins() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register_contract(None, ChainLogisticsContract);
+    let client = ChainLogisticsContractClient::new(&env, &contract_id);
+
+    let owner = Address::generate(&env);
+    let metadata = String::from_str(&env, "Metadata");
+    let names = ["Brazil", "Kenya", "Brazil", "India", "Kenya", "Vietnam", "India"];
+    for name in names {
+        client.register_product(&owner, &String::from_str(&env, name), &metadata);
+    }
+
+    assert_eq!(client.get_origin_count(), 4);
+    let expect = Vec::from_array(
+        &env,
+        [
+            String::from_str(&env, "Brazil"),
+            String::from_str(&env, "Kenya"),
+            String::from_str(&env, "India"),
+            String::from_str(&env, "Vietnam"),
+        ],
+    );
+    assert_eq!(client.get_origins(&0, &10), expect);
+    assert_eq!(client.get_origins(&1, &2), expect.slice(1..3));
+    assert_eq!(client.get_origins(&4, &10).len(), 0);
+
+    // Burning every Vietnam product keeps the origin listed
+    client.burn_product(&owner, &6);
+    assert_eq!(client.get_origin_count(), 4);
+    assert_eq!(client.get_origins(&0, &10), expect);
+}
+
+#[test]
+fn test_extended_stats() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register_contract(None, ChainLogisticsContract);
+    let client = ChainLogisticsContractClient::new(&env, &contract_id);
+
+    // Nothing stored yet reads as zero and None
+    assert_eq!(
+        client.get_stats(),
+        ProductStats {
+            total_products: 0,
+            active_products: 0,
+            inactive_products: 0,
+            total_transfers: 0,
+            distinct_origins: 0,
+            total_owners: 0,
+            last_registration_at: None,
+        }
+    );
+
+    let alice = Address::generate(&env);
+    let bob = Address::generate(&env);
+    let carol = Address::generate(&env);
+    let metadata = String::from_str(&env, "Metadata");
+    env.ledger().with_mut(|li| li.timestamp = 1_000);
+    client.register_product(&alice, &String::from_str(&env, "Peru"), &metadata); // 1
+    client.register_product(&alice, &String::from_str(&env, "Chile"), &metadata); // 2
+    env.ledger().with_mut(|li| li.timestamp = 2_000);
+    client.register_product(&bob, &String::from_str(&env, "Peru"), &metadata); // 3
+    client.register_product(&bob, &String::from_str(&env, "Peru"), &metadata); // 4
+    env.ledger().with_mut(|li| li.timestamp = 3_000);
+    client.transfer_product(&alice, &1, &carol);
+    client.transfer_product(&carol, &1, &bob);
+    client.renounce_ownership(&bob, &3);
+    client.burn_product(&alice, &2);
+
+    assert_eq!(
+        client.get_stats(),
+        ProductStats {
+            total_products: 4,
+            active_products: 2,
+            inactive_products: 1,
+            total_transfers: 2,
+            distinct_origins: 2,
+            // Carol only ever received a product
+            total_owners: 2,
+            last_registration_at: Some(2_000),
+        }
+    );
+}
+
+#[test]
+fn test_origin_stats() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register_contract(None, ChainLogisticsContract);
+    let client = ChainLogisticsContractClient::new(&env, &contract_id);
+
+    let owner = Address::generate(&env);
+    let buyer = Address::generate(&env);
+    let colombia = String::from_str(&env, "Colombia");
+    let ethiopia = String::from_str(&env, "Ethiopia");
+    let metadata = String::from_str(&env, "Metadata");
+
+    env.ledger().with_mut(|li| li.timestamp = 500);
+    client.register_product(&owner, &ethiopia, &metadata); // 1
+    env.ledger().with_mut(|li| li.timestamp = 1_000);
+    for _ in 0..5 {
+        client.register_product(&owner, &colombia, &metadata); // 2-6
+    }
+    env.ledger().with_mut(|li| li.timestamp = 1_500);
+    client.register_product(&owner, &colombia, &metadata); // 7
+
+    client.transfer_product(&owner, &2, &buyer);
+    client.transfer_product(&owner, &3, &buyer);
+    // A second hop is not another departure from the first owner
+    client.transfer_product(&buyer, &2, &owner);
+    client.burn_product(&owner, &4);
+    client.renounce_ownership(&owner, &5);
+    client.update_origin(&owner, &6, &ethiopia);
+
+    assert_eq!(
+        client.get_origin_stats(&colombia),
+        OriginStats { total_registered: 5, active: 3, transferred: 2, last_registration_at: 1_500 }
+    );
+    assert_eq!(
+        client.get_origin_stats(&ethiopia),
+        OriginStats { total_registered: 2, active: 2, transferred: 0, last_registration_at: 500 }
+    );
+    assert_eq!(client.get_origin_stats(&String::from_str(&env, "Kenya")), OriginStats::default());
+}
+
+#[test]
+fn test_owner_stats() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register_contract(None, ChainLogisticsContract);
+    let client = ChainLogisticsContractClient::new(&env, &contract_id);
+
+    let alice = Address::generate(&env);
+    let bob = Address::generate(&env);
+    let origin = String::from_str(&env, "Ecuador");
+    let metadata = String::from_str(&env, "Metadata");
+    for _ in 0..3 {
+        client.register_product(&alice, &origin, &metadata); // 1-3
+    }
+    client.register_product(&bob, &origin, &metadata); // 4
+
+    client.transfer_product(&alice, &1, &bob);
+    client.transfer_product(&alice, &2, &bob);
+    client.transfer_product(&bob, &4, &alice);
+    client.transfer_product(&bob, &1, &alice);
+    client.burn_product(&bob, &2);
+
+    assert_eq!(client.get_owner_stats(&alice), OwnerStats { owned: 3, registered: 3, received: 2, sent: 2 });
+    assert_eq!(client.get_owner_stats(&bob), OwnerStats { owned: 0, registered: 1, received: 2, sent: 2 });
+    // The owner index still holds Bob's vacated slots
+    assert_eq!(client.get_owner_product_count(&bob), 3);
+    assert_eq!(client.get_owner_stats(&Address::generate(&env)), OwnerStats::default());
+}
+
+#[test]
+fn test_recent_activity_feed() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+    let contract_id = env.register_contract(None, ChainLogisticsContract);
+    let client = ChainLogisticsContractClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    let owner = Address::generate(&env);
+    let buyer = Address::generate(&env);
+    let origin = String::from_str(&env, "Uganda");
+    let metadata = String::from_str(&env, "Metadata");
+    client.initialize(&admin, &String::from_str(&env, "Registry"));
+    assert_eq!(client.get_recent_activity(&10).len(), 0);
+
+    for _ in 0..103 {
+        client.register_product(&owner, &origin, &metadata);
+    }
+    client.transfer_product(&owner, &7, &buyer);
+    client.renounce_ownership(&buyer, &7);
+
+    let feed = client.get_recent_activity(&3);
+    assert_eq!(feed.len(), 3);
+    let renounced = feed.get(0).unwrap();
+    assert_eq!((renounced.seq, renounced.kind, renounced.product_id), (105, symbol_short!("renounce"), 7));
+    assert_eq!(renounced.actor, buyer);
+    let transferred = feed.get(1).unwrap();
+    assert_eq!((transferred.seq, transferred.kind, transferred.product_id), (104, symbol_short!("transfer"), 7));
+    assert_eq!(transferred.actor, buyer);
+    let registered = feed.get(2).unwrap();
+    assert_eq!((registered.seq, registered.kind, registered.product_id), (103, symbol_short!("register"), 103));
+    assert_eq!(registered.actor, owner);
+
+    // The buffer holds 100 items, so the first five registrations fell off
+    let feed = client.get_recent_activity(&500);
+    assert_eq!(feed.len(), 100);
+    let oldest = feed.get(99).unwrap();
+    assert_eq!((oldest.seq, oldest.product_id), (6, 6));
+
+    // A smaller feed only keeps what was written after the resize
+    let mut config = client.get_config();
+    config.recent_activity_size = 4;
+    client.set_config(&admin, &config);
+    assert_eq!(client.get_recent_activity(&10).len(), 0);
+    for _ in 0..6 {
+        client.register_product(&owner, &origin, &metadata);
+    }
+    let feed = client.get_recent_activity(&10);
+    assert_eq!(feed.len(), 4);
+    assert_eq!(feed.get(0).unwrap().product_id, 109);
+    assert_eq!(feed.get(3).unwrap().product_id, 106);
+
+    config.recent_activity_size = 1001;
+    assert_eq!(client.try_set_config(&admin, &config), Err(Ok(Error::InvalidInput)));
+}
+
+#[test]
+fn test_checked_product_reads() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register_contract(None, ChainLogisticsContract);
+    let client = ChainLogisticsContractClient::new(&env, &contract_id);
+
+    let owner = Address::generate(&env);
+    let buyer = Address::generate(&env);
+    let origin = String::from_str(&env, "Rwanda");
+    let metadata = String::from_str(&env, "Metadata");
+    let live = client.register_product(&owner, &origin, &metadata);
+    let burned = client.register_product(&owner, &origin, &metadata);
+    let archived = client.register_product(&owner, &origin, &metadata);
+    client.burn_product(&owner, &burned);
+    // An issued id whose record is gone without a burn, as after archival
+    env.as_contract(&contract_id, || storage::remove_product(&env, archived));
+
+    assert_eq!(client.get_product_checked(&live).id, live);
+    assert_eq!(client.try_get_product_checked(&0), Err(Ok(Error::ProductNotFound)));
+    assert_eq!(client.try_get_product_checked(&99), Err(Ok(Error::ProductNotFound)));
+    assert_eq!(client.try_get_product_checked(&burned), Err(Ok(Error::ProductBurned)));
+    assert_eq!(client.try_get_product_checked(&archived), Err(Ok(Error::ProductArchived)));
+
+    // Mutating entry points report the same distinctions
+    assert_eq!(client.try_transfer_product(&owner, &burned, &buyer), Err(Ok(Error::ProductBurned)));
+    assert_eq!(client.try_add_tag(&owner, &archived, &symbol_short!("x")), Err(Ok(Error::ProductArchived)));
+    assert_eq!(client.try_transfer_product(&owner, &99, &buyer), Err(Ok(Error::ProductNotFound)));
+
+    // The Option-returning read is unchanged
+    assert_eq!(client.get_product(&burned), None);
+    assert_eq!(client.get_product(&archived), None);
+}
+
+#[test]
+fn test_cursor_pagination_across_burns() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register_contract(None, ChainLogisticsContract);
+    let client = ChainLogisticsContractClient::new(&env, &contract_id);
+
+    let owner = Address::generate(&env);
+    let buyer = Address::generate(&env);
+    let origin = String::from_str(&env, "Bolivia");
+    let metadata = String::from_str(&env, "Metadata");
+    for _ in 0..10 {
+        client.register_product(&owner, &origin, &metadata);
+    }
+    let ids = |products: Vec<Product>| {
+        let mut ids = Vec::new(&env);
+        for p in products.iter() {
+            ids.push_back(p.id);
+        }
+        ids
+    };
+    let expect = |ids: &[u64]| Vec::from_slice(&env, ids);
+
+    // Advancing an offset by the items returned repeats product 4 once a
+    // burn vacates a slot on the first page
+    client.burn_product(&owner, &2);
+    let page = client.get_all_products(&0, &4);
+    assert_eq!(ids(page.clone()), expect(&[1, 3, 4]));
+    assert_eq!(ids(client.get_all_products(&page.len().into(), &4)), expect(&[4, 5, 6, 7]));
+
+    // Cursors pick up after the last slot examined, whatever burns between fetches
+    let page = client.get_all_products_cursor(&0, &3);
+    assert_eq!(ids(page.items), expect(&[1, 3, 4]));
+    assert_eq!((page.cursor, page.has_more), (4, true));
+    client.burn_product(&owner, &3);
+    client.burn_product(&owner, &5);
+    let page = client.get_all_products_cursor(&page.cursor, &3);
+    assert_eq!(ids(page.items), expect(&[6, 7, 8]));
+    client.burn_product(&owner, &9);
+    let page = client.get_all_products_cursor(&page.cursor, &3);
+    assert_eq!(ids(page.items), expect(&[10]));
+    assert_eq!((page.cursor, page.has_more), (10, false));
+    assert_eq!(client.get_all_products_cursor(&page.cursor, &3).items.len(), 0);
+
+    // Owner cursors also pass over slots vacated by transfers
+    let page = client.get_products_by_owner_cursor(&owner, &0, &2);
+    assert_eq!(ids(page.items), expect(&[1, 4]));
+    client.transfer_product(&owner, &6, &buyer);
+    let page = client.get_products_by_owner_cursor(&owner, &page.cursor, &2);
+    assert_eq!(ids(page.items), expect(&[7, 8]));
+    let page = client.get_products_by_owner_cursor(&owner, &page.cursor, &2);
+    assert_eq!(ids(page.items), expect(&[10]));
+    assert!(!page.has_more);
+
+    client.update_origin(&owner, &7, &String::from_str(&env, "Peru"));
+    let page = client.get_products_by_origin_cursor(&origin, &0, &10);
+    assert_eq!(ids(page.items), expect(&[1, 4, 6, 8, 10]));
+    assert!(!page.has_more);
+}
+
+#[test]
+fn test_products_by_status() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register_contract(None, ChainLogisticsContract);
+    let client = ChainLogisticsContractClient::new(&env, &contract_id);
+
+    let owner = Address::generate(&env);
+    let origin = String::from_str(&env, "Tanzania");
+    let metadata = String::from_str(&env, "Metadata");
+    for _ in 0..5 {
+        client.register_product(&owner, &origin, &metadata);
+    }
+    let ids = |products: Vec<Product>| {
+        let mut ids = Vec::new(&env);
+        for p in products.iter() {
+            ids.push_back(p.id);
+        }
+        ids
+    };
+    let expect = |ids: &[u64]| Vec::from_slice(&env, ids);
+    let counts = |registered: u64, in_transit: u64, in_customs: u64, delivered: u64| {
+        Vec::from_array(
+            &env,
+            [
+                (ProductStatus::Registered, registered),
+                (ProductStatus::InTransit, in_transit),
+                (ProductStatus::InCustoms, in_customs),
+                (ProductStatus::Delivered, delivered),
+            ],
+        )
+    };
+    let place = String::from_str(&env, "Dar es Salaam");
+    let note = String::from_str(&env, "");
+    assert_eq!(client.get_status_counts(), counts(5, 0, 0, 0));
+
+    client.add_checkpoint(&owner, &2, &CheckpointKind::Pickup, &place, &note);
+    client.add_checkpoint(&owner, &4, &CheckpointKind::Departure, &place, &note);
+    client.add_checkpoint(&owner, &1, &CheckpointKind::Departure, &place, &note);
+    // A second transit checkpoint keeps the product where it is in the index
+    client.add_checkpoint(&owner, &2, &CheckpointKind::Arrival, &place, &note);
+    assert_eq!(ids(client.get_products_by_status(&ProductStatus::InTransit, &0, &10)), expect(&[2, 4, 1]));
+    assert_eq!(ids(client.get_products_by_status(&ProductStatus::Registered, &0, &10)), expect(&[3, 5]));
+    assert_eq!(client.get_status_counts(), counts(2, 3, 0, 0));
+
+    client.add_checkpoint(&owner, &4, &CheckpointKind::CustomsIn, &place, &note);
+    client.add_checkpoint(&owner, &4, &CheckpointKind::CustomsOut, &place, &note);
+    client.add_checkpoint(&owner, &2, &CheckpointKind::CustomsIn, &place, &note);
+    client.add_checkpoint(&owner, &1, &CheckpointKind::Delivery, &place, &note);
+    client.confirm_delivery(&owner, &5);
+    assert_eq!(client.get_status(&4), ProductStatus::InTransit);
+    assert_eq!(client.get_status(&2), ProductStatus::InCustoms);
+    assert_eq!(ids(client.get_products_by_status(&ProductStatus::InTransit, &0, &10)), expect(&[4]));
+    assert_eq!(ids(client.get_products_by_status(&ProductStatus::InCustoms, &0, &10)), expect(&[2]));
+    assert_eq!(ids(client.get_products_by_status(&ProductStatus::Delivered, &0, &10)), expect(&[1, 5]));
+    assert_eq!(client.get_status_counts(), counts(1, 1, 1, 2));
+
+    client.burn_product(&owner, &5);
+    assert_eq!(ids(client.get_products_by_status(&ProductStatus::Delivered, &0, &10)), expect(&[1]));
+    assert_eq!(client.get_status_counts(), counts(1, 1, 1, 1));
+    assert_eq!(client.try_get_status(&5), Err(Ok(Error::ProductBurned)));
+}
+
+#[test]
+fn test_products_with_alerts() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register_contract(None, ChainLogisticsContract);
+    let client = ChainLogisticsContractClient::new(&env, &contract_id);
+
+    let owner = Address::generate(&env);
+    let origin = String::from_str(&env, "Norway");
+    let metadata = String::from_str(&env, "Salmon");
+    let fish = client.register_product(&owner, &origin, &metadata);
+    let other = client.register_product(&owner, &origin, &metadata);
+    let ids = |products: Vec<Product>| {
+        let mut ids = Vec::new(&env);
+        for p in products.iter() {
+            ids.push_back(p.id);
+        }
+        ids
+    };
+    let place = String::from_str(&env, "Bergen");
+    let note = String::from_str(&env, "");
+    assert_eq!(client.get_products_with_alerts(&0, &10).len(), 0);
+
+    env.ledger().with_mut(|li| li.timestamp = 1_000);
+    client.set_temperature_limits(&owner, &fish, &-200, &400);
+    client.record_temperature(&owner, &fish, &900, &1_000);
+    client.add_checkpoint(&owner, &fish, &CheckpointKind::Exception, &place, &note);
+    assert_eq!(client.get_alerts(&fish), ALERT_EXCURSION | ALERT_EXCEPTION);
+    assert_eq!(client.get_alerts(&other), 0);
+    assert_eq!(ids(client.get_products_with_alerts(&0, &10)), Vec::from_array(&env, [fish]));
+
+    // One alert cleared, one still raised: still listed
+    client.acknowledge_excursion(&owner, &fish);
+    assert_eq!(client.get_alerts(&fish), ALERT_EXCEPTION);
+    assert_eq!(ids(client.get_products_with_alerts(&0, &10)), Vec::from_array(&env, [fish]));
+
+    // The last alert cleared delists the product
+    client.clear_exception(&owner, &fish);
+    assert_eq!(client.get_alerts(&fish), 0);
+    assert_eq!(client.get_products_with_alerts(&0, &10).len(), 0);
+
+    // A dispute stays raised until every disputed checkpoint is corrected
+    let first = client.add_checkpoint(&owner, &other, &CheckpointKind::Departure, &place, &note);
+    let second = client.add_checkpoint(&owner, &other, &CheckpointKind::Arrival, &place, &note);
+    client.dispute_checkpoint(&owner, &other, &first, &String::from_str(&env, "Wrong port"));
+    client.dispute_checkpoint(&owner, &other, &second, &String::from_str(&env, "Wrong port"));
+    assert_eq!(client.get_alerts(&other), ALERT_DISPUTE);
+    client.append_correction(&owner, &other, &first, &String::from_str(&env, "Oslo"));
+    assert_eq!(ids(client.get_products_with_alerts(&0, &10)), Vec::from_array(&env, [other]));
+    client.append_correction(&owner, &other, &second, &String::from_str(&env, "Oslo"));
+    assert_eq!(client.get_alerts(&other), 0);
+    assert_eq!(client.get_products_with_alerts(&0, &10).len(), 0);
+}
+
+#[test]
+fn test_top_origins_board() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+    let contract_id = env.register_contract(None, ChainLogisticsContract);
+    let client = ChainLogisticsContractClient::new(&env, &contract_id);
+
+    let owner = Address::generate(&env);
+    let metadata = String::from_str(&env, "Metadata");
+    let names = [
+        "O00", "O01", "O02", "O03", "O04", "O05", "O06", "O07", "O08", "O09",
+        "O10", "O11", "O12", "O13", "O14", "O15", "O16", "O17", "O18", "O19",
+    ];
+    let register = |i: usize| {
+        client.register_product(&owner, &String::from_str(&env, names[i]), &metadata);
+    };
+    let board = |entries: &[(usize, u64)]| {
+        let mut board = Vec::new(&env);
+        for (i, count) in entries {
+            board.push_back((String::from_str(&env, names[*i]), *count));
+        }
+        board
+    };
+
+    for i in 0..20 {
+        register(i);
+    }
+    // All tied at one; the 16 seen first hold the board
+    let mut expected = [(0usize, 1u64); 16];
+    for (i, entry) in expected.iter_mut().enumerate() {
+        *entry = (i, 1);
+    }
+    assert_eq!(client.get_top_origins(), board(&expected));
+
+    // An origin off the board overtakes the last slot and goes to the top
+    register(19);
+    let mut expected = [(19usize, 2u64); 16];
+    for i in 0..15 {
+        expected[i + 1] = (i, 1);
+    }
+    assert_eq!(client.get_top_origins(), board(&expected));
+
+    // Tied at two, the origin seen first ranks higher
+    register(15);
+    let mut expected = [(15usize, 2u64); 16];
+    expected[1] = (19, 2);
+    for i in 0..14 {
+        expected[i + 2] = (i, 1);
+    }
+    assert_eq!(client.get_top_origins(), board(&expected));
+
+    // An origin already on the board climbs past the others
+    register(3);
+    register(3);
+    let expected = [
+        (3usize, 3u64), (15, 2), (19, 2), (0, 1), (1, 1), (2, 1), (4, 1), (5, 1),
+        (6, 1), (7, 1), (8, 1), (9, 1), (10, 1), (11, 1), (12, 1), (13, 1),
+    ];
+    assert_eq!(client.get_top_origins(), board(&expected));
+}
+
+#[test]
+fn test_pending_transfers_inbox() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register_contract(None, ChainLogisticsContract);
+    let client = ChainLogisticsContractClient::new(&env, &contract_id);
+
+    let owner = Address::generate(&env);
+    let recipient = Address::generate(&env);
+    let other = Address::generate(&env);
+    let origin = String::from_str(&env, "Laos");
+    let metadata = String::from_str(&env, "Metadata");
+    let first = client.register_product(&owner, &origin, &metadata);
+    let second = client.register_product(&owner, &origin, &metadata);
+    let third = client.register_product(&owner, &origin, &metadata);
+
+    env.ledger().with_mut(|li| li.timestamp = 1_000);
+    client.propose_transfer(&owner, &first, &recipient, &None);
+    client.propose_transfer(&owner, &second, &recipient, &Some(5_000));
+    client.propose_transfer(&owner, &third, &recipient, &Some(2_000));
+
+    let inbox = client.get_pending_transfers_for(&recipient, &0, &10);
+    assert_eq!(inbox.len(), 3);
+    assert_eq!(
+        inbox.get(1).unwrap(),
+        IncomingTransfer { product_id: second, from: owner.clone(), proposed_at: 1_000, expires_at: 5_000 }
+    );
+
+    // Expired proposals drop out of the inbox
+    env.ledger().with_mut(|li| li.timestamp = 2_000);
+    let inbox = client.get_pending_transfers_for(&recipient, &0, &10);
+    assert_eq!(inbox.len(), 2);
+    assert_eq!(inbox.get(0).unwrap().product_id, first);
+    assert_eq!(inbox.get(1).unwrap().product_id, second);
+
+    // Re-proposing to someone else moves the entry to their inbox
+    client.propose_transfer(&owner, &third, &other, &None);
+    assert_eq!(client.get_pending_transfers_for(&other, &0, &10).get(0).unwrap().product_id, third);
+
+    client.accept_transfer(&recipient, &first);
+    client.cancel_transfer(&owner, &second);
+    assert_eq!(client.get_pending_transfers_for(&recipient, &0, &10).len(), 0);
+    assert_eq!(client.get_pending_transfers_for(&other, &0, &10).len(), 1);
+}
+
+#[test]
+fn test_registration_event() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register_contract(None, ChainLogisticsContract);
+    let client = ChainLogisticsContractClient::new(&env, &contract_id);
+
+    let owner = Address::generate(&env);
+    let origin = String::from_str(&env, "Cuba");
+    env.ledger().with_mut(|li| li.timestamp = 4_200);
+    let id = client.register_product(&owner, &origin, &String::from_str(&env, "Cigars"));
+
+    let events = env.events().all();
+    assert_eq!(events.len(), 1);
+    let (emitter, topics, data) = events.get(0).unwrap();
+    assert_eq!(emitter, contract_id);
+    assert_eq!(
+        topics,
+        (Symbol::new(&env, events::PRODUCT), Symbol::new(&env, events::REGISTERED), owner.clone()).into_val(&env)
+    );
+    let data: (u64, (u64, String, u64)) = data.into_val(&env);
+    assert_eq!(data, (1, (id, origin, 4_200)));
+}
+
+#[test]
+fn test_transfer_events() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register_contract(None, ChainLogisticsContract);
+    let client = ChainLogisticsContractClient::new(&env, &contract_id);
+
+    let owner = Address::generate(&env);
+    let buyer = Address::generate(&env);
+    let origin = String::from_str(&env, "Fiji");
+    let metadata = String::from_str(&env, "Metadata");
+    for _ in 0..3 {
+        client.register_product(&owner, &origin, &metadata);
+    }
+    let transferred = |id: u64| (Symbol::new(&env, events::PRODUCT), Symbol::new(&env, events::TRANSFERRED), id).into_val(&env);
+
+    // Test events accumulate across calls, so each check looks past the
+    // events already seen
+    env.ledger().with_mut(|li| li.timestamp = 7_000);
+    let seen = env.events().all().len();
+    client.transfer_product(&owner, &1, &buyer);
+    let events = env.events().all().slice(seen..);
+    assert_eq!(events.len(), 1);
+    let (_, topics, data) = events.get(0).unwrap();
+    assert_eq!(topics, transferred(1));
+    let data: (u64, (Address, Address, u64)) = data.into_val(&env);
+    assert_eq!(data, (4, (owner.clone(), buyer.clone(), 7_000)));
+
+    // One event per product in a batch
+    let seen = env.events().all().len();
+    client.transfer_products(&owner, &Vec::from_array(&env, [2u64, 3]), &buyer);
+    let events = env.events().all().slice(seen..);
+    assert_eq!(events.len(), 2);
+    assert_eq!(events.get(0).unwrap().1, transferred(2));
+    assert_eq!(events.get(1).unwrap().1, transferred(3));
+
+    // A failed transfer publishes nothing
+    let seen = env.events().all().len();
+    assert!(client.try_transfer_product(&owner, &1, &buyer).is_err());
+    assert_eq!(env.events().all().len(), seen);
+}
+
+#[test]
+fn test_auth_events() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register_contract(None, ChainLogisticsContract);
+    let client = ChainLogisticsContractClient::new(&env, &contract_id);
+
+    let owner = Address::generate(&env);
+    let buyer = Address::generate(&env);
+    let updater = Address::generate(&env);
+    let viewer = Address::generate(&env);
+    let id = client.register_product(&owner, &String::from_str(&env, "Chile"), &String::from_str(&env, "Metadata"));
+    let auth = |id: u64| (Symbol::new(&env, events::PRODUCT), Symbol::new(&env, events::AUTH), id).into_val(&env);
+    let payload = |event: (Address, Vec<Val>, Val)| -> (Address, bool, Role) {
+        let (_seq, payload): (u64, (Address, bool, Role)) = event.2.into_val(&env);
+        payload
+    };
+
+    let seen = env.events().all().len();
+    client.add_authorized_actor(&owner, &id, &updater);
+    client.add_authorized_actor_with_role(&owner, &id, &viewer, &Role::Viewer);
+    let events = env.events().all().slice(seen..);
+    assert_eq!(events.len(), 2);
+    assert_eq!(events.get(0).unwrap().1, auth(id));
+    assert_eq!(payload(events.get(0).unwrap()), (updater.clone(), true, Role::Updater));
+    assert_eq!(payload(events.get(1).unwrap()), (viewer.clone(), true, Role::Viewer));
+
+    // A revocation names the role that was lost; removing a stranger is silent
+    let seen = env.events().all().len();
+    client.remove_authorized_actor(&owner, &id, &viewer);
+    client.remove_authorized_actor(&owner, &id, &buyer);
+    let events = env.events().all().slice(seen..);
+    assert_eq!(events.len(), 1);
+    assert_eq!(payload(events.get(0).unwrap()), (viewer.clone(), false, Role::Viewer));
+
+    // Global grants are announced against the owner
+    let seen = env.events().all().len();
+    client.add_global_actor(&owner, &viewer);
+    let events = env.events().all().slice(seen..);
+    assert_eq!(events.len(), 1);
+    assert_eq!(
+        events.get(0).unwrap().1,
+        (Symbol::new(&env, events::OWNER), Symbol::new(&env, events::AUTH), owner.clone()).into_val(&env)
+    );
+
+    // A transfer that clears grants revokes each one before the transfer event
+    client.set_clear_actors_on_transfer(&owner, &id, &true);
+    let seen = env.events().all().len();
+    client.transfer_product(&owner, &id, &buyer);
+    let events = env.events().all().slice(seen..);
+    assert_eq!(events.len(), 2);
+    assert_eq!(events.get(0).unwrap().1, auth(id));
+    assert_eq!(payload(events.get(0).unwrap()), (updater, false, Role::Updater));
+    assert_eq!(
+        events.get(1).unwrap().1,
+        (Symbol::new(&env, events::PRODUCT), Symbol::new(&env, events::TRANSFERRED), id).into_val(&env)
+    );
+}
+
+#[test]
+fn test_status_and_checkpoint_events() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register_contract(None, ChainLogisticsContract);
+    let client = ChainLogisticsContractClient::new(&env, &contract_id);
+
+    let owner = Address::generate(&env);
+    let id = client.register_product(&owner, &String::from_str(&env, "Peru"), &String::from_str(&env, "Metadata"));
+    let topics = |name: &str| (Symbol::new(&env, events::PRODUCT), Symbol::new(&env, name), id).into_val(&env);
+    let note = String::from_str(&env, "Loaded onto truck 7");
+
+    // First leg: one checkpoint event, then the move out of Registered
+    let lima = String::from_str(&env, "Lima");
+    let seen = env.events().all().len();
+    client.add_checkpoint(&owner, &id, &CheckpointKind::Pickup, &lima, &note);
+    let events = env.events().all().slice(seen..);
+    assert_eq!(events.len(), 2);
+    assert_eq!(events.get(0).unwrap().1, topics(events::CHECKPOINT));
+    let (_, data): (u64, (u32, CheckpointKind, String)) = events.get(0).unwrap().2.into_val(&env);
+    assert_eq!(data, (1, CheckpointKind::Pickup, lima));
+    assert_eq!(events.get(1).unwrap().1, topics(events::STATUS));
+    let (_, data): (u64, (ProductStatus, Option<ProductStatus>)) = events.get(1).unwrap().2.into_val(&env);
+    assert_eq!(data, (ProductStatus::Registered, Some(ProductStatus::InTransit)));
+
+    // Staying in transit writes no status, so only the checkpoint is published
+    let seen = env.events().all().len();
+    client.add_checkpoint(&owner, &id, &CheckpointKind::Departure, &String::from_str(&env, "Callao"), &note);
+    let events = env.events().all().slice(seen..);
+    assert_eq!(events.len(), 1);
+    assert_eq!(events.get(0).unwrap().1, topics(events::CHECKPOINT));
+
+    // Delivery without a checkpoint is a status change alone
+    let seen = env.events().all().len();
+    client.confirm_delivery(&owner, &id);
+    let events = env.events().all().slice(seen..);
+    assert_eq!(events.len(), 1);
+    let (_, data): (u64, (ProductStatus, Option<ProductStatus>)) = events.get(0).unwrap().2.into_val(&env);
+    assert_eq!(data, (ProductStatus::InTransit, Some(ProductStatus::Delivered)));
+}
+
+#[test]
+fn test_audit_log_orders_actions() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register_contract(None, ChainLogisticsContract);
+    let client = ChainLogisticsContractClient::new(&env, &contract_id);
+
+    let owner = Address::generate(&env);
+    let carrier = Address::generate(&env);
+    let buyer = Address::generate(&env);
+    let hauler = Address::generate(&env);
+    let id = client.register_product(&owner, &String::from_str(&env, "Kenya"), &String::from_str(&env, "Metadata"));
+
+    env.ledger().with_mut(|li| li.timestamp = 100);
+    client.add_authorized_actor(&owner, &id, &carrier);
+    env.ledger().with_mut(|li| li.timestamp = 150);
+    client.set_gtin(&owner, &id, &String::from_str(&env, "4006381333931"));
+    env.ledger().with_mut(|li| li.timestamp = 200);
+    let seq = client.add_checkpoint(
+        &carrier,
         &id,
-        &String::from_str(&env, "Organic Coffee Beans"),
+        &CheckpointKind::Pickup,
+        &String::from_str(&env, "Mombasa"),
         &String::from_str(&env, ""),
-        &String::from_str(&env, "Yirgacheffe, Ethiopia"),
-        &String::from_str(&env, "Coffee"),
-        &tags,
-        &certs,
-        &media,
-        &custom,
     );
+    env.ledger().with_mut(|li| li.timestamp = 300);
+    client.transfer_product(&owner, &id, &buyer);
+    env.ledger().with_mut(|li| li.timestamp = 400);
+    client.remove_authorized_actor(&buyer, &id, &carrier);
+    env.ledger().with_mut(|li| li.timestamp = 500);
+    client.set_custodian(&buyer, &id, &hauler);
+
+    assert_eq!(client.get_audit_count(&id), 7);
+    let log = client.get_audit_log(&id, &0, &10);
+    let expected = [
+        (symbol_short!("register"), owner.clone(), 0u64, 0u64),
+        (symbol_short!("grant"), owner.clone(), 100, 0),
+        (symbol_short!("gtin"), owner.clone(), 150, 0),
+        (symbol_short!("checkpt"), carrier.clone(), 200, seq as u64),
+        (symbol_short!("transfer"), owner.clone(), 300, 1),
+        (symbol_short!("revoke"), buyer.clone(), 400, 0),
+        (symbol_short!("custody"), buyer.clone(), 500, 0),
+    ];
+    assert_eq!(log.len(), expected.len() as u32);
+    for (i, (entry, (action, actor, timestamp, ref_id))) in log.iter().zip(expected).enumerate() {
+        let event_seq = i as u64 + 1;
+        assert_eq!(entry, AuditEntry { event_seq, action, actor, timestamp, ref_id });
+    }
+
+    // ref_id points into the specialized histories
+    assert_eq!(client.get_checkpoint(&id, &seq).location, String::from_str(&env, "Mombasa"));
+    assert_eq!(client.get_audit_log(&id, &4, &10).len(), 3);
+}
+
+#[test]
+fn test_daily_activity() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+    let contract_id = env.register_contract(None, ChainLogisticsContract);
+    let client = ChainLogisticsContractClient::new(&env, &contract_id);
+
+    let owner = Address::generate(&env);
+    let buyer = Address::generate(&env);
+    let origin = String::from_str(&env, "Ghana");
+    let metadata = String::from_str(&env, "Metadata");
+    let day = 86_400u64;
+
+    // Day 10: two registrations. Day 12: one registration and two transfers.
+    env.ledger().with_mut(|li| li.timestamp = 10 * day + 5);
+    let first = client.register_product(&owner, &origin, &metadata);
+    env.ledger().with_mut(|li| li.timestamp = 11 * day - 1);
+    client.register_product(&owner, &origin, &metadata);
+    env.ledger().with_mut(|li| li.timestamp = 12 * day);
+    let third = client.register_product(&owner, &origin, &metadata);
+    client.transfer_product(&owner, &first, &buyer);
+    client.transfer_product(&owner, &third, &buyer);
+
+    let series = client.get_daily_activity(&9, &13);
+    let expected = [(9, 0, 0), (10, 2, 0), (11, 0, 0), (12, 1, 2), (13, 0, 0)];
+    assert_eq!(series.len(), expected.len() as u32);
+    for (stats, (day, registrations, transfers)) in series.iter().zip(expected) {
+        assert_eq!(stats, DailyStats { day, registrations, transfers });
+    }
+
+    // A burned product still counts as registered that day
+    client.burn_product(&buyer, &first);
+    assert_eq!(client.get_daily_activity(&10, &10).get(0).unwrap().registrations, 2);
+
+    assert_eq!(client.get_daily_activity(&0, &91).len(), 92);
+    assert_eq!(client.try_get_daily_activity(&0, &92), Err(Ok(Error::InvalidInput)));
+    assert_eq!(client.try_get_daily_activity(&5, &4), Err(Ok(Error::InvalidInput)));
+}
+
+#[test]
+fn test_event_seq_counts_mutations() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register_contract(None, ChainLogisticsContract);
+    let client = ChainLogisticsContractClient::new(&env, &contract_id);
+
+    let owner = Address::generate(&env);
+    let carrier = Address::generate(&env);
+    let buyer = Address::generate(&env);
+    let origin = String::from_str(&env, "Togo");
+    let metadata = String::from_str(&env, "Metadata");
+    assert_eq!(client.get_event_seq(), 0);
+
+    let id = client.register_product(&owner, &origin, &metadata);
+    assert_eq!(client.get_event_seq(), 1);
+    client.add_authorized_actor(&owner, &id, &carrier);
+    assert_eq!(client.get_event_seq(), 2);
+
+    // A checkpoint that also moves the status is still one mutation, and
+    // both of its events carry its seq
+    let seen = env.events().all().len();
+    client.add_checkpoint(&carrier, &id, &CheckpointKind::Pickup, &String::from_str(&env, "Lome"), &String::from_str(&env, ""));
+    assert_eq!(client.get_event_seq(), 3);
+    let events = env.events().all().slice(seen..);
+    assert_eq!(events.len(), 2);
+    for (_, _, data) in events.iter() {
+        let (seq, _): (u64, Val) = data.into_val(&env);
+        assert_eq!(seq, 3);
+    }
+
+    // Reads and failed calls leave the counter alone
+    client.get_product(&id);
+    assert!(client.try_transfer_product(&buyer, &id, &owner).is_err());
+    assert_eq!(client.get_event_seq(), 3);
+
+    client.transfer_product(&owner, &id, &buyer);
+    assert_eq!(client.get_event_seq(), 4);
+    client.add_global_actor(&buyer, &carrier);
+    assert_eq!(client.get_event_seq(), 5);
+    client.burn_product(&buyer, &id);
+    assert_eq!(client.get_event_seq(), 6);
+
+    // The audit log holds every product mutation; the gap is the global grant
+    let mut seqs = Vec::new(&env);
+    for entry in client.get_audit_log(&id, &0, &10).iter() {
+        seqs.push_back(entry.event_seq);
+    }
+    assert_eq!(seqs, Vec::from_array(&env, [1u64, 2, 3, 4, 6]));
+
+    // Calls that touch no product count as well, once each
+    let admin = Address::generate(&env);
+    client.initialize(&admin, &String::from_str(&env, "ChainLogistics"));
+    assert_eq!(client.get_event_seq(), 7);
+    client.pause(&admin);
+    client.unpause(&admin);
+    assert_eq!(client.get_event_seq(), 9);
+    client.add_registrar(&admin, &owner);
+    client.set_invitations_required(&owner, &true);
+    assert_eq!(client.get_event_seq(), 11);
+    client.set_max_actors(&admin, &5);
+    assert_eq!(client.get_event_seq(), 12);
+
+    // A batch transfer is one mutation however many products it moves
+    let a = client.register_product(&owner, &origin, &metadata);
+    let b = client.register_product(&owner, &origin, &metadata);
+    assert_eq!(client.get_event_seq(), 14);
+    let seen = env.events().all().len();
+    client.transfer_products(&owner, &Vec::from_array(&env, [a, b]), &carrier);
+    assert_eq!(client.get_event_seq(), 15);
+    let events = env.events().all().slice(seen..);
+    assert_eq!(events.len(), 2);
+    for (_, _, data) in events.iter() {
+        let (seq, _): (u64, Val) = data.into_val(&env);
+        assert_eq!(seq, 15);
+    }
+    assert_eq!(client.get_audit_log(&a, &1, &1).get(0).unwrap().event_seq, 15);
+    assert_eq!(client.get_audit_log(&b, &1, &1).get(0).unwrap().event_seq, 15);
+}
+
+#[test]
+fn test_admin_transfer_drops_claim() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register_contract(None, ChainLogisticsContract);
+    let client = ChainLogisticsContractClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    let holder = Address::generate(&env);
+    let appointee = Address::generate(&env);
+    let finder = Address::generate(&env);
+    let buyer = Address::generate(&env);
+    client.initialize(&admin, &String::from_str(&env, "ChainLogistics"));
+    let id = client.register_product(&holder, &String::from_str(&env, "Malta"), &String::from_str(&env, "Lace"));
+
+    let secret = Bytes::from_slice(&env, b"pickup code 9120");
+    client.create_claim(&holder, &id, &env.crypto().sha256(&secret), &5_000);
+    client.admin_transfer(&admin, &id, &appointee, &String::from_str(&env, "Court order 2026-207"));
+    assert_eq!(client.get_claim(&id), None);
+
+    // The old owner's preimage no longer moves the product
+    let res = client.try_redeem_claim(&finder, &id, &secret);
+    match res {
+        Err(Ok(e)) => assert_eq!(e, Error::NoPendingTransfer),
+        _ => panic!("expected NoPendingTransfer"),
+    }
+    assert_eq!(client.get_product(&id).unwrap().owner, appointee);
+
+    // Nor does it keep the new owner locked out of transfers
+    client.transfer_product(&appointee, &id, &buyer);
+    assert_eq!(client.get_product(&id).unwrap().owner, buyer);
+}
+
+#[test]
+fn test_overdue_alert() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register_contract(None, ChainLogisticsContract);
+    let client = ChainLogisticsContractClient::new(&env, &contract_id);
+
+    let owner = Address::generate(&env);
+    let id = client.register_product(&owner, &String::from_str(&env, "Chad"), &String::from_str(&env, "Gum arabic"));
+    let hub = String::from_str(&env, "N'Djamena");
+    let note = String::from_str(&env, "");
+    env.ledger().with_mut(|li| li.timestamp = 100);
+    client.set_delivery_deadline(&owner, &id, &1_000);
+
+    // On time: nothing raised
+    client.add_checkpoint(&owner, &id, &CheckpointKind::Pickup, &hub, &note);
+    assert_eq!(client.get_alerts(&id), 0);
+
+    // Late checkpoints and ETA updates raise it
+    env.ledger().with_mut(|li| li.timestamp = 1_001);
+    client.add_checkpoint(&owner, &id, &CheckpointKind::Departure, &hub, &note);
+    assert_eq!(client.get_alerts(&id), ALERT_OVERDUE);
+    assert_eq!(client.get_products_with_alerts(&0, &10).len(), 1);
+
+    // A new deadline clears it until that one passes too
+    client.set_delivery_deadline(&owner, &id, &2_000);
+    assert_eq!(client.get_alerts(&id), 0);
+    env.ledger().with_mut(|li| li.timestamp = 2_500);
+    client.update_eta(&owner, &id, &3_000);
+    assert_eq!(client.get_alerts(&id), ALERT_OVERDUE);
+
+    // Delivery clears it for good
+    client.add_checkpoint(&owner, &id, &CheckpointKind::Delivery, &hub, &note);
+    assert_eq!(client.get_alerts(&id), 0);
+    assert_eq!(client.get_products_with_alerts(&0, &10).len(), 0);
+}
+
+#[test]
+fn test_blacklisted_owner_cannot_transfer() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.budget().reset_unlimited();
+    let contract_id = env.register_contract(None, ChainLogisticsContract);
+    let client = ChainLogisticsContractClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    let sanctioned = Address::generate(&env);
+    let seller = Address::generate(&env);
+    let buyer = Address::generate(&env);
+    let finder = Address::generate(&env);
+    client.initialize(&admin, &String::from_str(&env, "ChainLogistics"));
+    let origin = String::from_str(&env, "Belarus");
+    let meta = String::from_str(&env, "Potash");
+    let batched = client.register_product(&sanctioned, &origin, &meta);
+    let proposed = client.register_product(&sanctioned, &origin, &meta);
+    let scheduled = client.register_product(&sanctioned, &origin, &meta);
+    let claimed = client.register_product(&sanctioned, &origin, &meta);
+    let joint = client.register_product(&seller, &origin, &meta);
+
+    // Handovers set up before the blacklisting
+    client.propose_transfer(&sanctioned, &proposed, &buyer, &None);
+    client.schedule_transfer(&sanctioned, &scheduled, &buyer, &100);
+    let secret = Bytes::from_slice(&env, b"dock 4 release");
+    client.create_claim(&sanctioned, &claimed, &env.crypto().sha256(&secret), &5_000);
+    client.add_co_owner(&seller, &joint, &sanctioned);
+    client.set_co_owner_threshold(&seller, &joint, &2);
+
+    client.blacklist(&admin, &sanctioned);
+    env.ledger().with_mut(|li| li.timestamp = 200);
+
+    let results = [
+        client.try_transfer_products(&sanctioned, &Vec::from_array(&env, [batched]), &buyer).err(),
+        client.try_transfer_all(&sanctioned, &buyer, &0, &10).err(),
+        client.try_accept_transfer(&buyer, &proposed).err(),
+        client.try_execute_scheduled_transfer(&finder, &scheduled).err(),
+        client.try_redeem_claim(&finder, &claimed, &secret).err(),
+        client.try_transfer_jointly(&Vec::from_array(&env, [seller.clone(), sanctioned.clone()]), &joint, &buyer).err(),
+    ];
+    for res in results {
+        match res {
+            Some(Ok(e)) => assert_eq!(e, Error::AddressBlacklisted),
+            _ => panic!("expected AddressBlacklisted"),
+        }
+    }
+    for id in [batched, proposed, scheduled, claimed] {
+        assert_eq!(client.get_product(&id).unwrap().owner, sanctioned);
+    }
+    assert_eq!(client.get_product(&joint).unwrap().owner, seller);
+}
+
+#[test]
+fn test_merge_rejects_locked_source() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register_contract(None, ChainLogisticsContract);
+    let client = ChainLogisticsContractClient::new(&env, &contract_id);
+
+    let owner = Address::generate(&env);
+    let origin = String::from_str(&env, "Chile");
+    let meta = String::from_str(&env, "Copper cathode");
+    let into = client.register_product(&owner, &origin, &meta);
+    let claimed = client.register_product(&owner, &origin, &meta);
+    let renounced = client.register_product(&owner, &origin, &meta);
+    for id in [into, claimed, renounced] {
+        client.set_quantity(&owner, &id, &10, &symbol_short!("KG"));
+    }
+
+    let secret = Bytes::from_slice(&env, b"yard 2");
+    client.create_claim(&owner, &claimed, &env.crypto().sha256(&secret), &5_000);
+    let res = client.try_merge_products(&owner, &into, &claimed);
+    match res {
+        Err(Ok(e)) => assert_eq!(e, Error::ProductLocked),
+        _ => panic!("expected ProductLocked"),
+    }
+
+    client.renounce_ownership(&owner, &renounced);
+    let res = client.try_merge_products(&owner, &into, &renounced);
+    match res {
+        Err(Ok(e)) => assert_eq!(e, Error::ProductRenounced),
+        _ => panic!("expected ProductRenounced"),
+    }
+
+    assert_eq!(client.get_quantity(&into).unwrap().amount, 10);
+    assert!(client.get_product(&claimed).is_some());
+    assert!(client.get_product(&renounced).is_some());
+}
+
+#[test]
+fn test_reject_transfer_to_blacklisted_sender() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register_contract(None, ChainLogisticsContract);
+    let client = ChainLogisticsContractClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    let sender = Address::generate(&env);
+    let recipient = Address::generate(&env);
+    client.initialize(&admin, &String::from_str(&env, "ChainLogistics"));
+    let id = client.register_product(&sender, &String::from_str(&env, "Panama"), &String::from_str(&env, "Coffee"));
+    client.transfer_product_with_window(&sender, &id, &recipient, &1_000);
+
+    client.blacklist(&admin, &sender);
+    let res = client.try_reject_transfer(&recipient, &id, &String::from_str(&env, "Short weight"));
+    match res {
+        Err(Ok(e)) => assert_eq!(e, Error::AddressBlacklisted),
+        _ => panic!("expected AddressBlacklisted"),
+    }
+    assert_eq!(client.get_product(&id).unwrap().owner, recipient);
+}
+
+#[test]
+fn test_custodian_must_be_eligible() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register_contract(None, ChainLogisticsContract);
+    let client = ChainLogisticsContractClient::new(&env, &contract_id);
+
+    let admin = Address::generate(&env);
+    let shipper = Address::generate(&env);
+    let carrier = Address::generate(&env);
+    let rogue = Address::generate(&env);
+    client.initialize(&admin, &String::from_str(&env, "ChainLogistics"));
+    let id = client.register_product(&shipper, &String::from_str(&env, "Peru"), &String::from_str(&env, "Quinoa"));
+
+    client.blacklist(&admin, &rogue);
+    let res = client.try_set_custodian(&shipper, &id, &rogue);
+    match res {
+        Err(Ok(e)) => assert_eq!(e, Error::AddressBlacklisted),
+        _ => panic!("expected AddressBlacklisted"),
+    }
+    assert!(!client.is_authorized(&id, &rogue));
+
+    let secret = Bytes::from_slice(&env, b"gate 7");
+    client.create_claim(&shipper, &id, &env.crypto().sha256(&secret), &5_000);
+    let res = client.try_set_custodian(&shipper, &id, &carrier);
     match res {
-        Err(Ok(e)) => assert_eq!(e, Error::CustomFieldValueTooLong),
-        _ => panic!("expected CustomFieldValueTooLong"),
+        Err(Ok(e)) => assert_eq!(e, Error::ProductLocked),
+        _ => panic!("expected ProductLocked"),
     }
+    assert_eq!(client.get_custodian(&id), None);
 }
@@ -1,4 +1,4 @@
-use soroban_sdk::{contracttype, Address, String};
+use soroban_sdk::{contracttype, Address, BytesN, String, Symbol, Vec};
 
 #[contracttype]
 #[derive(Clone, Debug, Eq, PartialEq)]
@@ -9,57 +9,425 @@ pub struct Product {
     pub active: bool,
     pub metadata: String,
     pub created_at: u64,
+    pub recalled: bool,
+    /// Owner before the latest transfer and when it happened. Both are empty
+    /// until the first transfer; `previous_owner` holds at most one entry,
+    /// since the SDK cannot encode an `Option<Address>` field.
+    pub previous_owner: Vec<Address>,
+    pub transferred_at: Option<u64>,
 }
 
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ProductTemplate {
+    pub id: u64,
+    pub creator: Address,
+    pub origin: String,
+    pub metadata: String,
+    pub category: Symbol,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Quantity {
+    pub amount: u64,
+    pub unit: Symbol,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct MediaRef {
+    pub hash: BytesN<32>,
+    pub kind: Symbol,
+    pub added_by: Address,
+    pub added_at: u64,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ProductNote {
+    pub seq: u32,
+    pub author: Address,
+    pub text: String,
+    pub timestamp: u64,
+}
+
+#[contracttype]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum CheckpointKind {
+    Pickup,
+    Departure,
+    Arrival,
+    CustomsIn,
+    CustomsOut,
+    Delivery,
+    Exception,
+}
+
+/// Where a product stands in its journey, moved along by checkpoints and
+/// delivery confirmations.
+#[contracttype]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ProductStatus {
+    Registered,
+    InTransit,
+    InCustoms,
+    Delivered,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Checkpoint {
+    pub seq: u32,
+    pub kind: CheckpointKind,
+    pub actor: Address,
+    pub location: String,
+    pub note: String,
+    pub timestamp: u64,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CheckpointRef {
+    pub product_id: u64,
+    pub seq: u32,
+    pub location: String,
+    pub timestamp: u64,
+}
+
+/// A corrected location linked to the checkpoint it replaces in reads.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CheckpointCorrection {
+    pub seq: u32,
+    pub actor: Address,
+    pub location: String,
+    pub timestamp: u64,
+}
+
+/// One line of a product's audit log. `event_seq` is the seq of the call
+/// that made the change, as carried by its events; `ref_id` is the seq of
+/// the matching record (transfer, checkpoint, note, ...), or 0 for actions
+/// without one.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct AuditEntry {
+    pub event_seq: u64,
+    pub action: Symbol,
+    pub actor: Address,
+    pub timestamp: u64,
+    pub ref_id: u64,
+}
+
+/// A mutating call made by someone other than the owner.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ActivityEntry {
+    pub seq: u32,
+    pub actor: Address,
+    pub action: Symbol,
+    pub timestamp: u64,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct EtaRevision {
+    pub eta: u64,
+    pub actor: Address,
+    pub timestamp: u64,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TempReading {
+    pub seq: u32,
+    pub actor: Address,
+    pub temp_centi_c: i32,
+    pub timestamp: u64,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SensorReading {
+    pub kind: Symbol,
+    pub value: i64,
+    pub timestamp: u64,
+}
+
+/// Running stats for one sensor channel of a product.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ChannelStats {
+    pub count: u32,
+    pub min: i64,
+    pub max: i64,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TempStats {
+    pub count: u32,
+    pub min_centi_c: i32,
+    pub max_centi_c: i32,
+}
+
+/// Access levels an owner can grant. Each level includes the ones before it.
+#[contracttype]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, PartialOrd, Ord)]
+pub enum Role {
+    Viewer,
+    Updater,
+    Transferrer,
+}
+
+#[contracttype]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum CustodyRole {
+    Owner,
+    Custodian,
+}
+
+/// A registry event in the recent-activity feed. `actor` is the
+/// registering owner, the receiving owner or the owner who renounced.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ActivityItem {
+    pub seq: u64,
+    pub kind: Symbol,
+    pub product_id: u64,
+    pub actor: Address,
+    pub timestamp: u64,
+}
+
+/// One change of hands. The registration entry has `from` equal to `to`.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CustodyEntry {
+    pub seq: u32,
+    pub from: Address,
+    pub to: Address,
+    pub role: CustodyRole,
+    pub timestamp: u64,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ProofOfDelivery {
+    pub evidence_hash: BytesN<32>,
+    pub recipient: Address,
+    pub timestamp: u64,
+}
+
+/// A completed change of owner. Seq 0 is the registration, with `from`
+/// equal to `to`; `memo` is empty when the call carried none. Forced
+/// transfers carry the admin's reason as the memo.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TransferRecord {
+    pub seq: u32,
+    pub from: Address,
+    pub to: Address,
+    pub timestamp: u64,
+    pub memo: String,
+    pub forced: bool,
+}
+
+/// An open claim: whoever presents the preimage of `claim_hash` before
+/// `expires_at` becomes the owner.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TransferClaim {
+    pub claim_hash: BytesN<32>,
+    pub created_at: u64,
+    pub expires_at: u64,
+}
+
+/// A transfer the recipient may still reject until `final_at`.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TransferWindow {
+    pub from: Address,
+    pub to: Address,
+    pub final_at: u64,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ScheduledTransfer {
+    pub from: Address,
+    pub to: Address,
+    pub not_before: u64,
+}
+
+/// `expires_at` is 0 when the proposal does not expire.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PendingTransfer {
+    pub from: Address,
+    pub to: Address,
+    pub proposed_at: u64,
+    pub expires_at: u64,
+}
+
+/// A proposal waiting in the recipient's inbox; `expires_at` is 0 when it
+/// does not expire.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct IncomingTransfer {
+    pub product_id: u64,
+    pub from: Address,
+    pub proposed_at: u64,
+    pub expires_at: u64,
+}
+
+/// Outcome of one `transfer_all` chunk. Pass `next_start` as the next
+/// call's `start` to continue the migration.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct BulkTransferResult {
+    pub moved: u32,
+    pub skipped: u32,
+    pub next_start: u64,
+}
+
+/// One page of a product listing. `total` is the number of index slots
+/// behind the listing, and `next_start` is the `start` of the following page,
+/// or `None` once the listing is exhausted.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ProductPage {
+    pub items: Vec<Product>,
+    pub total: u64,
+    pub next_start: Option<u64>,
+}
+
+/// One page of a cursor listing. `cursor` is the last index slot examined;
+/// pass it back to continue after it. `has_more` is false once the listing's
+/// last slot has been examined.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CursorPage {
+    pub items: Vec<Product>,
+    pub cursor: u64,
+    pub has_more: bool,
+}
+
+/// Outcome of one `repair_indexes` chunk. Pass `next_start` as the next
+/// call's `start` to continue.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RepairReport {
+    pub checked: u32,
+    pub fixed: u32,
+    pub removed: u32,
+    pub next_start: u64,
+}
+
+/// Deployment-tunable limits, stored by the admin with `set_config`.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Config {
+    pub max_metadata_len: u32,
+    pub max_page_limit: u32,
+    pub max_actors: u32,
+    pub max_batch_size: u32,
+    /// When non-zero, product reads extend the TTL of what they touch to
+    /// this many ledgers.
+    pub read_ttl_extend_to: u32,
+    /// Registrations one address may make per window; 0 disables the limit.
+    pub max_registrations_per_window: u32,
+    pub registration_window_secs: u64,
+    /// Entries kept in the recent-activity feed; 0 stops recording it.
+    pub recent_activity_size: u32,
+}
+
+/// Fee charged per registration, paid in `token` to `recipient`.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RegistrationFee {
+    pub token: Address,
+    pub amount: i128,
+    pub recipient: Address,
+}
+
+/// A sponsor's promise to pay the fee for `remaining` more registrations.
+/// The sponsor must also approve this contract to spend the fee token.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Sponsorship {
+    pub sponsor: Address,
+    pub remaining: u32,
+}
+
+/// Everything a wallet or explorer needs to describe a deployment.
+/// `admin` and `fee` hold at most one entry and are empty when unset; the
+/// SDK cannot encode `Option` fields of address or struct type.
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ContractInfo {
+    pub name: String,
+    pub version: String,
+    pub data_version: u32,
+    pub admin: Vec<Address>,
+    pub paused: bool,
+    pub sunset: bool,
+    pub fee: Vec<RegistrationFee>,
+}
+
+/// Registry-wide counters, each kept up to date by the calls that change
+/// it. Counters added after launch start from zero on older deployments.
 #[contracttype]
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct ProductStats {
     pub total_products: u64,
     pub active_products: u64,
+    pub inactive_products: u64,
+    pub total_transfers: u64,
+    pub distinct_origins: u64,
+    pub total_owners: u64,
+    pub last_registration_at: Option<u64>,
+}
+
+/// Rollup for one address. `registered` counts products it registered,
+/// whoever holds them now; `owned` counts what it holds today.
+#[contracttype]
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct OwnerStats {
+    pub owned: u64,
+    pub registered: u64,
+    pub received: u64,
+    pub sent: u64,
+}
+
+/// Registrations and transfers on one ledger day (`timestamp / 86400`).
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DailyStats {
+    pub day: u64,
+    pub registrations: u64,
+    pub transfers: u64,
+}
+
+/// Rollup for one origin. `transferred` counts products that have left
+/// their first owner; origins never seen read as all zeros. Correcting a
+/// product's origin moves its counts to the new origin.
+#[contracttype]
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct OriginStats {
+    pub total_registered: u64,
+    pub active: u64,
+    pub transferred: u64,
+    pub last_registration_at: u64,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Certification {
+    pub seq: u32,
+    pub cert_type: Symbol,
+    pub certifier: Address,
+    pub issued_at: u64,
+    pub expires_at: u64,
+    pub evidence_hash: BytesN<32>,
+    pub revoked: bool,
 }
- use soroban_sdk::{contracttype, Address, BytesN, Map, String, Symbol, Vec};
- 
- #[contracttype]
- #[derive(Clone, Debug, Eq, PartialEq)]
- pub struct Origin {
-     pub location: String,
- }
- 
- #[contracttype]
- #[derive(Clone, Debug, Eq, PartialEq)]
- pub struct Product {
-     pub id: String,
-     pub name: String,
-     pub description: String,
-     pub origin: Origin,
-     pub owner: Address,
-     pub created_at: u64,
-     pub active: bool,
-     pub category: String,
-     pub tags: Vec<String>,
-     pub certifications: Vec<BytesN<32>>,
-     pub media_hashes: Vec<BytesN<32>>,
-     pub custom: Map<Symbol, String>,
- }
- 
- #[contracttype]
- #[derive(Clone, Debug, Eq, PartialEq)]
- pub struct TrackingEvent {
-     pub event_id: u64,
-     pub product_id: String,
-     pub actor: Address,
-     pub timestamp: u64,
-     pub event_type: Symbol,
-     pub data_hash: BytesN<32>,
-     pub note: String,
- }
- 
- #[contracttype]
- #[derive(Clone, Debug, Eq, PartialEq)]
- pub enum DataKey {
-     Product(String),
-     ProductEventIds(String),
-     Event(u64),
-     EventSeq,
-     Auth(String, Address),
- }
@@ -1,9 +1,71 @@
- use soroban_sdk::String;
- 
- pub fn non_empty(s: &String) -> bool {
-     s.len() > 0
- }
- 
- pub fn max_len(s: &String, max: u32) -> bool {
-     s.len() <= max
- }
+use soroban_sdk::String;
+
+use crate::error::Error;
+
+pub const MAX_ORIGIN_LEN: u32 = 64;
+pub const DEFAULT_MAX_METADATA_LEN: u32 = 1024;
+
+pub fn non_empty(s: &String) -> bool {
+    s.len() > 0
+}
+
+pub fn max_len(s: &String, max: u32) -> bool {
+    s.len() <= max
+}
+
+/// Origins must be 1 to `MAX_ORIGIN_LEN` bytes.
+pub fn validate_origin(origin: &String) -> Result<(), Error> {
+    if !non_empty(origin) || !max_len(origin, MAX_ORIGIN_LEN) {
+        return Err(Error::InvalidOrigin);
+    }
+    Ok(())
+}
+
+/// Metadata may be empty but no longer than `max` bytes.
+pub fn validate_metadata(metadata: &String, max: u32) -> Result<(), Error> {
+    if !max_len(metadata, max) {
+        return Err(Error::MetadataTooLong);
+    }
+    Ok(())
+}
+
+/// Copies an ASCII-digit string of exactly `N` characters into a buffer of
+/// digit values. Returns `None` for any other length or a non-digit byte.
+pub fn parse_digits<const N: usize>(s: &String) -> Option<[u8; N]> {
+    if s.len() as usize != N {
+        return None;
+    }
+    let mut digits = [0u8; N];
+    s.copy_into_slice(&mut digits);
+    for d in digits.iter_mut() {
+        if !d.is_ascii_digit() {
+            return None;
+        }
+        *d -= b'0';
+    }
+    Some(digits)
+}
+
+/// GS1 mod-10 check: weights alternate 3,1,3,... from the digit left of the
+/// check digit, and the check digit brings the weighted sum to a multiple of 10.
+fn gs1_check_digit_valid(digits: &[u8]) -> bool {
+    let (body, check) = digits.split_at(digits.len() - 1);
+    let sum: u32 = body
+        .iter()
+        .rev()
+        .enumerate()
+        .map(|(i, d)| *d as u32 * if i % 2 == 0 { 3 } else { 1 })
+        .sum();
+    (10 - sum % 10) % 10 == check[0] as u32
+}
+
+/// Accepts GTIN-8, GTIN-12, GTIN-13 and GTIN-14 with a valid check digit.
+pub fn is_valid_gtin(gtin: &String) -> bool {
+    match gtin.len() {
+        8 => parse_digits::<8>(gtin).is_some_and(|d| gs1_check_digit_valid(&d)),
+        12 => parse_digits::<12>(gtin).is_some_and(|d| gs1_check_digit_valid(&d)),
+        13 => parse_digits::<13>(gtin).is_some_and(|d| gs1_check_digit_valid(&d)),
+        14 => parse_digits::<14>(gtin).is_some_and(|d| gs1_check_digit_valid(&d)),
+        _ => false,
+    }
+}